@@ -16,7 +16,8 @@ use eth_log::options::RunOptions as LogOptions;
 use proxy::http;
 use proxy::options::ServerOptions;
 use signer_proxy::options::SignerProxyOptions;
-use signer::options::SignerOptions;
+use signer::options::{SignerOptions,PassphraseOptions};
+use signer::keystore::{KeyFile,Kdf};
 use std::net::SocketAddr;
 use structopt::StructOpt;
 use log::LevelFilter;
@@ -28,6 +29,7 @@ const MOD_NAME: &str = module_path!();
 wrap_errs!(
     Proxy => proxy::Error,
     Signer => signer::Error,
+    Audit => signer::audit::Error,
     EthLog => eth_log::Error,
 );
 
@@ -79,9 +81,25 @@ pub enum Cmd {
         #[structopt(flatten)]
         signer_options: SignerOptions,
     },
-    /// Generate a random seck256k1 secret key (written to stdout)
+    /// Generate a random seck256k1 secret key (written to stdout, or to an encrypted keystore)
     #[structopt(name = "keygen")]
-    KeyGen {},
+    KeyGen {
+        /// Write an encrypted (v3) keystore to path instead of printing the raw key
+        #[structopt(name = "path", long = "keystore")]
+        keystore: Option<String>,
+        /// Key derivation function for keystore output (`scrypt` or `pbkdf2`)
+        #[structopt(name = "kdf", long = "kdf", default_value = "scrypt")]
+        kdf: Kdf,
+        #[structopt(flatten)]
+        passphrase: PassphraseOptions,
+    },
+    /// Verify the hash-chain of a signer audit log
+    #[structopt(name = "verify-audit-log")]
+    VerifyAuditLog {
+        /// Path to audit log
+        #[structopt(name = "path")]
+        path: String,
+    },
     /// Stream EVM event logs
     #[structopt(name = "stream-logs")]
     StreamLogs {
@@ -139,10 +157,11 @@ fn run(cmd: Cmd) -> Result<(),Error> {
         },
         Cmd::SignerProxy { srv_addr, server_options, signer_options } => {
             let incoming = proxy::bind_with_options(&srv_addr,&server_options)?;
+            let auth = server_options.load_auth()?;
             let signer_setup = signer_proxy::spawn_local(&signer_options)?;
             info!("Configured to server signer-proxy at {}",srv_addr);
             let work = signer_setup.map(move |request_handler| {
-                let server = http::serve_json(incoming, move |request| {
+                let server = http::serve_json_with_context(incoming, auth, move |(_context,request): (http::Context,_)| {
                     request_handler.handle_request(request).then(|rslt| {
                         Ok(rslt.map_err(|e| e.to_string()))
                     })
@@ -154,17 +173,35 @@ fn run(cmd: Cmd) -> Result<(),Error> {
         },
         Cmd::RunSigner { srv_addr, server_options, signer_options } => {
             let incoming = proxy::bind_with_options(&srv_addr,&server_options)?;
+            let auth = server_options.load_auth()?;
             let signer_instance = signer::Signer::from_options(&signer_options)?;
             info!("Configured to serve {} with signer {}",srv_addr,signer_instance.address());
-            let server = http::serve_json(incoming, move |request| {
-                let response = signer_instance.serve(request)
-                    .map_err(|e| e.to_string());
-                Ok(response)
+            let server = http::serve_json_with_context(incoming, auth, move |(context,request): (http::Context,_)| {
+                signer_instance.serve_async(context.peer,request).then(|rslt| {
+                    Ok::<_,()>(rslt.map_err(|e| e.to_string()))
+                })
             }).map_err(|e| error!("Server task failed with `{}`",e));
             
             tokio::run(server);
         },
-        Cmd::KeyGen {} => { println!("{:?}",signer::crypto::keygen()); },
+        Cmd::KeyGen { keystore, kdf, passphrase } => {
+            let secret = signer::crypto::keygen();
+            if let Some(path) = keystore {
+                let passphrase = passphrase.load(true)?;
+                let keyfile = KeyFile::encrypt(&secret,passphrase.as_bytes(),kdf)?;
+                keyfile.save(&path)?;
+                let address = signer::crypto::Signer::new(secret)?.address();
+                info!("Wrote keystore for {} to {}",address,path);
+                println!("{}",address);
+            } else {
+                println!("{:?}",secret);
+            }
+        },
+        Cmd::VerifyAuditLog { path } => {
+            let head = signer::audit::verify(&path)?;
+            info!("Verified {} records in {}",head.count,path);
+            println!("{}",head.hash);
+        },
         Cmd::StreamLogs { log_options, server_options } => {
             eth_log::run_with_servers(log_options,server_options)?;
         }
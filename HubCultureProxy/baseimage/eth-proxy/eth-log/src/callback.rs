@@ -65,12 +65,17 @@ impl<T> LogCallback<T> where T: Borrow<Tera> {
                 future::Either::A(send_request)
             },
             Endpoint::Stdout => {
-                let push_line = self.stdout.push_line(templated).map_err(|_| {
-                    // FIXME: While highly unlikely, the failure of the stdout handle
-                    // is permanent (unlike http callbacks, which may fail intermittently).
-                    // This condition shuold be (but currently is not) treated differently
-                    // than a simple http callback failure.
-                    Error::message("stdout handle failed unexpectedly")
+                let push_line = self.stdout.push_line(templated).map_err(|err| {
+                    if err.is_overloaded() {
+                        let msg = format!("stdout overloaded ({} lines pending)",self.stdout.depth());
+                        Error::message(msg)
+                    } else {
+                        // FIXME: While highly unlikely, the failure of the stdout handle
+                        // is permanent (unlike http callbacks, which may fail intermittently).
+                        // This condition shuold be (but currently is not) treated differently
+                        // than a simple http callback failure.
+                        Error::message("stdout handle failed unexpectedly")
+                    }
                 });
                 future::Either::B(push_line.into_future())
             },
@@ -0,0 +1,61 @@
+//! Custom template filters.
+//!
+//! Integers appear in template data in their hex-encoded rpc form; these
+//! filters allow templates to render them in human-readable decimal:
+//!
+//! ```text
+//! {{ event.value | decimal }}            => 1000000000000000000
+//! {{ event.value | units }}              => 1
+//! {{ event.value | units(unit="gwei") }} => 1000000000
+//! {{ event.value | units(unit=6) }}      => 1000000000000
+//! ```
+//!
+use ethrpc::util::units::{self,Unit};
+use ethrpc::types::U256;
+use tera::{self,Tera,Value};
+use std::collections::HashMap;
+
+
+/// Register all custom filters with `tera`.
+///
+pub fn register(tera: &mut Tera) {
+    tera.register_filter("decimal",decimal);
+    tera.register_filter("units",scale_units);
+}
+
+
+/// Render an integer as a decimal string.
+///
+fn decimal(value: Value, _: HashMap<String,Value>) -> tera::Result<Value> {
+    let num = uint_arg(&value)?;
+    Ok(Value::String(units::to_decimal(&num)))
+}
+
+
+/// Render an integer as a decimal string scaled by `unit` (default: ether).
+///
+fn scale_units(value: Value, args: HashMap<String,Value>) -> tera::Result<Value> {
+    let num = uint_arg(&value)?;
+    let unit = match args.get("unit") {
+        Some(Value::String(name)) => name.parse().map_err(|e| format!("invalid unit `{}`: {}",name,e))?,
+        Some(Value::Number(decimals)) => {
+            decimals.as_u64().filter(|d| *d <= u8::max_value() as u64)
+                .map(|d| Unit::Decimals(d as u8))
+                .ok_or_else(|| format!("invalid decimals `{}`",decimals))?
+        },
+        Some(other) => { return Err(format!("invalid unit `{}`",other).into()); },
+        None => Unit::Ether,
+    };
+    Ok(Value::String(units::format_units(&num,unit)))
+}
+
+
+/// Interpret a template value as a 256-bit integer.
+///
+fn uint_arg(value: &Value) -> tera::Result<U256> {
+    match value {
+        Value::String(s) => units::parse_uint(s).map_err(|e| format!("invalid integer `{}`: {}",s,e).into()),
+        Value::Number(n) => n.as_u64().map(U256::from).ok_or_else(|| format!("invalid integer `{}`",n).into()),
+        other => Err(format!("expected integer, got `{}`",other).into()),
+    }
+}
@@ -20,6 +20,8 @@ pub mod options;
 pub mod config;
 pub mod util;
 pub mod events;
+pub mod filters;
+pub mod start;
 mod error;
 
 
@@ -38,6 +40,7 @@ use std::sync::Arc;
 
 pub fn run_with_servers(opt: RunOptions, srv: ServerOptions) -> Result<(),Error> {
     let config = opt.load_config()?;
+    let verify = opt.receipt_verification()?;
     let templates = opt.load_templates()?;
     for (_,event) in config.iter_events() {
         if !templates.contains_key(event.template()) {
@@ -46,6 +49,7 @@ pub fn run_with_servers(opt: RunOptions, srv: ServerOptions) -> Result<(),Error>
         }
     }
     let mut tera = Tera::default();
+    filters::register(&mut tera);
     tera.add_raw_templates(
         templates.iter().map(|(name,data)| {
             (name.as_str(),data.as_str())
@@ -59,8 +63,11 @@ pub fn run_with_servers(opt: RunOptions, srv: ServerOptions) -> Result<(),Error>
         proxy::bind_with_options(&server.address,&srv)
     }).collect::<Result<_,_>>()?;
     
-    let work = future::lazy(move || {
-        let stdout = util::spawn_stdout();
+    let resolve_start = opt.start_block.resolve(opt.node_addr.clone()).map_err(|e| {
+        error!("Unable to resolve start block: {}",e);
+    });
+    let work = resolve_start.and_then(move |start_block| {
+        let stdout = util::spawn_stdout(opt.stdout_capacity.get());
         let mut jobs = Vec::new();
         // construct all callback jobs
         for (index,callback) in config.iter_callbacks().enumerate() {
@@ -69,7 +76,9 @@ pub fn run_with_servers(opt: RunOptions, srv: ServerOptions) -> Result<(),Error>
             let filter = callback.filter();
             info!("Configuring callback {} ({})",index,callback.endpoint());
             debug!("{:?}",filter);
-            let logs = util::stream_logs_with_retry(opt.node_addr.clone(),opt.start_block,filter,opt.lag_by);
+            let logs = util::stream_logs_with_retry(opt.node_addr.clone(),opt.rpc_capacity.get(),start_block,filter,opt.lag_by);
+            let logs = util::gate_on_readiness(opt.node_addr.clone(),opt.max_head_age(),logs);
+            let logs = util::verify_inclusion(verify.clone(),logs);
             let work = logs.from_err::<Error>().for_each(move |(_blk,logs)| {
                 for log in logs.iter() {
                     let work = log_callback.handle_log(&log).map_err(move |e| {
@@ -86,7 +95,7 @@ pub fn run_with_servers(opt: RunOptions, srv: ServerOptions) -> Result<(),Error>
         for (incoming,server_config) in server_conns.into_iter().zip(config.iter_servers()) {
             let event_server = EventServer::spawn_now(
                 opt.node_addr.clone(),
-                opt.start_block,
+                start_block,
                 server_config.origin.to_owned(),
                 tera.clone(),
                 server_config.events.to_owned()
@@ -113,6 +122,7 @@ pub fn run_with_servers(opt: RunOptions, srv: ServerOptions) -> Result<(),Error>
 
 pub fn run(opt: RunOptions) -> Result<(),Error> {
     let config = opt.load_config()?;
+    let verify = opt.receipt_verification()?;
     let templates = opt.load_templates()?;
     for (_,event) in config.iter_events() {
         if !templates.contains_key(event.template()) {
@@ -121,6 +131,7 @@ pub fn run(opt: RunOptions) -> Result<(),Error> {
         }
     }
     let mut tera = Tera::default();
+    filters::register(&mut tera);
     tera.add_raw_templates(
         templates.iter().map(|(name,data)| {
             (name.as_str(),data.as_str())
@@ -128,8 +139,11 @@ pub fn run(opt: RunOptions) -> Result<(),Error> {
         )?;
     let tera = Arc::new(tera);
     let client = http::client()?;
-    let work = future::lazy(move || {
-        let stdout = util::spawn_stdout();
+    let resolve_start = opt.start_block.resolve(opt.node_addr.clone()).map_err(|e| {
+        error!("Unable to resolve start block: {}",e);
+    });
+    let work = resolve_start.and_then(move |start_block| {
+        let stdout = util::spawn_stdout(opt.stdout_capacity.get());
         let mut jobs = Vec::new();
         for (index,callback) in config.iter_callbacks().enumerate() {
             let (tera,stdout,client) = (tera.clone(),stdout.clone(),client.clone());
@@ -137,7 +151,9 @@ pub fn run(opt: RunOptions) -> Result<(),Error> {
             let filter = callback.filter();
             info!("Configuring callback {} ({})",index,callback.endpoint());
             debug!("{:?}",filter);
-            let logs = util::stream_logs_with_retry(opt.node_addr.clone(),opt.start_block,filter,opt.lag_by);
+            let logs = util::stream_logs_with_retry(opt.node_addr.clone(),opt.rpc_capacity.get(),start_block,filter,opt.lag_by);
+            let logs = util::gate_on_readiness(opt.node_addr.clone(),opt.max_head_age(),logs);
+            let logs = util::verify_inclusion(verify.clone(),logs);
             let work = logs.from_err::<Error>().for_each(move |(_blk,logs)| {
                 for log in logs.iter() {
                     let work = log_callback.handle_log(&log).map_err(move |e| {
@@ -1,11 +1,13 @@
-use ethrpc::types::U256;
 use ethrpc::Url;
 use config::{ConfigFile,Config};
+use start::StartBlock;
 use error::Error;
 use serde::de::DeserializeOwned;
 use ignore::WalkBuilder;
 use std::collections::HashMap;
+use std::num::NonZeroUsize;
 use std::path::Path;
+use std::time::Duration;
 use std::fs;
 use toml;
 
@@ -22,12 +24,28 @@ pub struct RunOptions {
     /// Address of ethereum node
     #[structopt(name = "node-url",long="node-addr",default_value="ws://127.0.0.1:8546")]
     pub node_addr: Url,
-    /// Block number to start stream from
-    #[structopt(name = "block-number",long="start-block",default_value="0x0")]
-    pub start_block: U256, 
+    /// Block to start stream from (number, `latest-N`, or RFC 3339 timestamp)
+    #[structopt(name = "block",long="start-block",default_value="0x0")]
+    pub start_block: StartBlock,
     /// Number of blocks to lag by
     #[structopt(name = "block-count",long="lag-by",default_value="3")]
     pub lag_by: u8,
+    /// Pause callbacks while the node's latest block is older than this (seconds)
+    #[structopt(name = "seconds",long="max-head-age",default_value="120")]
+    pub max_head_age: u64,
+    /// Only fire callbacks for logs proven included via the block's receipts trie
+    #[structopt(long="verify-receipts")]
+    pub verify_receipts: bool,
+    /// Node from which block headers are loaded when verifying receipts
+    /// (required by `verify-receipts`)
+    #[structopt(name = "header-url",long="trusted-node")]
+    pub trusted_node: Option<Url>,
+    /// Maximum number of lines buffered for stdout
+    #[structopt(name = "line-count",long="stdout-capacity",default_value="1024")]
+    pub stdout_capacity: NonZeroUsize,
+    /// Maximum number of requests buffered per node connection
+    #[structopt(name = "request-count",long="rpc-capacity",default_value="1024")]
+    pub rpc_capacity: NonZeroUsize,
 }
 
 
@@ -42,6 +60,29 @@ impl RunOptions {
     pub fn load_templates(&self) -> Result<HashMap<String,String>,Error> {
         load_dir(&self.template_dir).collect()
     }
+
+    pub fn max_head_age(&self) -> Duration {
+        Duration::from_secs(self.max_head_age)
+    }
+
+    /// Nodes from which receipts & trusted headers are loaded, if receipt
+    /// verification is enabled.
+    ///
+    /// Receipts proven against headers from the node which served them prove
+    /// nothing, so verification fails unless a distinct trusted node is given.
+    ///
+    pub fn receipt_verification(&self) -> Result<Option<(Url,Url)>,Error> {
+        if !self.verify_receipts {
+            return Ok(None);
+        }
+        match self.trusted_node.as_ref() {
+            Some(trusted) if *trusted != self.node_addr => {
+                Ok(Some((self.node_addr.clone(),trusted.clone())))
+            },
+            Some(_) => Err(Error::message("`trusted-node` must differ from `node-addr`")),
+            None => Err(Error::message("`verify-receipts` requires `trusted-node`")),
+        }
+    }
 }
 
 
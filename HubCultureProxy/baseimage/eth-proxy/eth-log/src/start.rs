@@ -0,0 +1,388 @@
+//! Resolution of log-stream starting points.
+//!
+use ethrpc::types::U256;
+use ethrpc::util::bufmath;
+use ethrpc::{self,Url};
+use error::Error;
+use tokio::prelude::*;
+use std::str::FromStr;
+use std::{fmt,error};
+
+
+/// Block from which to begin streaming logs.
+///
+/// Accepts an explicit block number, an offset from the latest block, or an
+/// RFC 3339 timestamp (resolved to the first block mined at or after that
+/// time).
+///
+/// ```
+/// # extern crate eth_log;
+/// # use eth_log::start::StartBlock;
+/// # fn main() {
+/// let latest: StartBlock = "latest-5000".parse().unwrap();
+/// assert_eq!(latest,StartBlock::Latest(5000));
+///
+/// let time: StartBlock = "2018-10-02T14:00:00Z".parse().unwrap();
+/// assert_eq!(time,StartBlock::Time(1538488800));
+///
+/// let offset: StartBlock = "2018-10-02T16:00:00.5+02:00".parse().unwrap();
+/// assert_eq!(offset,time);
+///
+/// assert!("latest-".parse::<StartBlock>().is_err());
+/// assert!("latest-latest-5".parse::<StartBlock>().is_err());
+/// assert!("2018-13-02T14:00:00Z".parse::<StartBlock>().is_err());
+/// # }
+/// ```
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum StartBlock {
+    /// Specific block number
+    Number(U256),
+    /// Some number of blocks prior to the latest block
+    Latest(u64),
+    /// Unix timestamp (seconds)
+    Time(u64),
+}
+
+
+impl StartBlock {
+
+    /// Resolve to a concrete block number.
+    ///
+    /// A timestamp which is later than the latest block resolves to the
+    /// next block to be mined.
+    ///
+    pub fn resolve(&self, node: Url) -> impl Future<Item=U256,Error=Error> {
+        let spec = *self;
+        let work = match spec {
+            StartBlock::Number(num) => { return future::Either::A(future::ok(num)); },
+            StartBlock::Latest(offset) => {
+                let work = ethrpc::connect(node).and_then(move |api| {
+                    api.eth().block_number().map(move |latest| blocks_before(latest,offset))
+                });
+                future::Either::A(work)
+            },
+            StartBlock::Time(timestamp) => {
+                let work = ethrpc::connect(node).and_then(move |api| {
+                    let find_block = api.util().find_block_by_time(timestamp);
+                    find_block.and_then(move |found| {
+                        match found {
+                            Some(num) => future::Either::A(future::ok(num)),
+                            None => {
+                                let next = api.eth().block_number().map(|latest| {
+                                    let mut next = latest;
+                                    let _ = bufmath::add(&mut next,&U256::from(1u64));
+                                    next
+                                });
+                                future::Either::B(next)
+                            },
+                        }
+                    })
+                });
+                future::Either::B(work)
+            },
+        };
+        let work = work.from_err().map(move |start| {
+            info!("Resolved start block `{}` to {}",spec,start);
+            start
+        });
+        future::Either::B(work)
+    }
+}
+
+
+impl Default for StartBlock {
+
+    fn default() -> Self { StartBlock::Number(U256::from(0u64)) }
+}
+
+
+impl fmt::Display for StartBlock {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StartBlock::Number(num) => num.fmt(f),
+            StartBlock::Latest(offset) => write!(f,"latest-{}",offset),
+            StartBlock::Time(timestamp) => write!(f,"unix time {}",timestamp),
+        }
+    }
+}
+
+
+impl FromStr for StartBlock {
+
+    type Err = ParseStartError;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        if s == "latest" {
+            Ok(StartBlock::Latest(0))
+        } else if s.starts_with("latest-") {
+            let offset = s["latest-".len()..].parse()
+                .map_err(|_| ParseStartError)?;
+            Ok(StartBlock::Latest(offset))
+        } else if s.contains(':') {
+            let timestamp = parse_rfc3339(s).ok_or(ParseStartError)?;
+            Ok(StartBlock::Time(timestamp))
+        } else {
+            let num = s.parse().map_err(|_| ParseStartError)?;
+            Ok(StartBlock::Number(num))
+        }
+    }
+}
+
+
+#[derive(Debug,Copy,Clone)]
+pub struct ParseStartError;
+
+
+impl ParseStartError {
+
+    fn as_str(&self) -> &str { "expected block number, `latest-N`, or RFC 3339 timestamp" }
+}
+
+
+impl fmt::Display for ParseStartError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+
+impl error::Error for ParseStartError {
+
+    fn description(&self) -> &str { self.as_str() }
+}
+
+
+/// Block `offset` blocks prior to `latest`, or the genesis block if `offset`
+/// exceeds the chain's height.
+///
+fn blocks_before(latest: U256, offset: u64) -> U256 {
+    let mut start = latest;
+    if bufmath::sub(&mut start,&U256::from(offset)) {
+        start = U256::from(0u64);
+    }
+    start
+}
+
+
+/// Parse an RFC 3339 timestamp (e.g. `2018-10-02T14:00:00Z`) into seconds since
+/// the unix epoch.  Fractional seconds are discarded.
+///
+fn parse_rfc3339(s: &str) -> Option<u64> {
+    let bytes = s.as_bytes();
+    if bytes.len() < 20 || !s.is_ascii() {
+        return None;
+    }
+    let separators = [(4,b'-'),(7,b'-'),(13,b':'),(16,b':')];
+    if separators.iter().any(|(idx,sep)| bytes[*idx] != *sep) {
+        return None;
+    }
+    if !(bytes[10] == b'T' || bytes[10] == b't' || bytes[10] == b' ') {
+        return None;
+    }
+    let year = parse_digits(&s[0..4])? as i64;
+    let month = parse_digits(&s[5..7])?;
+    let day = parse_digits(&s[8..10])?;
+    let hour = parse_digits(&s[11..13])?;
+    let minute = parse_digits(&s[14..16])?;
+    // leap seconds are folded into the preceding second
+    let second = parse_digits(&s[17..19])?.min(59);
+    if month < 1 || month > 12 || day < 1 || day > days_in_month(year,month) {
+        return None;
+    }
+    if hour > 23 || minute > 59 {
+        return None;
+    }
+    let mut rest = &s[19..];
+    if rest.starts_with('.') {
+        let digits = rest[1..].bytes().take_while(|b| b.is_ascii_digit()).count();
+        if digits == 0 {
+            return None;
+        }
+        rest = &rest[1 + digits..];
+    }
+    let offset = match rest {
+        "Z" | "z" => 0,
+        other if other.len() == 6 && other.as_bytes()[3] == b':' => {
+            let hours = parse_digits(&other[1..3])?;
+            let minutes = parse_digits(&other[4..6])?;
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = (hours * 3600 + minutes * 60) as i64;
+            match other.as_bytes()[0] {
+                b'+' => offset,
+                b'-' => -offset,
+                _ => { return None; },
+            }
+        },
+        _ => { return None; },
+    };
+    let days = days_from_civil(year,month,day);
+    let local = days * 86400 + (hour * 3600 + minute * 60 + second) as i64;
+    let utc = local - offset;
+    if utc < 0 { None } else { Some(utc as u64) }
+}
+
+
+fn parse_digits(s: &str) -> Option<u32> {
+    if s.bytes().all(|b| b.is_ascii_digit()) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+
+/// Number of days between the unix epoch and the given date (proleptic gregorian).
+///
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+
+#[cfg(test)]
+mod test {
+    use start::{StartBlock,parse_rfc3339,days_from_civil,blocks_before};
+    use ethrpc::types::U256;
+
+
+    #[test]
+    fn civil_days() {
+        assert_eq!(days_from_civil(1970,1,1),0);
+        assert_eq!(days_from_civil(1969,12,31),-1);
+        assert_eq!(days_from_civil(1970,3,1),59);
+        // 2000 is a leap year (divisible by 400), 1900 & 2100 are not
+        assert_eq!(days_from_civil(2000,2,29),11016);
+        assert_eq!(days_from_civil(2000,3,1),11017);
+        assert_eq!(days_from_civil(1900,3,1) - days_from_civil(1900,2,28),1);
+        assert_eq!(days_from_civil(2100,3,1) - days_from_civil(2100,2,28),1);
+        assert_eq!(days_from_civil(2024,3,1) - days_from_civil(2024,2,28),2);
+        assert_eq!(days_from_civil(1600,3,1),-135080);
+        assert_eq!(days_from_civil(1,1,1),-719162);
+        assert_eq!(days_from_civil(9999,12,31),2932896);
+    }
+
+
+    #[test]
+    fn rfc3339() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"),Some(0));
+        assert_eq!(parse_rfc3339("2018-10-02T14:00:00Z"),Some(1538488800));
+        // leap days
+        assert_eq!(parse_rfc3339("2000-02-29T00:00:00Z"),Some(951782400));
+        assert_eq!(parse_rfc3339("2024-02-29T12:00:00Z"),Some(1709208000));
+        // leap seconds are folded into the preceding second
+        assert_eq!(parse_rfc3339("2016-12-31T23:59:60Z"),Some(1483228799));
+        // numeric offsets, including those which cross a day boundary
+        assert_eq!(parse_rfc3339("2018-10-02T14:00:00+00:00"),Some(1538488800));
+        assert_eq!(parse_rfc3339("2018-10-02T14:00:00-00:00"),Some(1538488800));
+        assert_eq!(parse_rfc3339("2018-10-02T19:30:00+05:30"),Some(1538488800));
+        assert_eq!(parse_rfc3339("2018-10-02T07:00:00-07:00"),Some(1538488800));
+        assert_eq!(parse_rfc3339("2018-10-03T13:45:00+23:45"),Some(1538488800));
+        assert_eq!(parse_rfc3339("2018-10-02T13:00:00-01:00"),Some(1538488800));
+        assert_eq!(parse_rfc3339("2018-10-01T22:00:00-16:00"),Some(1538488800));
+        // fractional seconds are discarded
+        assert_eq!(parse_rfc3339("2018-10-02T14:00:00.5Z"),Some(1538488800));
+        assert_eq!(parse_rfc3339("2018-10-02T14:00:00.999999999Z"),Some(1538488800));
+        assert_eq!(parse_rfc3339("2018-10-02T16:00:00.25+02:00"),Some(1538488800));
+        // lowercase & space separators
+        assert_eq!(parse_rfc3339("2018-10-02t14:00:00z"),Some(1538488800));
+        assert_eq!(parse_rfc3339("2018-10-02 14:00:00Z"),Some(1538488800));
+    }
+
+
+    #[test]
+    fn rfc3339_invalid() {
+        let invalid = [
+            // out of range fields
+            "2018-13-02T14:00:00Z",
+            "2018-00-02T14:00:00Z",
+            "2018-10-00T14:00:00Z",
+            "2018-10-32T14:00:00Z",
+            "2018-09-31T14:00:00Z",
+            "2019-02-29T14:00:00Z",
+            "1900-02-29T14:00:00Z",
+            "2018-10-02T24:00:00Z",
+            "2018-10-02T25:00:00Z",
+            "2018-10-02T14:60:00Z",
+            "2018-10-02T14:00:00+24:00",
+            "2018-10-02T14:00:00+02:60",
+            // prior to the unix epoch
+            "1969-12-31T23:59:59Z",
+            "1970-01-01T00:00:00+00:01",
+            // malformed fields
+            "2018-10-02T14:00:00",
+            "2018-10-02T14:00:00.Z",
+            "2018-10-02T14:00:00.5",
+            "2018-10-02T14:00:00+0200",
+            "2018-10-02T14:00:00+02",
+            "2018-10-02T14:00:00*02:00",
+            "2018-10-02T14:00:00Z ",
+            "2018-10-02T14:00:00ZZ",
+            "2018-10-02X14:00:00Z",
+            "2018/10/02T14:00:00Z",
+            "2018-1-02T14:00:00Z",
+            "+018-10-02T14:00:00Z",
+            "2018-10-02T-4:00:00Z",
+            // truncated
+            "",
+            "2018",
+            "2018-10-02",
+            "2018-10-02T14:00",
+            "2018-10-02T14:00:00.",
+            "2018-10-02T14:00:0Z",
+            // non-ascii
+            "2018\u{2010}10\u{2010}02T14:00:00Z",
+            "２０１８-10-02T14:00:00Z",
+            "2018-10-02T14:00:00\u{e9}",
+            "2018-10-02T14:00:00.\u{661}Z",
+        ];
+        for s in invalid.iter() {
+            assert_eq!(parse_rfc3339(s),None,"{:?}",s);
+            assert!(s.parse::<StartBlock>().is_err(),"{:?}",s);
+        }
+    }
+
+
+    #[test]
+    fn parse_start() {
+        assert_eq!("0x4d2".parse::<StartBlock>().unwrap(),StartBlock::Number(U256::from(1234u64)));
+        assert_eq!("latest".parse::<StartBlock>().unwrap(),StartBlock::Latest(0));
+        assert_eq!("latest-0".parse::<StartBlock>().unwrap(),StartBlock::Latest(0));
+        assert_eq!("latest-18446744073709551615".parse::<StartBlock>().unwrap(),StartBlock::Latest(u64::max_value()));
+        assert!("latest-18446744073709551616".parse::<StartBlock>().is_err());
+        assert!("latest--5".parse::<StartBlock>().is_err());
+        assert!("latest+5".parse::<StartBlock>().is_err());
+        assert_eq!("1970-01-01T00:00:00Z".parse::<StartBlock>().unwrap(),StartBlock::Time(0));
+    }
+
+
+    #[test]
+    fn latest_offset() {
+        let head = U256::from(5000u64);
+        assert_eq!(blocks_before(head,0),head);
+        assert_eq!(blocks_before(head,1),U256::from(4999u64));
+        assert_eq!(blocks_before(head,5000),U256::from(0u64));
+        // offsets beyond the head of the chain start from genesis
+        assert_eq!(blocks_before(head,5001),U256::from(0u64));
+        assert_eq!(blocks_before(head,u64::max_value()),U256::from(0u64));
+        assert_eq!(blocks_before(U256::from(0u64),1),U256::from(0u64));
+    }
+}
@@ -1,51 +1,62 @@
-use ethrpc::types::{U256,Filter,Never,Log};
-use ethrpc::util::bufmath;
+use ethrpc::types::{U256,H256,Filter,Never,Log,Receipt};
+use ethrpc::util::{bufmath,queue};
 use ethrpc::{self,Url};
 use error::Error;
 use tokio::timer::Delay;
 use tokio::prelude::*;
+use std::collections::HashMap;
 use std::time::{Instant,Duration};
+use std::cmp;
 
-use tokio_channel::mpsc;
 use tokio::{self,io};
 
 
-pub type StdoutError = mpsc::SendError<String>;
+pub type StdoutError = queue::SendError<String>;
 
 /// Handle which allows pushing lines to stdout from multiple tasks.
 ///
 #[derive(Debug,Clone)]
 pub struct Stdout {
-    inner: mpsc::UnboundedSender<String>
+    inner: queue::Sender<String>
 }
 
 
 impl Stdout {
 
-    fn new(inner: mpsc::UnboundedSender<String>) -> Self { Self { inner } }
+    fn new(inner: queue::Sender<String>) -> Self { Self { inner } }
 
+    /// Push a line to stdout.
+    ///
+    /// Fails with `StdoutError::Overloaded` if too many lines are
+    /// already waiting to be written.
+    ///
     pub fn push_line(&self, line: impl Into<String>) -> Result<(),StdoutError> {
-        self.inner.unbounded_send(line.into())
+        self.inner.try_send(line.into())
     }
+
+    /// Number of lines waiting to be written.
+    ///
+    pub fn depth(&self) -> usize { self.inner.depth() }
 }
 
 
 /// Asynchronously spawn a background task which manages writing lines to stdout.
 ///
-pub fn stdout() -> impl Future<Item=Stdout,Error=Never> {
-    future::lazy(|| { Ok(spawn_stdout()) })
+pub fn stdout(capacity: usize) -> impl Future<Item=Stdout,Error=Never> {
+    future::lazy(move || { Ok(spawn_stdout(capacity)) })
 }
 
 
-/// Spawn a background task which manages writing lines to stdout.
+/// Spawn a background task which manages writing lines to stdout, buffering
+/// at most `capacity` lines.
 /// 
 /// ## Panics
 ///
 /// This function will panic if called outside of an event loop.  The `stdout`
 /// function is a non-panicking alternative.
 ///
-pub fn spawn_stdout() -> Stdout {
-    let (tx,rx) = mpsc::unbounded();
+pub fn spawn_stdout(capacity: usize) -> Stdout {
+    let (tx,rx) = queue::bounded(capacity);
     let handle = Stdout::new(tx);
     let work = rx.for_each(|mut line| {
         line.push('\n');
@@ -60,110 +71,268 @@ pub fn spawn_stdout() -> Stdout {
 
 
 
-/// Stream logs with basic retry behavior.
+/// Pause a log stream while the node is not ready.
 ///
-/// *note*: This helper only implements retry behavior in the strictest sense; it will attempt
-/// to reestablish a log-stream up to three times within a two minute period.  *However*, it
-/// enforces only minimal backoff with no jitter.  This function is intended to address
-/// connection failures due to red/blue transitions & load-balancer connection timeouts,
-/// *not* failures/restarts of a monolithic node.  This function is generally intended for
-/// use against load-balanced node pools where near-instant reconnect attempts are
-/// practical and appropriate.
+/// Node readiness (see `ethrpc::api::Util::check_ready`) is confirmed before
+/// each batch of logs is pulled from `logs`, and re-checked periodically while
+/// the node is syncing or its head is older than `max_head_age`.  Callbacks
+/// driven by the gated stream are therefore deferred (not dropped) until the
+/// node catches up.  Failed readiness checks are treated as not-ready.
 ///
-pub fn stream_logs_with_retry(url: Url, start: U256, filter: Filter, lag: u8) -> impl Stream<Item=(U256,Vec<Log>),Error=Error> + Send + 'static {
-    let builder = log_stream_builder(url,filter,lag);
-    stream_logs_with_builder(builder,start)
-}
-
-
-fn stream_logs_with_builder(builder: impl LogStreamBuilder, start: U256) -> impl Stream<Item=(U256,Vec<Log>),Error=Error> {
-    let backoff = Duration::from_millis(128);
-    let cooldown = Duration::from_secs(127); 
-    let max_fail = 3;
-    let mut stream = None;
-    let mut last_seen: Option<U256> = None;
-    let mut last_err = None;
-    let mut err_count = 0;
-    stream::poll_fn(move || -> Result<Async<Option<(U256,Vec<Log>)>>,Error> {
-        let poll = stream.get_or_insert_with(|| {
-            let target = match last_seen.as_ref() {
-                Some(block) => {
-                    let mut block = block.to_owned();
-                    add_assign(&mut block,1);
-                    block
+pub fn gate_on_readiness<S>(url: Url, max_head_age: Duration, logs: S) -> impl Stream<Item=S::Item,Error=Error>
+        where S: Stream<Error=Error> {
+    let recheck = Duration::from_secs(15);
+    let mut logs = logs;
+    let mut last_ready: Option<Instant> = None;
+    let mut paused_since: Option<Instant> = None;
+    let mut check = None;
+    let mut wait: Option<Delay> = None;
+    stream::poll_fn(move || -> Result<Async<Option<S::Item>>,Error> {
+        loop {
+            if let Some(delay) = wait.as_mut() {
+                match delay.poll()? {
+                    Async::Ready(()) => { },
+                    Async::NotReady => { return Ok(Async::NotReady); },
+                }
+            }
+            wait = None;
+            match last_ready {
+                Some(instant) if instant.elapsed() < recheck => { return logs.poll(); },
+                _ => { },
+            }
+            let poll = check.get_or_insert_with(|| {
+                let url = url.clone();
+                ethrpc::connect(url).and_then(move |api| api.util().check_ready(max_head_age))
+            }).poll();
+            // `None` if the node is ready, else the reason it is not.
+            let not_ready = match poll {
+                Ok(Async::Ready(readiness)) => {
+                    if readiness.is_ready() { None } else { Some(readiness.to_string()) }
                 },
-                None => start,
+                Ok(Async::NotReady) => { return Ok(Async::NotReady); },
+                Err(err) => Some(format!("readiness check failed: {}",err)),
             };
-            // `ethapi` crate does not attempt connection until polled, so we
-            // can pre-assemble the stream to avoid moving `builder`.
-            let stream = builder.build(target);
-            let until = Instant::now() + (backoff * err_count);
-            Delay::new(until).from_err().map(move |()| {
-                stream
-            }).flatten_stream()
-        }).poll();
-        match poll {
-            Ok(Async::Ready(Some((block,logs)))) => {
-                last_seen = Some(block);
-                Ok(Async::Ready(Some((block,logs))))
-            },
-            Ok(Async::Ready(None)) => {
-                let _ = stream.take();
-                Ok(Async::Ready(None))
-            },
-            Ok(Async::NotReady) => Ok(Async::NotReady),
-            Err(err) => {
-                let _ = stream.take();
-                let now = Instant::now();
-                let mut last = last_err.get_or_insert(now);
-                if (*last + cooldown) < now {
-                    *last = now;
-                    err_count = 0;
+            let _ = check.take();
+            let now = Instant::now();
+            if let Some(reason) = not_ready {
+                match paused_since {
+                    Some(since) => debug!("Callbacks paused for {}s; {}",(now - since).as_secs(),reason),
+                    None => {
+                        warn!("Pausing callbacks; {}",reason);
+                        paused_since = Some(now);
+                    },
                 }
-                if err_count < max_fail {
-                    err_count += 1;
-                    warn!("Log stream failed, attempting retry ({} of {})",err_count,max_fail);
-                    task::current().notify();
-                    Ok(Async::NotReady)
-                } else {
-                    Err(err)
+                last_ready = None;
+                wait = Some(Delay::new(now + recheck));
+            } else {
+                if let Some(since) = paused_since.take() {
+                    info!("Resuming callbacks; node ready after {}s pause",(now - since).as_secs());
                 }
-            },
+                last_ready = Some(now);
+            }
         }
     })
 }
 
 
-trait LogStreamBuilder {
+/// Drop logs which cannot be proven included in a canonical block.
+///
+/// If `verify` is supplied (as `(node,trusted_node)`), the header of each block
+/// with logs is loaded by its hash from `trusted_node`, and its receipts from `node`
+/// (see `ethrpc::api::Util::get_verified_receipts`).  Logs from blocks which are not
+/// canonical on `trusted_node` (e.g. reorged out, or never mined) are dropped.  Each
+/// remaining log must match the proven log at its `(transaction_hash,log_index)`,
+/// and each proven log may be matched only once; all other logs (including duplicates)
+/// are dropped.  Receipts which do not match the trusted header indicate a faulty or
+/// malicious node, and fail the stream.
+///
+pub fn verify_inclusion<S>(verify: Option<(Url,Url)>, logs: S) -> impl Stream<Item=(U256,Vec<Log>),Error=Error>
+        where S: Stream<Item=(U256,Vec<Log>),Error=Error> {
+    logs.and_then(move |(block,logs)| {
+        let (node,trusted) = match verify.as_ref() {
+            Some(verify) if !logs.is_empty() => verify.clone(),
+            _ => { return future::Either::A(future::ok((block,logs))); },
+        };
+        let mut hashes = Vec::new();
+        for hash in logs.iter().filter_map(|log| log.block_hash) {
+            if !hashes.contains(&hash) { hashes.push(hash); }
+        }
+        let connect = ethrpc::connect(trusted).join(ethrpc::connect(node));
+        let work = connect.and_then(move |(trusted,node)| {
+            let verify_blocks: Vec<_> = hashes.into_iter().map(|hash| {
+                let node = node.clone();
+                trusted.util().await_canonical_block(hash,block,Duration::from_millis(1024)).and_then(move |header| {
+                    match header {
+                        Some(header) => future::Either::A(node.util().get_verified_receipts(header).map(move |receipts| (hash,Some(receipts)))),
+                        None => future::Either::B(future::ok((hash,None))),
+                    }
+                })
+            }).collect();
+            future::collect(verify_blocks)
+        }).from_err::<Error>().map(move |verified| {
+            let mut pending: HashMap<H256,_> = verified.iter().filter_map(|(hash,receipts)| {
+                receipts.as_ref().map(|receipts| (*hash,proven_logs(receipts)))
+            }).collect();
+            let proven = logs.into_iter().filter(|log| {
+                match log.block_hash.and_then(|hash| pending.get_mut(&hash)) {
+                    Some(proven) => {
+                        let included = is_included(log,proven);
+                        if !included {
+                            error!("Dropping log not included in block {} (tx: {:?}, index: {:?})",
+                                block,log.transaction_hash,log.log_index);
+                        }
+                        included
+                    },
+                    None => {
+                        warn!("Dropping log from non-canonical block {:?} (tx: {:?}, index: {:?})",
+                            log.block_hash,log.transaction_hash,log.log_index);
+                        false
+                    },
+                }
+            }).collect();
+            (block,proven)
+        });
+        future::Either::B(work)
+    })
+}
 
-    type Stream: Stream<Item=(U256,Vec<Log>),Error=Error> + Send + 'static;
 
-    fn build(&self, start: U256) -> Self::Stream;
+/// Index the logs of proven receipts by `(transaction_hash,log_index)`.
+///
+/// Log indexes are counted from the position of each log in the block, since
+/// the `logIndex` reported by the node is not committed to by the receipts root.
+///
+fn proven_logs(receipts: &[Receipt]) -> HashMap<(H256,U256),&Log> {
+    receipts.iter().flat_map(|receipt| {
+        receipt.logs.iter().map(move |log| (receipt.transaction_hash,log))
+    }).enumerate().map(|(index,(tx_hash,log))| {
+        ((tx_hash,U256::from(index as u64)),log)
+    }).collect()
 }
 
 
-impl<T,S> LogStreamBuilder for T where T: Fn(U256) -> S, S: Stream<Item=(U256,Vec<Log>),Error=Error> + Send + 'static {
+/// Check that `log` matches the proven log at its position, consuming the proven log
+/// such that each may be matched at most once.
+///
+fn is_included(log: &Log, proven: &mut HashMap<(H256,U256),&Log>) -> bool {
+    let key = match (log.transaction_hash,log.log_index) {
+        (Some(tx_hash),Some(index)) => (tx_hash,index),
+        _ => { return false; },
+    };
+    match proven.remove(&key) {
+        Some(proven) => proven.address == log.address && proven.topics == log.topics && proven.data == log.data,
+        None => false,
+    }
+}
 
-    type Stream = S;
 
-    fn build(&self, start: U256) -> Self::Stream { (self)(start) }
+/// Stream logs matching `filter`, starting from block `start`, retrying on failure.
+///
+/// The transport re-establishes lost connections itself, but a failed initial
+/// connection or an error reported by the node still ends the underlying stream.
+/// In that case the stream is rebuilt, resuming from the block after the last one
+/// yielded.  Retries back off exponentially (from 128ms up to one minute), and the
+/// backoff is reset once a batch of logs is yielded successfully.
+///
+pub fn stream_logs_with_retry(url: Url, capacity: usize, start: U256, filter: Filter, lag: u8) -> impl Stream<Item=(U256,Vec<Log>),Error=Error> + Send + 'static {
+    let builder = move |start| stream_logs(url.clone(),capacity,start,filter.clone(),lag);
+    retry_with_backoff(builder,start)
 }
 
 
-fn log_stream_builder(url: Url, filter: Filter, lag: u8) -> impl LogStreamBuilder + Send + 'static {
-    move |start| { stream_logs(url.clone(),start,filter.clone(),lag) }
+fn retry_with_backoff<F,S>(builder: F, start: U256) -> impl Stream<Item=(U256,Vec<Log>),Error=Error>
+        where F: Fn(U256) -> S, S: Stream<Item=(U256,Vec<Log>),Error=Error> {
+    let backoff = Duration::from_millis(128);
+    let max_backoff = Duration::from_secs(60);
+    let mut stream = None;
+    let mut last_seen: Option<U256> = None;
+    let mut err_count: u32 = 0;
+    stream::poll_fn(move || -> Result<Async<Option<(U256,Vec<Log>)>>,Error> {
+        loop {
+            let poll = stream.get_or_insert_with(|| {
+                let target = match last_seen {
+                    Some(block) => next_block(block),
+                    None => start,
+                };
+                let delay = match err_count {
+                    0 => Duration::from_millis(0),
+                    n => cmp::min(backoff * 2u32.saturating_pow(n - 1),max_backoff),
+                };
+                // streams do not connect until polled, so the next stream can be
+                // built now & held until the backoff elapses.
+                let next = builder(target);
+                Delay::new(Instant::now() + delay).from_err().map(move |()| next).flatten_stream()
+            }).poll();
+            match poll {
+                Ok(Async::Ready(Some((block,logs)))) => {
+                    last_seen = Some(block);
+                    err_count = 0;
+                    return Ok(Async::Ready(Some((block,logs))));
+                },
+                Ok(Async::Ready(None)) => {
+                    let _ = stream.take();
+                    return Ok(Async::Ready(None));
+                },
+                Ok(Async::NotReady) => { return Ok(Async::NotReady); },
+                Err(err) => {
+                    let _ = stream.take();
+                    err_count = err_count.saturating_add(1);
+                    warn!("Log stream failed, retrying (attempt {}): {}",err_count,err);
+                },
+            }
+        }
+    })
 }
 
 
-fn stream_logs(url: Url, start: U256, filter: Filter, lag: u8) -> impl Stream<Item=(U256,Vec<Log>),Error=Error> + Send + 'static {
-    ethrpc::connect(url).from_err::<Error>().map(move |api| {
+/// Stream logs matching `filter`, starting from block `start`.
+///
+fn stream_logs(url: Url, capacity: usize, start: U256, filter: Filter, lag: u8) -> impl Stream<Item=(U256,Vec<Log>),Error=Error> + Send + 'static {
+    ethrpc::connect_with_capacity(url,capacity).from_err::<Error>().map(move |api| {
         api.util().log_stream(start,Duration::from_millis(1024),filter,Some(lag)).from_err::<Error>()
     }).flatten_stream()
 }
 
 
-fn add_assign(num: &mut U256, add: u8) {
-    let lhs = U256::from(add as u64);
-    let overflow = bufmath::add(num,&lhs);
+fn next_block(block: U256) -> U256 {
+    let mut next = block;
+    let overflow = bufmath::add(&mut next,&U256::from(1));
     assert!(overflow == false,"256-bit integer overflow during add-assign");
+    next
+}
+
+
+#[cfg(test)]
+mod test {
+    use ethrpc::types::U256;
+    use error::Error;
+    use util;
+    use tokio::prelude::*;
+    use tokio::runtime::current_thread;
+    use std::sync::{Arc,Mutex};
+
+
+    #[test]
+    fn retry_resumes_after_last_block() {
+        let targets = Arc::new(Mutex::new(Vec::new()));
+        let builder = {
+            let targets = targets.clone();
+            move |start: U256| {
+                let mut targets = targets.lock().unwrap();
+                targets.push(start);
+                // every stream yields one block, then fails
+                let items = match targets.len() {
+                    1 => vec![Err(Error::message("connection refused"))],
+                    _ => vec![Ok((start,Vec::new())),Err(Error::message("node error"))],
+                };
+                stream::iter_result(items)
+            }
+        };
+        let blocks = util::retry_with_backoff(builder,U256::from(7)).take(3).map(|(block,_)| block).collect();
+        let blocks = current_thread::block_on_all(blocks).unwrap();
+        let expect: Vec<U256> = vec![U256::from(7),U256::from(8),U256::from(9)];
+        assert_eq!(blocks,expect);
+        let targets = targets.lock().unwrap().clone();
+        assert_eq!(targets,vec![U256::from(7),U256::from(7),U256::from(8),U256::from(9)]);
+    }
 }
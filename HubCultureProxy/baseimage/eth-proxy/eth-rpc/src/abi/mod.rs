@@ -104,10 +104,11 @@
 //!
 //! # }
 //! ```
-use serde::de::{Deserialize,Deserializer};
+use serde::de::{self,Deserialize,Deserializer,IntoDeserializer,Visitor};
 use serde::ser::{Serialize,Serializer};
 use proxy::util::serde_str;
 use types::{Bytes,H256,U256,Uint8};
+use util::{units,trim};
 use crypto::{Keccak256,Address};
 use std::borrow::Borrow;
 use std::str::FromStr;
@@ -211,7 +212,32 @@ pub(crate) fn signature(name: &str, tokens: impl IntoIterator<Item=Token>) -> H2
 
 /// An abi value.
 ///
-#[derive(Hash,Debug,Copy,Clone,PartialEq,Eq,Serialize,Deserialize)]
+/// Values deserialize from `0x`-prefixed hex strings, or from decimal
+/// strings/integers (which are always interpreted as `Uint`):
+///
+/// ```
+/// # extern crate serde_json;
+/// # extern crate ethrpc;
+/// # use ethrpc::abi::{Token,Value};
+/// # fn main() {
+/// let hex: Value = serde_json::from_str(r#""0x3e8""#).unwrap();
+/// let dec: Value = serde_json::from_str(r#""1000""#).unwrap();
+/// let int: Value = serde_json::from_str("1000").unwrap();
+///
+/// assert_eq!(hex,Value::Uint("0x3e8".parse().unwrap()));
+/// assert_eq!(dec,hex);
+/// assert_eq!(int,hex);
+///
+/// // Negative integers are never valid.
+/// assert!(serde_json::from_str::<Value>("-1000").is_err());
+///
+/// // Small decimals may be used where a `uint8` is expected.
+/// let small: Value = serde_json::from_str(r#""16""#).unwrap();
+/// assert!(small.try_cast(Token::Uint8).is_ok());
+/// # }
+/// ```
+///
+#[derive(Hash,Debug,Copy,Clone,PartialEq,Eq,Serialize)]
 #[serde(untagged)]
 pub enum Value {
     Addr(Address), 
@@ -221,6 +247,76 @@ pub enum Value {
 }
 
 
+impl<'de> Deserialize<'de> for Value {
+
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self,D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+
+/// Hex-encoded values, disambiguated by length.
+///
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HexValue {
+    Addr(Address),
+    Hash(H256),
+    Uint8(Uint8),
+    Uint(U256),
+}
+
+
+impl From<HexValue> for Value {
+
+    fn from(hex: HexValue) -> Self {
+        match hex {
+            HexValue::Addr(addr) => Value::Addr(addr),
+            HexValue::Hash(hash) => Value::Hash(hash),
+            HexValue::Uint8(uint8) => Value::Uint8(uint8),
+            HexValue::Uint(uint) => Value::Uint(uint),
+        }
+    }
+}
+
+
+struct ValueVisitor;
+
+
+impl<'de> Visitor<'de> for ValueVisitor {
+
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("hex string, decimal string, or unsigned integer")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value,E> {
+        Ok(Value::Uint(value.into()))
+    }
+
+    // formats such as toml yield all integers as signed
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value,E> {
+        if value < 0 {
+            Err(E::invalid_value(de::Unexpected::Signed(value),&self))
+        } else {
+            self.visit_u64(value as u64)
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value,E> {
+        if value.trim().starts_with("0x") {
+            let hex = HexValue::deserialize(value.into_deserializer())
+                .map_err(|_: E| E::custom(format!("invalid abi value `{}`",value)))?;
+            Ok(hex.into())
+        } else {
+            let uint = units::parse_decimal(value).map_err(E::custom)?;
+            Ok(Value::Uint(uint))
+        }
+    }
+}
+
+
 impl Value {
 
     /// Attempt to cast this value to the type indicated by `target`.
@@ -321,6 +417,13 @@ impl Value {
     pub fn as_uint8(&self) -> Option<Uint8> {
         match self {
             Value::Uint8(uint8) => Some(*uint8),
+            // decimal inputs always parse as `Uint`, so allow
+            // narrowing if the value fits.
+            Value::Uint(uint) => match trim(uint) {
+                [] => Some(0u8.into()),
+                [byte] => Some((*byte).into()),
+                _other => None,
+            },
             _other => None,
         }
     }
@@ -1,6 +1,7 @@
 use tokio::timer;
 use std::{fmt,error};
 use api::Response;
+use trie::ProofError;
 use rpc;
 
 
@@ -9,6 +10,9 @@ wrap_errs!(
     Rsp => Unexpected,
     Timer => timer::Error,
     Fatal => TransportFailed,
+    Timeout => TimedOut,
+    Overload => Overloaded,
+    Proof => ProofError,
 );
 
 
@@ -35,15 +39,58 @@ impl error::Error for TransportFailed {
 }
 
 
+/// Error indicating that an operation did not complete within its deadline.
+///
+#[derive(Debug,Copy,Clone)]
+pub struct TimedOut;
+
+
+impl fmt::Display for TimedOut {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("operation timed out")
+    }
+}
+
+impl error::Error for TimedOut {
+
+    fn description(&self) -> &str { "operation timed out" }
+}
+
+
+/// Error indicating that a request was rejected because the transport
+/// was at capacity.
+///
+#[derive(Debug,Copy,Clone)]
+pub struct Overloaded;
+
+
+impl fmt::Display for Overloaded {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("transport overloaded")
+    }
+}
+
+impl error::Error for Overloaded {
+
+    fn description(&self) -> &str { "transport overloaded" }
+}
+
+
 /// Error indicating unexpected response payload.
 ///
 /// This error specifically indicates that the response was a valid JSON-RPC
-/// payload, but was not the *expected* payload.
+/// payload, but was not the *expected* payload for the request's method.
 ///
 #[derive(Debug,Clone)]
 pub struct Unexpected<T=Response> {
+    /// Method of the originating request
+    pub method: &'static str,
     /// Short description of expected payload
     pub expecting: &'static str,
+    /// Reason the payload could not be decoded
+    pub reason: String,
     /// The payload that was actually recieved
     pub got: T
 }
@@ -52,7 +99,7 @@ pub struct Unexpected<T=Response> {
 impl<T> fmt::Display for Unexpected<T> {
 
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f,"invalid response (expecting {})",self.expecting)
+        write!(f,"invalid response to `{}` (expecting {}): {}",self.method,self.expecting,self.reason)
     }
 }
 
@@ -62,3 +109,13 @@ impl error::Error for Unexpected {
     fn description(&self) -> &str { "unexpected response value" }
 }
 
+
+impl rpc::Classify for Error {
+
+    fn kind(&self) -> rpc::ErrorKind {
+        match self {
+            Error::Rpc(err) => err.kind(),
+            _ => rpc::ErrorKind::Other,
+        }
+    }
+}
@@ -1,5 +1,6 @@
-use types::{Filter,Log,Bytes,BlockId,Block,Transaction,TxInfo,TxCall,Receipt,U256,H256};
-use api::{Request,Response,Expect,AsyncRpc};
+use types::{Filter,Log,Bytes,BlockId,Block,BlockTx,Transaction,TxInfo,TxCall,Receipt,SyncState,AccountProof,U256,H256};
+use api::{Request,Response,AsyncRpc};
+use serde::de::DeserializeOwned;
 use crypto::Address;
 use rpc;
 
@@ -22,7 +23,28 @@ impl<'a,T> Eth<'a,T> where T: rpc::Transport<Request,Response> {
     /// Equivalent to the `eth_getBlockByNumber` method.
     ///
     pub fn get_block_by_number(&self, block: BlockId) -> AsyncRpc<T::Future,Option<Block<H256>>> {
-        let req = Request::get_block_by_number(block,false);
+        self.get_block_with(block)
+    }
+
+    /// Equivalent to the `eth_getBlockByNumber` method with full
+    /// transaction objects.
+    ///
+    pub fn get_full_block_by_number(&self, block: BlockId) -> AsyncRpc<T::Future,Option<Block<TxInfo>>> {
+        self.get_block_with(block)
+    }
+
+    /// Equivalent to the `eth_getBlockByNumber` method, with transactions
+    /// loaded as `TX` (see `BlockTx`).
+    ///
+    pub fn get_block_with<TX: BlockTx>(&self, block: BlockId) -> AsyncRpc<T::Future,Option<Block<TX>>> {
+        let req = Request::get_block_by_number(block,TX::FULL);
+        self.execute(req)
+    }
+
+    /// Equivalent to the `eth_getBlockByHash` method.
+    ///
+    pub fn get_block_by_hash(&self, hash: H256) -> AsyncRpc<T::Future,Option<Block<H256>>> {
+        let req = Request::get_block_by_hash(hash,false);
         self.execute(req)
     }
 
@@ -61,6 +83,15 @@ impl<'a,T> Eth<'a,T> where T: rpc::Transport<Request,Response> {
         self.execute(req)
     }
 
+    /// Equivalent to the `eth_getProof` method.
+    ///
+    /// The returned proof is unverified (see `AccountProof::verify`).
+    ///
+    pub fn get_proof(&self, addr: Address, keys: Vec<H256>, block: BlockId) -> AsyncRpc<T::Future,AccountProof> {
+        let req = Request::get_proof(addr,keys,block);
+        self.execute(req)
+    }
+
     /// Equivalent to the `eth_estimateGas` method.
     ///
     pub fn estimate_gas(&self, tx: Transaction, block: BlockId) -> AsyncRpc<T::Future,U256> {
@@ -103,7 +134,15 @@ impl<'a,T> Eth<'a,T> where T: rpc::Transport<Request,Response> {
         self.execute(req)
     }
 
-    fn execute<I>(&self, request: Request) -> AsyncRpc<T::Future,I> where Response: Expect<I> {
-        AsyncRpc::new(self.transport.call(request))
+    /// Equivalent to the `eth_syncing` method.
+    ///
+    pub fn syncing(&self) -> AsyncRpc<T::Future,SyncState> {
+        let req = Request::syncing();
+        self.execute(req)
+    }
+
+    fn execute<I>(&self, request: Request) -> AsyncRpc<T::Future,I> where I: DeserializeOwned {
+        let (method,expecting) = (request.method_name(),request.expecting());
+        AsyncRpc::new(self.transport.call(request),method,expecting)
     }
 }
@@ -43,12 +43,13 @@
 //! # fn main() { }
 //! ```
 //!
-use types::{Filter,Log,Bytes,U256,H256,BlockId,Block,Transaction,TxInfo,TxCall,Receipt,Never};
+use types::{Filter,Bytes,H256,BlockId,Transaction,TxCall};
 use crypto::Address;
+use erc20::Erc20;
 use rpc;
-use std::collections::HashMap;
 use std::marker::PhantomData;
-use std::fmt;
+use std::time::Duration;
+use serde::de::{Deserialize,DeserializeOwned};
 use serde_json::Value;
 
 
@@ -57,10 +58,10 @@ mod util;
 mod eth;
 
 pub use self::error::Error;
-pub use self::util::{Util,LatestLogs};
+pub use self::util::{Util,LatestLogs,Confirmation,Readiness};
 pub use self::eth::Eth;
 
-use self::error::{Unexpected,TransportFailed};
+use self::error::{Unexpected,TransportFailed,TimedOut,Overloaded};
 use tokio::prelude::*;
 use transport;
 use url::Url;
@@ -69,7 +70,14 @@ use url::Url;
 /// Connect to specified node
 ///
 pub fn connect(url: Url) -> impl Future<Item=Api<impl rpc::Transport<Request,Response>>,Error=Error> {
-    transport::ws::connect(url).map(Api::new).map_err(|e| -> Error {
+    connect_with_capacity(url,transport::plex::DEFAULT_CAPACITY)
+}
+
+
+/// Connect to specified node, buffering up to `capacity` pending requests
+///
+pub fn connect_with_capacity(url: Url, capacity: usize) -> impl Future<Item=Api<impl rpc::Transport<Request,Response>>,Error=Error> {
+    transport::ws::connect_with_capacity(url,capacity).map(Api::new).map_err(|e| -> Error {
         error!("During connect: {:?}",e);
         Error::from(error::TransportFailed)
     })
@@ -107,6 +115,10 @@ impl<T> Api<T> {
     /// Access extra utility functions.
     ///
     pub fn util(&self) -> Util<T> { Util::new(&self.transport) }
+
+    /// Access the ERC-20 interface of the specified token contract.
+    ///
+    pub fn erc20(&self, token: Address) -> Erc20<T> { Erc20::new(&self.transport,token) }
 }
 
 
@@ -119,6 +131,8 @@ pub enum Request {
     GetLogs([Filter;1]),
     /// Equivalent to `eth_getBlockByNumber`
     GetBlockByNumber(BlockId,bool),
+    /// Equivalent to `eth_getBlockByHash`
+    GetBlockByHash(H256,bool),
     /// Equivalent to `eth_getTransactionByHash`
     GetTxByHash([H256;1]),
     /// Equivalent to `eth_getTransactionReceipt`
@@ -127,6 +141,8 @@ pub enum Request {
     GetBalance(Address,BlockId),
     /// Equivalent to `eth_getTransactionCount`
     GetTxCount(Address,BlockId),
+    /// Equivalent to `eth_getProof`
+    GetProof(Address,Vec<H256>,BlockId),
     /// Equivalent to `eth_estimateGas`
     EstimateGas(Transaction,BlockId),
     /// Equivalten to `eth_call`
@@ -139,6 +155,8 @@ pub enum Request {
     GasPrice,
     /// Equivalent to `eth_accounts`
     Accounts,
+    /// Equivalent to `eth_syncing`
+    Syncing,
 }
 
 
@@ -152,6 +170,10 @@ impl Request {
     ///
     pub fn get_block_by_number(block: BlockId, full: bool) -> Self { Request::GetBlockByNumber(block,full) }
 
+    /// Construct a request for the `eth_getBlockByHash` method.
+    ///
+    pub fn get_block_by_hash(hash: H256, full: bool) -> Self { Request::GetBlockByHash(hash,full) }
+
     /// Construct a request for the `eth_getTransactionByHash` method.
     ///
     pub fn get_tx_by_hash(hash: H256) -> Self { Request::GetTxByHash([hash]) }
@@ -168,6 +190,10 @@ impl Request {
     ///
     pub fn get_tx_count(addr: Address, block: BlockId) -> Self { Request::GetTxCount(addr,block) }
 
+    /// Construct a request for the `eth_getProof` method.
+    ///
+    pub fn get_proof(addr: Address, keys: Vec<H256>, block: BlockId) -> Self { Request::GetProof(addr,keys,block) }
+
     /// Construct a request for the `eth_estimateGas` method.
     ///
     pub fn estimate_gas(tx: Transaction, block: BlockId) -> Self { Request::EstimateGas(tx,block) }
@@ -191,232 +217,121 @@ impl Request {
     /// Contruct a request for the `eth_accounts` method.
     ///
     pub fn accounts() -> Self { Request::Accounts }
-}
 
+    /// Construct a request for the `eth_syncing` method.
+    ///
+    pub fn syncing() -> Self { Request::Syncing }
+}
 
-impl rpc::Request for Request {
 
-    type Params = Self;
+impl Request {
 
-    fn method(&self) -> &str {
+    /// Name of the rpc method invoked by this request.
+    ///
+    pub fn method_name(&self) -> &'static str {
         match self {
             Request::GetLogs(_) => "eth_getLogs",
             Request::GetBlockByNumber(_,_) => "eth_getBlockByNumber",
+            Request::GetBlockByHash(_,_) => "eth_getBlockByHash",
             Request::GetTxByHash(_) => "eth_getTransactionByHash",
             Request::GetTxReceipt(_) => "eth_getTransactionReceipt",
             Request::GetBalance(_,_) => "eth_getBalance",
             Request::GetTxCount(_,_) => "eth_getTransactionCount",
-            Request::EstimateGas(_,_) => "eth_estimateGase",
+            Request::GetProof(_,_,_) => "eth_getProof",
+            Request::EstimateGas(_,_) => "eth_estimateGas",
             Request::Call(_,_) => "eth_call",
             Request::SendRawTx(_) => "eth_sendRawTransaction",
             Request::BlockNumber => "eth_blockNumber",
             Request::GasPrice => "eth_gasPrice",
             Request::Accounts => "eth_accounts",
+            Request::Syncing => "eth_syncing",
         }
     }
 
+    /// Short description of the result expected from this request.
+    ///
+    pub fn expecting(&self) -> &'static str {
+        match self {
+            Request::GetLogs(_) => "array of logs",
+            Request::GetBlockByNumber(_,false) => "block or null",
+            Request::GetBlockByNumber(_,true) => "block with full transactions or null",
+            Request::GetBlockByHash(_,false) => "block or null",
+            Request::GetBlockByHash(_,true) => "block with full transactions or null",
+            Request::GetTxByHash(_) => "transaction or null",
+            Request::GetTxReceipt(_) => "receipt or null",
+            Request::GetBalance(_,_) => "256-bit unsigned integer",
+            Request::GetTxCount(_,_) => "256-bit unsigned integer",
+            Request::GetProof(_,_,_) => "account & storage proofs",
+            Request::EstimateGas(_,_) => "256-bit unsigned integer",
+            Request::Call(_,_) => "arbitrary byte-array",
+            Request::SendRawTx(_) => "transaction hash",
+            Request::BlockNumber => "256-bit unsigned integer",
+            Request::GasPrice => "256-bit unsigned integer",
+            Request::Accounts => "array of addresses",
+            Request::Syncing => "sync progress or false",
+        }
+    }
+}
+
+
+impl rpc::Request for Request {
+
+    type Params = Self;
+
+    fn method(&self) -> &str { self.method_name() }
+
     fn params(&self) -> Option<&Self::Params> {
         match self {
             Request::GetLogs(_) => Some(self),
             Request::GetBlockByNumber(_,_) => Some(self),
+            Request::GetBlockByHash(_,_) => Some(self),
             Request::GetTxByHash(_) => Some(self),
             Request::GetTxReceipt(_) => Some(self),
             Request::GetBalance(_,_) => Some(self),
             Request::GetTxCount(_,_) => Some(self),
+            Request::GetProof(_,_,_) => Some(self),
             Request::EstimateGas(_,_) => Some(self),
             Request::Call(_,_) => Some(self),
             Request::SendRawTx(_) => Some(self),
             Request::BlockNumber => None,
             Request::GasPrice => None,
             Request::Accounts => None,
-        }
-    }
-}
-
-
-/// An Ethereum JSON-RPC response payload
-///
-#[derive(Debug,Clone,Serialize,Deserialize)]
-#[serde(untagged)]
-pub enum Response {
-    /// A null value
-    NullValue(()),
-    /// Empty sequence
-    EmptySeq([Never;0]),
-    /// Empty mapping
-    EmptyMap(HashMap<Never,Never>),
-    /// A block datastructure
-    Block(Block<H256>),
-    /// Pending/mined transaction info
-    TxInfo(TxInfo),
-    /// Transaction execution receipt
-    TxReceipt(Receipt),
-    /// Sequence of log objects
-    Logs(Vec<Log>),
-    /// Sequence of addresses
-    Addrs(Vec<Address>),
-    /// A single address
-    Addr(Address),
-    /// 256-bit arbitrary bytearray
-    Hash(H256),
-    /// Unsigned 256-bit integer (hex encoded)
-    Uint(U256), 
-    /// Arbitrary byte-array
-    Bytes(Bytes),
-    /// Arbitrary JSON
-    Other(Value),
-}
-
-
-impl Response {
-
-    pub fn expect_block(self) -> Result<Option<Block<H256>>,Unexpected> {
-        match self {
-            Response::Block(block) => Ok(Some(block)),
-            Response::NullValue(()) => Ok(None),
-            other => Err(Unexpected {
-                expecting: "block or null",
-                got: other,
-            }),
-        }
-    }
-
-    pub fn expect_tx_info(self) -> Result<Option<TxInfo>,Unexpected> {
-        match self {
-            Response::TxInfo(tx) => Ok(Some(tx)),
-            Response::NullValue(()) => Ok(None),
-            other => Err(Unexpected {
-                expecting: "transaction or null",
-                got: other,
-            }),
+            Request::Syncing => None,
         }
     }
 
-    pub fn expect_tx_receipt(self) -> Result<Option<Receipt>,Unexpected> {
+    fn deadline(&self) -> Option<Duration> {
         match self {
-            Response::TxReceipt(receipt) => Ok(Some(receipt)),
-            Response::NullValue(()) => Ok(None),
-            other => Err(Unexpected {
-                expecting: "receipt or null",
-                got: other,
-            })
-        }
-    }
-
-    pub fn expect_logs(self) -> Result<Vec<Log>,Unexpected> {
-        match self {
-            Response::Logs(logs) => Ok(logs),
-            Response::EmptySeq(_) => Ok(Default::default()),
-            other => Err(Unexpected {
-                expecting: "array of logs",
-                got: other,
-            }),
-        }
-    }
-
-    pub fn expect_addrs(self) -> Result<Vec<Address>,Unexpected> {
-        match self {
-            Response::Addrs(addrs) => Ok(addrs),
-            Response::EmptySeq(_) => Ok(Default::default()),
-            other => Err(Unexpected {
-                expecting: "array of addresses",
-                got: other,
-            })
-        }
-    }
-
-    pub fn expect_hash(self) -> Result<H256,Unexpected> {
-        match self {
-            Response::Hash(hash) => Ok(hash),
-            other => Err(Unexpected {
-                expecting: "256-bit bytes-like value",
-                got: other,
-            })
-        }
-    }
-
-    pub fn expect_uint(self) -> Result<U256,Unexpected> {
-        match self {
-            Response::Uint(uint) => Ok(uint),
-            Response::Addr(addr) => {
-                let mut buf = [0u8;32];
-                let offset = buf.len() - addr.len();
-                (&mut buf[offset..]).copy_from_slice(&addr);
-                Ok(buf.into())
-            },
-            Response::Hash(hash) => Ok(hash.into_other()),
-            other => Err(Unexpected {
-                expecting: "256-bit unsigned integer",
-                got: other,
-            })
-        }
-    }
-
-    pub fn expect_bytes(self) -> Result<Bytes,Unexpected> {
-        match self {
-            Response::Bytes(bytes) => Ok(bytes),
-            Response::Addr(addr) => {
-                let mut buf = Vec::with_capacity(addr.len());
-                buf.extend_from_slice(&addr);
-                Ok(buf.into())
-            },
-            Response::Hash(hash) => {
-                let mut buf = Vec::with_capacity(hash.len());
-                buf.extend_from_slice(&hash);
-                Ok(buf.into())
-            },
-            Response::Uint(uint) => {
-                let trimmed = ::util::trim(&uint);
-                let mut buf = Vec::with_capacity(trimmed.len());
-                buf.extend_from_slice(&trimmed);
-                Ok(buf.into())
-            },
-            other => Err(Unexpected {
-                expecting: "arbitrary byte-array",
-                got: other,
-            })
+            // log queries over large block ranges can take the node
+            // considerably longer than other methods to serve.
+            Request::GetLogs(_) => Some(Duration::from_secs(180)),
+            _other => None,
         }
     }
 }
 
 
-/// A type which may or may not be an expected value.
+/// An Ethereum JSON-RPC response payload.
 ///
-pub trait Expect<T>: Sized {
-
-    fn as_expected(self) -> Result<T,Unexpected<Self>>;
-}
-
-macro_rules! impl_expect {
-    ($($method:ident => $type:ty,)*) => {
-        $(
-            impl Expect<$type> for Response {
-                
-                fn as_expected(self) -> Result<$type,Unexpected> { self.$method() }
-            }
-        )*
-    }
-}
-
-
-impl_expect! {
-    expect_block => Option<Block<H256>>, 
-    expect_tx_info => Option<TxInfo>,
-    expect_tx_receipt => Option<Receipt>,
-    expect_logs => Vec<Log>,
-    expect_addrs => Vec<Address>,
-    expect_hash => H256,
-    expect_uint => U256,
-    expect_bytes => Bytes,
-}
+/// Results are held in their raw JSON form by the transport, and decoded
+/// directly into the type expected by the originating request's method
+/// (see `AsyncRpc`).
+///
+pub type Response = Value;
 
 
 /// An asynchronous rpc operation.
 ///
+/// Resolves to the result of the call, decoded as `T`.  Results which do
+/// not match `T` produce an `Unexpected` error which describes the method,
+/// the expected payload, and the reason decoding failed.
+///
 pub enum AsyncRpc<F,T> {
     /// Drive inner future to completion
     Work {
         inner: F,
+        method: &'static str,
+        expecting: &'static str,
         expect: PhantomData<T>,
     },
     /// Fail immediately with supplied error
@@ -426,10 +341,13 @@ pub enum AsyncRpc<F,T> {
 }
 
 
-impl<F,T> AsyncRpc<F,T> where F: Future<Item=rpc::Result<Response>>, Response: Expect<T>, F::Error: fmt::Display {
+impl<F,T> AsyncRpc<F,T> where F: Future<Item=rpc::Result<Response>>, T: DeserializeOwned, F::Error: rpc::TransportError {
 
-    pub fn new(inner: F) -> Self {
-        AsyncRpc::Work { inner, expect: PhantomData }
+    /// Wrap a pending call to `method` (see `Request::method_name`) whose result
+    /// is described by `expecting` (see `Request::expecting`).
+    ///
+    pub fn new(inner: F, method: &'static str, expecting: &'static str) -> Self {
+        AsyncRpc::Work { inner, method, expecting, expect: PhantomData }
     }
 
     pub fn fail(reason: impl Into<Error>) -> Self {
@@ -438,13 +356,27 @@ impl<F,T> AsyncRpc<F,T> where F: Future<Item=rpc::Result<Response>>, Response: E
 
     fn poll_inner(&mut self) -> Poll<T,Error> {
         match self {
-            AsyncRpc::Work { ref mut inner, .. } => {
+            AsyncRpc::Work { ref mut inner, ref method, ref expecting, .. } => {
                 let item = try_ready!(inner.poll().map_err(|e| {
-                    warn!("Transport failed: {}",e);
-                    Error::from(TransportFailed)
+                    if e.is_timeout() {
+                        warn!("Request timed out: {}",e);
+                        Error::from(TimedOut)
+                    } else if e.is_overloaded() {
+                        warn!("Request rejected: {}",e);
+                        Error::from(Overloaded)
+                    } else {
+                        warn!("Transport failed: {}",e);
+                        Error::from(TransportFailed)
+                    }
                 }))?;
-                let expected = item.as_expected()?;
-                Ok(Async::Ready(expected))
+                match T::deserialize(&item) {
+                    Ok(expected) => Ok(Async::Ready(expected)),
+                    Err(err) => {
+                        warn!("Unexpected result for `{}`: {}",method,err);
+                        let (method,expecting,reason) = (*method,*expecting,err.to_string());
+                        Err(Unexpected { method, expecting, reason, got: item }.into())
+                    },
+                }
             },
             AsyncRpc::Fail { ref mut error } => {
                 let err = error.take().expect("No polling past completion");
@@ -455,7 +387,7 @@ impl<F,T> AsyncRpc<F,T> where F: Future<Item=rpc::Result<Response>>, Response: E
 }
 
 
-impl<F,T> Future for AsyncRpc<F,T> where F: Future<Item=rpc::Result<Response>>, Response: Expect<T>, F::Error: fmt::Display {
+impl<F,T> Future for AsyncRpc<F,T> where F: Future<Item=rpc::Result<Response>>, T: DeserializeOwned, F::Error: rpc::TransportError {
     
     type Item = T;
 
@@ -465,4 +397,3 @@ impl<F,T> Future for AsyncRpc<F,T> where F: Future<Item=rpc::Result<Response>>,
         self.poll_inner()
     }
 }
-
@@ -1,8 +1,13 @@
-use types::{BlockId,Block,U256,H256,Filter,Log};
+use types::{BlockId,Block,BlockTx,TxInfo,U256,H256,Filter,Log,Receipt,Status,SyncState,AccountProof};
+use crypto::Address;
+use trie::{self,ProofError};
+use api::error::{TimedOut,Unexpected};
 use api::{Request,Response,Error,Api};
-use util::bufmath;
+use util::{self,bufmath};
 use rpc;
-use std::time::{Duration,Instant};
+use serde_json::Value;
+use std::time::{Duration,Instant,SystemTime,UNIX_EPOCH};
+use std::fmt;
 use tokio::prelude::*;
 use tokio::timer;
 
@@ -26,9 +31,21 @@ impl<'a,T> Util<'a,T> where T: rpc::Transport<Request,Response> {
     /// Wait for a specified block to exist.
     ///
     pub fn await_block(&self, block: BlockId, poll: Duration) -> impl Future<Item=Block<H256>,Error=Error> {
+        self.await_block_with(block,poll)
+    }
+
+    /// Wait for a specified block to exist, loading full transaction objects.
+    ///
+    pub fn await_full_block(&self, block: BlockId, poll: Duration) -> impl Future<Item=Block<TxInfo>,Error=Error> {
+        self.await_block_with(block,poll)
+    }
+
+    /// Wait for a specified block to exist, loading transactions as `TX`.
+    ///
+    pub fn await_block_with<TX: BlockTx>(&self, block: BlockId, poll: Duration) -> impl Future<Item=Block<TX>,Error=Error> {
         future::loop_fn((Instant::now(),self.api()), move |(time,api)| {
             timer::Delay::new(time).from_err().and_then(move |()| {
-                api.eth().get_block_by_number(block).map(move |block| {
+                api.eth().get_block_with(block).map(move |block| {
                     match block {
                         Some(block) => future::Loop::Break(block),
                         None => future::Loop::Continue((time + poll,api)),
@@ -55,6 +72,240 @@ impl<'a,T> Util<'a,T> where T: rpc::Transport<Request,Response> {
     }
 
 
+    /// Load the header of block `hash`, if it is part of the canonical chain.
+    ///
+    /// Waits until the node reaches block `number` (the height at which the block is
+    /// expected), so that a lagging node does not report a canonical block as unknown.
+    /// Resolves to `None` if the node does not know of the block, if the block is not
+    /// at height `number`, or if another block is canonical at that height.
+    ///
+    pub fn await_canonical_block(&self, hash: H256, number: U256, poll: Duration) -> impl Future<Item=Option<Block<H256>>,Error=Error> {
+        let api = self.api();
+        self.await_block_number(number,poll).and_then(move |()| {
+            api.eth().get_block_by_hash(hash).and_then(move |block| {
+                match block.filter(|block| block.number == Some(number)) {
+                    Some(block) => {
+                        let check_canon = api.eth().get_block_by_number(number.into()).map(move |canon| {
+                            let canon = canon.and_then(|b| b.hash);
+                            if canon == Some(hash) { Some(block) } else { None }
+                        });
+                        future::Either::A(check_canon)
+                    },
+                    None => future::Either::B(future::ok(None)),
+                }
+            })
+        })
+    }
+
+    /// Check whether the node is fit to serve requests which depend on current state.
+    ///
+    /// A node is ready if it is not syncing, and the timestamp of its latest block
+    /// is no more than `max_head_age` in the past.  A node which has stalled (e.g.
+    /// lost all peers) will not report itself as syncing, hence the head check.
+    /// Fails if the node reports no latest block, since its state is then unknown.
+    ///
+    pub fn check_ready(&self, max_head_age: Duration) -> impl Future<Item=Readiness,Error=Error> {
+        let get_syncing = self.api().eth().syncing();
+        let get_head = self.api().eth().get_block_by_number(BlockId::Latest);
+        get_syncing.join(get_head).and_then(move |(syncing,head)| {
+            match (syncing,head) {
+                (SyncState::Syncing(progress),_) => Ok(Readiness::Syncing {
+                    current: progress.current_block,
+                    highest: progress.highest_block,
+                }),
+                (SyncState::NotSyncing,Some(head)) => {
+                    let timestamp = util::as_u64(&head.timestamp).unwrap_or(u64::max_value());
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH)
+                        .map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+                    let age = Duration::from_secs(now.saturating_sub(timestamp));
+                    if age > max_head_age {
+                        Ok(Readiness::Stale { age })
+                    } else {
+                        Ok(Readiness::Ready)
+                    }
+                },
+                (SyncState::NotSyncing,None) => Err(missing_block(BlockId::Latest)),
+            }
+        })
+    }
+
+
+    /// Find the first block with a timestamp at or after `timestamp` (seconds since
+    /// the unix epoch).
+    ///
+    /// Performs a binary search over block numbers, relying on block timestamps
+    /// being monotonic.  Requires `O(log n)` block lookups for a chain of `n` blocks.
+    /// Yields `None` if the latest block is older than `timestamp`.
+    ///
+    pub fn find_block_by_time(&self, timestamp: u64) -> impl Future<Item=Option<U256>,Error=Error> {
+        let api = self.api();
+        let get_head = api.eth().get_block_by_number(BlockId::Latest);
+        get_head.and_then(move |head| {
+            let head = head.ok_or_else(|| missing_block(BlockId::Latest))?;
+            let head_number = head.number.as_ref().and_then(|num| util::as_u64(num))
+                .ok_or_else(|| missing_block(BlockId::Latest))?;
+            if block_time(&head) < timestamp {
+                Ok(None)
+            } else {
+                Ok(Some(head_number))
+            }
+        }).and_then(move |head_number| {
+            let head_number = match head_number {
+                Some(num) => num,
+                None => { return future::Either::A(future::ok(None)); },
+            };
+            // invariant: the target block is within `low..=high`
+            let work = future::loop_fn((api,0u64,head_number), move |(api,low,high)| {
+                if low >= high {
+                    return future::Either::A(future::ok(future::Loop::Break(Some(U256::from(low)))));
+                }
+                let mid = low + (high - low) / 2;
+                let target = BlockId::Number(U256::from(mid));
+                let get_block = api.eth().get_block_by_number(target);
+                let work = get_block.and_then(move |block| {
+                    let block = block.ok_or_else(|| missing_block(target))?;
+                    if block_time(&block) < timestamp {
+                        Ok(future::Loop::Continue((api,mid + 1,high)))
+                    } else {
+                        Ok(future::Loop::Continue((api,low,mid)))
+                    }
+                });
+                future::Either::B(work)
+            });
+            future::Either::B(work)
+        })
+    }
+
+    /// Load account & storage proofs via `eth_getProof`, and verify them against
+    /// the state root of `header`.
+    ///
+    /// The proof is requested by the number of `header`, and is only as trustworthy
+    /// as `header` itself; a header loaded from the node serving the proof proves
+    /// nothing.  Headers must come from a trusted source (e.g. a separate trusted
+    /// node, or a light client).  Fails with `Error::Proof` if the proof does not
+    /// match `header`, or is not a proof of `addr` & `keys` (in order).
+    ///
+    pub fn get_verified_proof(&self, addr: Address, keys: Vec<H256>, header: Block<H256>) -> impl Future<Item=AccountProof,Error=Error> {
+        let api = self.api();
+        let number = header.number.ok_or_else(|| -> Error { ProofError::Mismatch("block number").into() });
+        let state_root = header.state_root;
+        number.into_future().and_then(move |number| {
+            api.eth().get_proof(addr,keys.clone(),number.into()).and_then(move |proof| {
+                proof.verify_request(addr,&keys,state_root).map_err(|err| {
+                    warn!("Proof for {} at block {} rejected: {}",addr,number,err);
+                    err
+                })?;
+                Ok(proof)
+            })
+        })
+    }
+
+
+    /// Load the receipts of a block, and verify them against the block's receipts root.
+    ///
+    /// Receipts are loaded by the transaction hashes listed in `header`, and the receipts
+    /// trie rebuilt from their consensus encoding.  If `header` is from a trusted source,
+    /// the logs of the resulting receipts are proven to be included in the block.  Fails
+    /// with `Error::Proof` if the receipts do not match `header`.
+    ///
+    pub fn get_verified_receipts(&self, header: Block<H256>) -> impl Future<Item=Vec<Receipt>,Error=Error> {
+        let api = self.api();
+        let get_receipts: Vec<_> = header.transactions.iter().map(|hash| {
+            api.eth().get_tx_receipt(*hash)
+        }).collect();
+        future::collect(get_receipts).and_then(move |receipts| {
+            let mut verified = Vec::with_capacity(receipts.len());
+            for (receipt,hash) in receipts.into_iter().zip(header.transactions.iter()) {
+                let receipt = receipt.ok_or_else(|| missing_receipt(*hash))?;
+                if receipt.transaction_hash != *hash {
+                    return Err(ProofError::Mismatch("transaction hash").into());
+                }
+                if Some(receipt.block_hash) != header.hash {
+                    return Err(ProofError::Mismatch("block hash").into());
+                }
+                verified.push(receipt);
+            }
+            let root = trie::receipts_root(&verified)?;
+            if root != header.receipts_root {
+                warn!("Receipts for block {:?} do not match receipts root",header.number);
+                return Err(ProofError::Mismatch("receipts root").into());
+            }
+            Ok(verified)
+        })
+    }
+
+
+    /// Wait for a transaction to be buried under `confirmations` blocks.
+    ///
+    /// A transaction with a receipt in block `n` has one confirmation once the current
+    /// block number is `n`, two once it is `n + 1`, etc. (a `confirmations` value of zero
+    /// is treated as one).  Before resolving, the receipt's block hash is checked against the
+    /// canonical block at that height; if the transaction was reorged out, waiting continues
+    /// until it is re-mined or forgotten by the node.  A transaction which the node has seen
+    /// at least once, but which is no longer known (neither mined nor pending), is reported
+    /// as `Confirmation::Dropped`.
+    ///
+    /// Fails with `Error::Timeout` if no outcome is reached within `timeout`.
+    ///
+    pub fn await_receipt(&self, hash: H256, confirmations: u64, timeout: Duration) -> impl Future<Item=Confirmation,Error=Error> {
+        let poll = Duration::from_millis(1024);
+        let depth = U256::from(confirmations.saturating_sub(1));
+        let work = future::loop_fn((Instant::now(),self.api(),ReceiptState::default()), move |(time,api,state)| {
+            timer::Delay::new(time).from_err().and_then(move |()| {
+                let get_receipt = api.eth().get_tx_receipt(hash);
+                let get_latest = api.eth().block_number();
+                get_receipt.join(get_latest).and_then(move |(receipt,latest)| {
+                    match receipt {
+                        Some(receipt) => {
+                            let mut state = state.saw_receipt(hash,&receipt);
+                            let mut current = latest;
+                            let behind = bufmath::sub(&mut current,&receipt.block_number);
+                            if behind || current < depth {
+                                let next = future::Loop::Continue((time + poll,api,state));
+                                future::Either::A(future::ok(next))
+                            } else {
+                                let number = receipt.block_number;
+                                let check_canon = api.eth().get_block_by_number(number.into()).map(move |block| {
+                                    let canon = block.and_then(|b| b.hash);
+                                    if canon == Some(receipt.block_hash) {
+                                        future::Loop::Break(Confirmation::Confirmed(receipt))
+                                    } else {
+                                        warn!("Receipt for {} references non-canonical block {}",hash,receipt.block_hash);
+                                        state.reorged = true;
+                                        future::Loop::Continue((time + poll,api,state))
+                                    }
+                                });
+                                future::Either::B(future::Either::A(check_canon))
+                            }
+                        },
+                        None => {
+                            let check_known = api.eth().get_tx_by_hash(hash).map(move |info| {
+                                let state = state.saw_no_receipt(hash,info.is_some());
+                                if info.is_none() && state.seen {
+                                    future::Loop::Break(Confirmation::Dropped { reorged: state.reorged })
+                                } else {
+                                    future::Loop::Continue((time + poll,api,state))
+                                }
+                            });
+                            future::Either::B(future::Either::B(check_known))
+                        },
+                    }
+                })
+            })
+        });
+        work.timeout(timeout).map_err(move |err| {
+            if err.is_elapsed() {
+                warn!("Timed out waiting for receipt of {}",hash);
+                Error::from(TimedOut)
+            } else if err.is_timer() {
+                Error::from(err.into_timer().expect("timer error must exist"))
+            } else {
+                err.into_inner().expect("inner error must exist")
+            }
+        })
+    }
+
+
     /// Stream blocks in order.
     /// 
     /// Stream may be configured to lag by up to 255 blocks; if so, block `n` will not
@@ -62,6 +313,23 @@ impl<'a,T> Util<'a,T> where T: rpc::Transport<Request,Response> {
     /// for reducing the probability of experiencing a chain reorg.
     ///
     pub fn block_stream(&self, start: U256, poll: Duration, lag: Option<u8>) -> impl Stream<Item=Block<H256>,Error=Error> {
+        self.block_stream_with(start,poll,lag)
+    }
+
+
+    /// Stream blocks in order, with full transaction objects.
+    ///
+    /// Equivalent to `block_stream`, but each block's transactions are loaded in
+    /// the same request, removing the need to call `get_tx_by_hash` for each.
+    ///
+    pub fn full_block_stream(&self, start: U256, poll: Duration, lag: Option<u8>) -> impl Stream<Item=Block<TxInfo>,Error=Error> {
+        self.block_stream_with(start,poll,lag)
+    }
+
+
+    /// Stream blocks in order, loading transactions as `TX`.
+    ///
+    pub fn block_stream_with<TX: BlockTx>(&self, start: U256, poll: Duration, lag: Option<u8>) -> impl Stream<Item=Block<TX>,Error=Error> {
         let mut block = start;
         let api = self.api();
         let mut work = None;
@@ -72,7 +340,7 @@ impl<'a,T> Util<'a,T> where T: rpc::Transport<Request,Response> {
                 let mut not_before = block.clone();
                 add_assign(&mut not_before,lag.unwrap_or(0)); 
                 api.util().await_block_number(not_before,poll).and_then(move |()| {
-                    api.util().await_block(target_block.into(),poll)
+                    api.util().await_block_with(target_block.into(),poll)
                 })
             }).poll();
             match rslt {
@@ -234,6 +502,166 @@ impl LatestLogs {
     }
 }
 
+/// Final outcome of `Util::await_receipt`.
+///
+#[derive(Debug,Clone,PartialEq)]
+pub enum Confirmation {
+    /// Receipt reached the requested depth on the canonical chain
+    Confirmed(Receipt),
+    /// Transaction is no longer known to the node
+    Dropped {
+        /// Whether the transaction was observed in a block which was later reorged out
+        reorged: bool,
+    },
+}
+
+
+impl Confirmation {
+
+    /// Execution status of a confirmed transaction.
+    ///
+    /// Yields `None` for dropped transactions, and for receipts produced by
+    /// pre-byzantium chains (which do not report execution status).
+    ///
+    pub fn status(&self) -> Option<Status> {
+        match self {
+            Confirmation::Confirmed(receipt) => receipt.status,
+            Confirmation::Dropped { .. } => None,
+        }
+    }
+
+    /// Check if this transaction was dropped.
+    ///
+    pub fn is_dropped(&self) -> bool {
+        match self {
+            Confirmation::Confirmed(_) => false,
+            Confirmation::Dropped { .. } => true,
+        }
+    }
+}
+
+
+/// Outcome of `Util::check_ready`.
+///
+#[derive(Debug,Clone,PartialEq)]
+pub enum Readiness {
+    /// Node is synced and its head is recent
+    Ready,
+    /// Node reports that it is syncing
+    Syncing {
+        /// Block the node has synced to
+        current: U256,
+        /// Highest block known to the node
+        highest: U256,
+    },
+    /// Node is not syncing, but its head is too old
+    Stale {
+        /// Age of the latest block
+        age: Duration,
+    },
+}
+
+
+impl Readiness {
+
+    /// Check if the node is ready.
+    ///
+    pub fn is_ready(&self) -> bool {
+        match self {
+            Readiness::Ready => true,
+            _other => false,
+        }
+    }
+}
+
+
+impl fmt::Display for Readiness {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Readiness::Ready => f.write_str("node ready"),
+            Readiness::Syncing { current, highest } => {
+                write!(f,"node syncing (at block {} of {})",current,highest)
+            },
+            Readiness::Stale { age } => {
+                write!(f,"node head is stale (latest block is {}s old)",age.as_secs())
+            },
+        }
+    }
+}
+
+
+/// Tracks what has been observed about a transaction across polls.
+///
+#[derive(Default,Debug,Copy,Clone)]
+struct ReceiptState {
+    /// Transaction has been seen by the node at least once
+    seen: bool,
+    /// Block in which the receipt was last observed
+    block: Option<H256>,
+    /// Transaction was observed in a block which is no longer canonical
+    reorged: bool,
+}
+
+
+impl ReceiptState {
+
+    fn saw_receipt(mut self, hash: H256, receipt: &Receipt) -> Self {
+        if let Some(previous) = self.block {
+            if previous != receipt.block_hash {
+                warn!("Transaction {} moved from block {} to {}",hash,previous,receipt.block_hash);
+                self.reorged = true;
+            }
+        }
+        self.seen = true;
+        self.block = Some(receipt.block_hash);
+        self
+    }
+
+    fn saw_no_receipt(mut self, hash: H256, known: bool) -> Self {
+        if let Some(previous) = self.block.take() {
+            warn!("Transaction {} no longer included in block {}",hash,previous);
+            self.reorged = true;
+        }
+        self.seen = self.seen || known;
+        self
+    }
+}
+
+
+
+/// Timestamp of a block in seconds since the unix epoch.
+///
+fn block_time<T>(block: &Block<T>) -> u64 {
+    util::as_u64(&block.timestamp).unwrap_or(u64::max_value())
+}
+
+
+/// Error for a block which should exist (i.e. is not above the chain head)
+/// but which the node failed to produce.
+///
+fn missing_block(block: BlockId) -> Error {
+    Unexpected {
+        method: "eth_getBlockByNumber",
+        expecting: "block",
+        reason: format!("missing block {}",block),
+        got: Value::Null,
+    }.into()
+}
+
+
+/// Error for a receipt of a transaction which should be included in a
+/// known block, but which the node failed to produce.
+///
+fn missing_receipt(hash: H256) -> Error {
+    Unexpected {
+        method: "eth_getTransactionReceipt",
+        expecting: "receipt",
+        reason: format!("missing receipt for {}",hash),
+        got: Value::Null,
+    }.into()
+}
+
 
 fn add_assign(num: &mut U256, add: u8) {
     let lhs = U256::from(add as u64);
@@ -0,0 +1,602 @@
+//! Contract binding generator.
+//!
+//! Generates rust source for a contract's bindings from its abi json.  The
+//! output is intended to be written to `OUT_DIR` by a build script and
+//! `include!`-ed into a module of the crate which uses it:
+//!
+//! ```ignore
+//! // build.rs
+//! extern crate ethrpc;
+//!
+//! use std::{env,fs};
+//! use std::path::Path;
+//!
+//! fn main() {
+//!     let abi = fs::read_to_string("contracts/HubCulture.abi").unwrap();
+//!     let source = ethrpc::contract::codegen::generate("HubCulture",&abi).unwrap();
+//!     let out = Path::new(&env::var("OUT_DIR").unwrap()).join("hub_culture.rs");
+//!     fs::write(out,source).unwrap();
+//!     println!("cargo:rerun-if-changed=contracts/HubCulture.abi");
+//! }
+//!
+//! // lib.rs
+//! pub mod hub_culture {
+//!     include!(concat!(env!("OUT_DIR"),"/hub_culture.rs"));
+//! }
+//! ```
+//!
+//! The generated module contains a `functions` submodule (one struct per
+//! function), an `events` submodule (one struct per event), and a handle
+//! named after the contract.  See the `contract` module for details.
+//!
+//! ```
+//! extern crate ethrpc;
+//!
+//! use ethrpc::contract::codegen;
+//!
+//! # fn main() {
+//! let abi = r#"[
+//!     {
+//!         "type": "function", "name": "balanceOf", "constant": true,
+//!         "inputs": [{"name": "_owner", "type": "address"}],
+//!         "outputs": [{"name": "", "type": "uint256"}]
+//!     },
+//!     {
+//!         "type": "function", "name": "transferFrom", "constant": false,
+//!         "inputs": [
+//!             {"name": "_from", "type": "address"},
+//!             {"name": "_to", "type": "address"},
+//!             {"name": "_value", "type": "uint256"}
+//!         ],
+//!         "outputs": [{"name": "", "type": "bool"}]
+//!     },
+//!     {
+//!         "type": "event", "name": "Deposit", "anonymous": false,
+//!         "inputs": [
+//!             {"name": "account", "type": "address", "indexed": true},
+//!             {"name": "value", "type": "uint256", "indexed": false}
+//!         ]
+//!     }
+//! ]"#;
+//!
+//! let source = codegen::generate("Token",abi).unwrap();
+//!
+//! assert!(source.contains("pub struct BalanceOf {\n        pub owner: Address,\n    }"));
+//! assert!(source.contains("const SIGNATURE: &'static str = \"transferFrom(address,address,uint256)\";"));
+//! assert!(source.contains("pub fn balance_of(&self, owner: Address, block: BlockId) -> ContractCall<T::Future,U256>"));
+//! assert!(source.contains("pub fn transfer_from(&self, from: Address, to: Address, value: U256) -> TxCall"));
+//! assert!(source.contains("value: decoder.data()?,"));
+//!
+//! // Types without a typed representation are rejected
+//! let unsupported = r#"[{"type": "function", "name": "f", "inputs": [{"name": "x", "type": "string"}]}]"#;
+//! assert!(codegen::generate("Token",unsupported).is_err());
+//! # }
+//! ```
+//!
+use abi::Token;
+use serde_json;
+use std::collections::HashSet;
+use std::fmt::{self,Write};
+use std::error;
+
+
+/// Generate bindings for the contract `name` from its abi json.
+///
+pub fn generate(name: &str, abi: &str) -> Result<String,Error> {
+    if !is_ident(name) || is_keyword(name) {
+        return Err(Error::unsupported(format!("`{}` is not a valid contract name",name)));
+    }
+    let items: Vec<AbiItem> = serde_json::from_str(abi)?;
+    let mut functions = Vec::new();
+    let mut events = Vec::new();
+    for item in items.iter() {
+        match item.kind.as_str() {
+            "function" => functions.push(FunctionDef::new(item)?),
+            "event" => events.push(EventDef::new(item)?),
+            // constructor, fallback, etc. are not callable by name
+            _other => { },
+        }
+    }
+    check_overloads(functions.iter().map(|f| f.name.as_str()))?;
+    check_overloads(events.iter().map(|e| e.name.as_str()))?;
+    let mut out = String::new();
+    emit(&mut out,name,&functions,&events).expect("writing to string cannot fail");
+    Ok(out)
+}
+
+
+/// Indicates failure to generate contract bindings.
+///
+#[derive(Debug)]
+pub enum Error {
+    /// Abi json was malformed
+    Json(serde_json::Error),
+    /// Abi uses a feature which cannot be represented
+    Unsupported(String),
+}
+
+
+impl Error {
+
+    fn unsupported(msg: impl Into<String>) -> Self { Error::Unsupported(msg.into()) }
+}
+
+
+impl From<serde_json::Error> for Error {
+
+    fn from(err: serde_json::Error) -> Self { Error::Json(err) }
+}
+
+
+impl fmt::Display for Error {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Json(err) => write!(f,"invalid abi json: {}",err),
+            Error::Unsupported(msg) => write!(f,"unsupported abi: {}",msg),
+        }
+    }
+}
+
+
+impl error::Error for Error {
+
+    fn description(&self) -> &str {
+        match self {
+            Error::Json(_) => "invalid abi json",
+            Error::Unsupported(_) => "unsupported abi",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match self {
+            Error::Json(err) => Some(err),
+            Error::Unsupported(_) => None,
+        }
+    }
+}
+
+
+#[derive(Debug,Deserialize)]
+struct AbiItem {
+    #[serde(rename = "type",default = "default_kind")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    inputs: Vec<AbiParam>,
+    #[serde(default)]
+    outputs: Vec<AbiParam>,
+    #[serde(default)]
+    constant: bool,
+    #[serde(rename = "stateMutability",default)]
+    state_mutability: Option<String>,
+    #[serde(default)]
+    anonymous: bool,
+}
+
+
+fn default_kind() -> String { "function".into() }
+
+
+#[derive(Debug,Deserialize)]
+struct AbiParam {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    indexed: bool,
+}
+
+
+/// A parameter with a typed representation.
+///
+struct ParamDef {
+    /// Name as it appears in the abi
+    abi_name: String,
+    /// Rust field name
+    field: String,
+    token: Token,
+}
+
+
+struct FunctionDef {
+    name: String,
+    /// Name of the generated struct
+    type_name: String,
+    /// Name of the handle method
+    method: String,
+    inputs: Vec<ParamDef>,
+    /// Rust type of the output(s)
+    output: String,
+    constant: bool,
+}
+
+
+impl FunctionDef {
+
+    fn new(item: &AbiItem) -> Result<Self,Error> {
+        let context = format!("function `{}`",item.name);
+        let inputs = param_defs(&item.inputs,&context)?;
+        let output = output_type(&item.outputs,&context)?;
+        let constant = item.constant || match item.state_mutability.as_ref().map(|s| s.as_str()) {
+            Some("view") | Some("pure") => true,
+            _other => false,
+        };
+        let type_name = camel_case(&item.name)
+            .ok_or_else(|| Error::unsupported(format!("invalid name for {}",context)))?;
+        let method = match snake_case(&item.name) {
+            Some(ref method) if method == "new" || method == "address" => format!("{}_",method),
+            Some(method) => method,
+            None => { return Err(Error::unsupported(format!("invalid name for {}",context))); },
+        };
+        Ok(Self { name: item.name.clone(), type_name, method, inputs, output, constant })
+    }
+
+    fn signature(&self) -> String { signature(&self.name,&self.inputs) }
+}
+
+
+struct EventDef {
+    name: String,
+    type_name: String,
+    inputs: Vec<(ParamDef,bool)>,
+}
+
+
+impl EventDef {
+
+    fn new(item: &AbiItem) -> Result<Self,Error> {
+        let context = format!("event `{}`",item.name);
+        if item.anonymous {
+            return Err(Error::unsupported(format!("{} is anonymous",context)));
+        }
+        let params = param_defs(&item.inputs,&context)?;
+        let inputs = params.into_iter().zip(item.inputs.iter())
+            .map(|(def,param)| (def,param.indexed))
+            .collect();
+        let type_name = camel_case(&item.name)
+            .ok_or_else(|| Error::unsupported(format!("invalid name for {}",context)))?;
+        Ok(Self { name: item.name.clone(), type_name, inputs })
+    }
+
+    fn signature(&self) -> String {
+        let params: Vec<_> = self.inputs.iter().map(|(param,_)| param.token.as_str()).collect();
+        format!("{}({})",self.name,params.join(","))
+    }
+}
+
+
+fn param_defs(params: &[AbiParam], context: &str) -> Result<Vec<ParamDef>,Error> {
+    // `block` is reserved for the block argument of constant calls
+    let mut seen: HashSet<String> = ["block".to_owned()].iter().cloned().collect();
+    let mut defs = Vec::with_capacity(params.len());
+    for (index,param) in params.iter().enumerate() {
+        let token: Token = param.kind.parse().map_err(|_| {
+            Error::unsupported(format!("type `{}` of param {} of {}",param.kind,index,context))
+        })?;
+        let field = match snake_case(&param.name) {
+            Some(ref field) if !seen.contains(field) => field.to_owned(),
+            _other => format!("arg{}",index),
+        };
+        seen.insert(field.clone());
+        defs.push(ParamDef { abi_name: param.name.clone(), field, token });
+    }
+    Ok(defs)
+}
+
+
+fn output_type(outputs: &[AbiParam], context: &str) -> Result<String,Error> {
+    let mut types = Vec::with_capacity(outputs.len());
+    for param in outputs.iter() {
+        let rust_type = match param.kind.as_str() {
+            "bool" => "bool",
+            "string" if outputs.len() == 1 => "String",
+            other => match other.parse::<Token>() {
+                Ok(token) => rust_type(token),
+                // narrower uints decode without loss as `U256`
+                Err(_) if is_uint(other) => "U256",
+                Err(_) => {
+                    return Err(Error::unsupported(format!("output type `{}` of {}",other,context)));
+                },
+            },
+        };
+        types.push(rust_type);
+    }
+    match types.len() {
+        0 => Ok("()".into()),
+        1 => Ok(types[0].into()),
+        2..=4 => Ok(format!("({})",types.join(","))),
+        count => Err(Error::unsupported(format!("{} outputs of {}",count,context))),
+    }
+}
+
+
+fn check_overloads<'a>(names: impl Iterator<Item=&'a str>) -> Result<(),Error> {
+    let mut seen = HashSet::new();
+    for name in names {
+        if !seen.insert(name) {
+            return Err(Error::unsupported(format!("overloaded name `{}`",name)));
+        }
+    }
+    Ok(())
+}
+
+
+fn emit(out: &mut String, name: &str, functions: &[FunctionDef], events: &[EventDef]) -> fmt::Result {
+    writeln!(out,"// Bindings for the `{}` contract.",name)?;
+    writeln!(out,"//")?;
+    writeln!(out,"// Generated by `ethrpc::contract::codegen`; do not edit.")?;
+    writeln!(out,"#[allow(unused_imports)]")?;
+    writeln!(out,"use ethrpc::abi::{{Event,Function,Token,Value}};")?;
+    writeln!(out,"#[allow(unused_imports)]")?;
+    writeln!(out,"use ethrpc::api::{{Api,Request,Response}};")?;
+    writeln!(out,"#[allow(unused_imports)]")?;
+    writeln!(out,"use ethrpc::contract::{{self,Contract,ContractCall,FunctionCall,LogDecoder}};")?;
+    writeln!(out,"#[allow(unused_imports)]")?;
+    writeln!(out,"use ethrpc::crypto::Address;")?;
+    writeln!(out,"#[allow(unused_imports)]")?;
+    writeln!(out,"use ethrpc::types::{{BlockId,Bytes,Log,TxCall,Uint8,U256,H256}};")?;
+    writeln!(out,"#[allow(unused_imports)]")?;
+    writeln!(out,"use ethrpc::Transport;")?;
+    writeln!(out)?;
+    emit_handle(out,name,functions)?;
+    writeln!(out)?;
+    writeln!(out,"/// Calls to the functions of `{}`.",name)?;
+    writeln!(out,"pub mod functions {{")?;
+    writeln!(out,"    use super::*;")?;
+    for function in functions.iter() {
+        writeln!(out)?;
+        emit_function(out,function)?;
+    }
+    writeln!(out,"}}")?;
+    writeln!(out)?;
+    writeln!(out,"/// Events emitted by `{}`.",name)?;
+    writeln!(out,"pub mod events {{")?;
+    writeln!(out,"    use super::*;")?;
+    for event in events.iter() {
+        writeln!(out)?;
+        emit_event(out,event)?;
+    }
+    writeln!(out,"}}")?;
+    Ok(())
+}
+
+
+fn emit_handle(out: &mut String, name: &str, functions: &[FunctionDef]) -> fmt::Result {
+    writeln!(out,"/// Handle to a deployed `{}` contract.",name)?;
+    writeln!(out,"#[derive(Debug,Clone)]")?;
+    writeln!(out,"pub struct {}<T> {{",name)?;
+    writeln!(out,"    inner: Contract<T>,")?;
+    writeln!(out,"}}")?;
+    writeln!(out)?;
+    writeln!(out,"impl<T> {}<T> {{",name)?;
+    writeln!(out)?;
+    writeln!(out,"    pub fn new(api: Api<T>, address: Address) -> Self {{")?;
+    writeln!(out,"        Self {{ inner: Contract::new(api,address) }}")?;
+    writeln!(out,"    }}")?;
+    writeln!(out)?;
+    writeln!(out,"    pub fn address(&self) -> Address {{ self.inner.address() }}")?;
+    for function in functions.iter().filter(|f| !f.constant) {
+        writeln!(out)?;
+        writeln!(out,"    /// Build a transaction which calls `{}`.",function.signature())?;
+        writeln!(out,"    pub fn {}(&self{}) -> TxCall {{",function.method,arg_list(&function.inputs))?;
+        writeln!(out,"        self.inner.transact({})",struct_literal(function))?;
+        writeln!(out,"    }}")?;
+    }
+    writeln!(out,"}}")?;
+    writeln!(out)?;
+    writeln!(out,"impl<T> {}<T> where T: Transport<Request,Response> {{",name)?;
+    for function in functions.iter().filter(|f| f.constant) {
+        writeln!(out)?;
+        writeln!(out,"    /// Call `{}` at the specified block.",function.signature())?;
+        writeln!(out,"    pub fn {}(&self{}, block: BlockId) -> ContractCall<T::Future,{}> {{",
+            function.method,arg_list(&function.inputs),function.output)?;
+        writeln!(out,"        self.inner.call({},block)",struct_literal(function))?;
+        writeln!(out,"    }}")?;
+    }
+    writeln!(out,"}}")?;
+    Ok(())
+}
+
+
+fn emit_function(out: &mut String, function: &FunctionDef) -> fmt::Result {
+    let name = &function.type_name;
+    writeln!(out,"    /// Call to `{}`.",function.signature())?;
+    writeln!(out,"    #[derive(Debug,Clone,PartialEq,Eq)]")?;
+    writeln!(out,"    pub struct {} {{",name)?;
+    for param in function.inputs.iter() {
+        writeln!(out,"        pub {}: {},",param.field,rust_type(param.token))?;
+    }
+    writeln!(out,"    }}")?;
+    writeln!(out)?;
+    writeln!(out,"    impl {} {{",name)?;
+    writeln!(out)?;
+    writeln!(out,"        /// Encode calldata for this call.")?;
+    writeln!(out,"        pub fn encode(&self) -> Bytes {{ FunctionCall::encode(self) }}")?;
+    writeln!(out,"    }}")?;
+    writeln!(out)?;
+    writeln!(out,"    impl FunctionCall for {} {{",name)?;
+    writeln!(out)?;
+    writeln!(out,"        type Output = {};",function.output)?;
+    writeln!(out)?;
+    writeln!(out,"        const SIGNATURE: &'static str = {:?};",function.signature())?;
+    writeln!(out)?;
+    let specs: Vec<_> = function.inputs.iter().map(|param| {
+        format!("({:?},{})",param.abi_name,token_path(param.token))
+    }).collect();
+    writeln!(out,"        fn function() -> Function {{")?;
+    writeln!(out,"            contract::function({:?},&[{}])",function.name,specs.join(","))?;
+    writeln!(out,"        }}")?;
+    writeln!(out)?;
+    let args: Vec<_> = function.inputs.iter().map(|param| {
+        format!("self.{}.into()",param.field)
+    }).collect();
+    writeln!(out,"        fn args(&self) -> Vec<Value> {{ vec![{}] }}",args.join(","))?;
+    writeln!(out,"    }}")?;
+    Ok(())
+}
+
+
+fn emit_event(out: &mut String, event: &EventDef) -> fmt::Result {
+    let name = &event.type_name;
+    let indexed = event.inputs.iter().filter(|(_,indexed)| *indexed).count();
+    let data_words = event.inputs.len() - indexed;
+    writeln!(out,"    /// The `{}` event.",event.signature())?;
+    writeln!(out,"    #[derive(Debug,Clone,PartialEq,Eq)]")?;
+    writeln!(out,"    pub struct {} {{",name)?;
+    for (param,_) in event.inputs.iter() {
+        writeln!(out,"        pub {}: {},",param.field,rust_type(param.token))?;
+    }
+    writeln!(out,"    }}")?;
+    writeln!(out)?;
+    writeln!(out,"    impl {} {{",name)?;
+    writeln!(out)?;
+    writeln!(out,"        pub const SIGNATURE: &'static str = {:?};",event.signature())?;
+    writeln!(out)?;
+    let specs: Vec<_> = event.inputs.iter().map(|(param,indexed)| {
+        format!("({:?},{},{})",param.abi_name,token_path(param.token),indexed)
+    }).collect();
+    writeln!(out,"        /// Specification of this event.")?;
+    writeln!(out,"        pub fn event() -> Event {{")?;
+    writeln!(out,"            contract::event({:?},&[{}])",event.name,specs.join(","))?;
+    writeln!(out,"        }}")?;
+    writeln!(out)?;
+    writeln!(out,"        /// Decode a log as this event (`None` if the log does not match).")?;
+    writeln!(out,"        pub fn from_log(log: &Log) -> Option<Self> {{")?;
+    writeln!(out,"            let mut decoder = LogDecoder::new(log,Self::event().signature(),{},{})?;",indexed,data_words)?;
+    writeln!(out,"            Some(Self {{")?;
+    for (param,indexed) in event.inputs.iter() {
+        let source = if *indexed { "indexed" } else { "data" };
+        writeln!(out,"                {}: decoder.{}()?,",param.field,source)?;
+    }
+    writeln!(out,"            }})")?;
+    writeln!(out,"        }}")?;
+    writeln!(out,"    }}")?;
+    Ok(())
+}
+
+
+fn arg_list(params: &[ParamDef]) -> String {
+    params.iter().map(|param| {
+        format!(", {}: {}",param.field,rust_type(param.token))
+    }).collect()
+}
+
+
+fn struct_literal(function: &FunctionDef) -> String {
+    let fields: Vec<_> = function.inputs.iter().map(|param| param.field.as_str()).collect();
+    if fields.is_empty() {
+        format!("functions::{} {{}}",function.type_name)
+    } else {
+        format!("functions::{} {{ {} }}",function.type_name,fields.join(", "))
+    }
+}
+
+
+fn signature(name: &str, params: &[ParamDef]) -> String {
+    let params: Vec<_> = params.iter().map(|param| param.token.as_str()).collect();
+    format!("{}({})",name,params.join(","))
+}
+
+
+fn rust_type(token: Token) -> &'static str {
+    match token {
+        Token::Addr => "Address",
+        Token::Uint8 => "Uint8",
+        Token::Uint => "U256",
+        Token::Hash => "H256",
+    }
+}
+
+
+fn token_path(token: Token) -> &'static str {
+    match token {
+        Token::Addr => "Token::Addr",
+        Token::Uint8 => "Token::Uint8",
+        Token::Uint => "Token::Uint",
+        Token::Hash => "Token::Hash",
+    }
+}
+
+
+fn is_uint(kind: &str) -> bool {
+    kind.starts_with("uint") && match kind["uint".len()..].parse::<u16>() {
+        Ok(bits) => bits > 0 && bits <= 256 && bits % 8 == 0,
+        Err(_) => false,
+    }
+}
+
+
+/// Convert an abi name to `snake_case` (leading/trailing underscores removed).
+///
+fn snake_case(name: &str) -> Option<String> {
+    let chars: Vec<char> = name.trim_matches('_').chars().collect();
+    let mut out = String::with_capacity(chars.len() + 4);
+    for (index,c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && index > 0 {
+            let prev = chars[index - 1];
+            let next_lower = chars.get(index + 1).map(|n| n.is_ascii_lowercase()).unwrap_or(false);
+            if prev.is_ascii_lowercase() || prev.is_ascii_digit() || (prev.is_ascii_uppercase() && next_lower) {
+                out.push('_');
+            }
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    if !is_ident(&out) {
+        None
+    } else if is_keyword(&out) {
+        Some(format!("{}_",out))
+    } else {
+        Some(out)
+    }
+}
+
+
+/// Convert an abi name to `CamelCase`.
+///
+fn camel_case(name: &str) -> Option<String> {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = true;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    if !is_ident(&out) {
+        None
+    } else if is_keyword(&out) {
+        Some(format!("{}_",out))
+    } else {
+        Some(out)
+    }
+}
+
+
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            s != "_" && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        },
+        _other => false,
+    }
+}
+
+
+fn is_keyword(s: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "abstract","alignof","as","async","await","become","box","break","const","continue",
+        "crate","do","dyn","else","enum","extern","false","final","fn","for","if","impl","in",
+        "let","loop","macro","match","mod","move","mut","offsetof","override","priv","proc",
+        "pub","pure","ref","return","self","Self","sizeof","static","struct","super","trait",
+        "true","try","type","typeof","unsafe","unsized","use","virtual","where","while","yield",
+    ];
+    KEYWORDS.contains(&s)
+}
@@ -0,0 +1,410 @@
+//! Typed contract bindings.
+//!
+//! Bindings are generated from abi json by `codegen::generate` (typically
+//! from a build script), and rely on the runtime support in this module:
+//!
+//! - Each function becomes a struct implementing `FunctionCall`, whose
+//! fields are the function's inputs.
+//! - Each event becomes a struct with a `from_log` constructor.
+//! - The contract itself becomes a handle wrapping `Contract`, with a
+//! method per function.  Constant functions are executed via `eth_call`
+//! and their outputs decoded; all others produce a `TxCall`.
+//!
+//! ## Example
+//!
+//! ```
+//! extern crate ethrpc;
+//!
+//! use ethrpc::contract::{self,FunctionCall,Output};
+//! use ethrpc::abi::{Function,Token,Value};
+//! use ethrpc::types::U256;
+//! use ethrpc::crypto::Address;
+//!
+//! // Equivalent to the code generated for `transfer(address,uint256)`
+//! struct Transfer {
+//!     to: Address,
+//!     value: U256,
+//! }
+//!
+//! impl FunctionCall for Transfer {
+//!
+//!     type Output = bool;
+//!
+//!     const SIGNATURE: &'static str = "transfer(address,uint256)";
+//!
+//!     fn function() -> Function {
+//!         contract::function("transfer",&[("to",Token::Addr),("value",Token::Uint)])
+//!     }
+//!
+//!     fn args(&self) -> Vec<Value> { vec![self.to.into(),self.value.into()] }
+//! }
+//!
+//! # fn main() {
+//! let call = Transfer {
+//!     to: "0x00000000000000000000000000000000deadbeef".parse().unwrap(),
+//!     value: "0x3e8".parse().unwrap(),
+//! };
+//!
+//! assert_eq!(&call.encode()[..4],&[0xa9,0x05,0x9c,0xbb]);
+//!
+//! let returned: [u8;32] = {
+//!     let mut word = [0u8;32];
+//!     word[31] = 1;
+//!     word
+//! };
+//! assert_eq!(bool::decode(&returned),Ok(true));
+//! # }
+//! ```
+//!
+use types::{Bytes,BlockId,Log,TxCall,Uint8,U256,H256};
+use abi::{Function,FunctionParam,Event,EventParam,Token,Value};
+use api::error::Unexpected;
+use api::{Api,AsyncRpc,Request,Response,Error};
+use crypto::Address;
+use util;
+use rpc;
+use serde_json::Value as Json;
+use tokio::prelude::*;
+use std::marker::PhantomData;
+
+pub mod codegen;
+
+
+/// A typed function call.
+///
+pub trait FunctionCall {
+
+    /// Decoded return value(s) of the function.
+    ///
+    type Output: Output;
+
+    /// Canonical signature (e.g. `transfer(address,uint256)`).
+    ///
+    const SIGNATURE: &'static str;
+
+    /// Specification of the function.
+    ///
+    fn function() -> Function;
+
+    /// Arguments in declaration order.
+    ///
+    fn args(&self) -> Vec<Value>;
+
+    /// Encode calldata for this call.
+    ///
+    fn encode(&self) -> Bytes {
+        Self::function().encode(&self.args())
+            .expect("generated args must match function spec")
+    }
+}
+
+
+/// Handle to a deployed contract.
+///
+#[derive(Debug,Clone)]
+pub struct Contract<T> {
+    api: Api<T>,
+    address: Address,
+}
+
+
+impl<T> Contract<T> {
+
+    pub fn new(api: Api<T>, address: Address) -> Self { Self { api, address } }
+
+    /// Address of the contract.
+    ///
+    pub fn address(&self) -> Address { self.address }
+
+    /// Build a transaction which executes `call`.
+    ///
+    pub fn transact<F: FunctionCall>(&self, call: F) -> TxCall {
+        tx_call(self.address,call.encode())
+    }
+}
+
+
+impl<T> Contract<T> where T: rpc::Transport<Request,Response> {
+
+    /// Execute `call` via `eth_call` & decode its output.
+    ///
+    pub fn call<F: FunctionCall>(&self, call: F, block: BlockId) -> ContractCall<T::Future,F::Output> {
+        let tx = self.transact(call);
+        let inner = self.api.eth().call(tx,block);
+        ContractCall { inner, signature: F::SIGNATURE, output: PhantomData }
+    }
+}
+
+
+/// A pending `eth_call`, resolving to the decoded output.
+///
+pub struct ContractCall<F,O> {
+    inner: AsyncRpc<F,Bytes>,
+    signature: &'static str,
+    output: PhantomData<O>,
+}
+
+
+impl<F,O> Future for ContractCall<F,O> where F: Future<Item=rpc::Result<Response>>, F::Error: rpc::TransportError, O: Output {
+
+    type Item = O;
+
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item,Self::Error> {
+        let data = try_ready!(self.inner.poll());
+        let output = O::decode(&data).map_err(|reason| {
+            warn!("Unexpected output for `{}`: {}",self.signature,reason);
+            unexpected(format!("{}: {}",self.signature,reason),&data)
+        })?;
+        Ok(Async::Ready(output))
+    }
+}
+
+
+/// A value which occupies a single abi word.
+///
+pub trait Word: Sized {
+
+    fn from_word(word: &[u8]) -> Result<Self,String>;
+}
+
+
+impl Word for U256 {
+
+    fn from_word(word: &[u8]) -> Result<Self,String> {
+        let mut buf = [0u8;32];
+        buf.copy_from_slice(word);
+        Ok(U256::from(buf))
+    }
+}
+
+
+impl Word for H256 {
+
+    fn from_word(word: &[u8]) -> Result<Self,String> {
+        let mut buf = [0u8;32];
+        buf.copy_from_slice(word);
+        Ok(H256::from(buf))
+    }
+}
+
+
+impl Word for Address {
+
+    fn from_word(word: &[u8]) -> Result<Self,String> {
+        let mut addr = Address::default();
+        let offset = word.len() - addr.len();
+        if util::trim(&word[..offset]).is_empty() {
+            addr.copy_from_slice(&word[offset..]);
+            Ok(addr)
+        } else {
+            Err("address word has non-zero padding".into())
+        }
+    }
+}
+
+
+impl Word for Uint8 {
+
+    fn from_word(word: &[u8]) -> Result<Self,String> {
+        match util::trim(word) {
+            [] => Ok(Uint8::from(0u8)),
+            [byte] => Ok(Uint8::from(*byte)),
+            _other => Err("value exceeds uint8".into()),
+        }
+    }
+}
+
+
+impl Word for bool {
+
+    fn from_word(word: &[u8]) -> Result<Self,String> {
+        match util::trim(word) {
+            [] => Ok(false),
+            [1] => Ok(true),
+            _other => Err("invalid bool word".into()),
+        }
+    }
+}
+
+
+/// Return value(s) of a function.
+///
+pub trait Output: Sized {
+
+    fn decode(data: &[u8]) -> Result<Self,String>;
+}
+
+
+impl Output for () {
+
+    fn decode(_: &[u8]) -> Result<Self,String> { Ok(()) }
+}
+
+
+/// Decodes an abi-encoded `string`.  Null-padded `bytes32` values are also
+/// accepted, since some older contracts (e.g. MKR) return those instead.
+///
+impl Output for String {
+
+    fn decode(data: &[u8]) -> Result<Self,String> {
+        let bytes = if data.len() == 32 {
+            let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+            &data[..end]
+        } else {
+            let offset = decode_len(data,0)?;
+            let len = decode_len(data,offset)?;
+            let start = offset + 32;
+            data.get(start..start + len).ok_or_else(|| {
+                format!("string of length {} exceeds return data",len)
+            })?
+        };
+        String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string())
+    }
+}
+
+
+macro_rules! impl_output_word {
+    ($($word:ty),*) => {
+        $(
+            impl Output for $word {
+
+                fn decode(data: &[u8]) -> Result<Self,String> { word_at(data,0) }
+            }
+        )*
+    }
+}
+
+impl_output_word!(U256,H256,Address,Uint8,bool);
+
+
+macro_rules! impl_output_tuple {
+    ($($word:ident => $index:expr),*) => {
+        impl<$($word: Word),*> Output for ($($word,)*) {
+
+            fn decode(data: &[u8]) -> Result<Self,String> {
+                Ok(($(word_at::<$word>(data,$index)?,)*))
+            }
+        }
+    }
+}
+
+impl_output_tuple!(A => 0, B => 1);
+impl_output_tuple!(A => 0, B => 1, C => 2);
+impl_output_tuple!(A => 0, B => 1, C => 2, D => 3);
+
+
+/// Cursor over the indexed topics & non-indexed data words of an event.
+///
+#[derive(Debug,Clone)]
+pub struct LogDecoder<'a> {
+    topics: &'a [H256],
+    data: &'a [u8],
+}
+
+
+impl<'a> LogDecoder<'a> {
+
+    /// Check that `log` matches the shape of the event with the given signature,
+    /// and prepare to decode its fields.
+    ///
+    pub fn new(log: &'a Log, signature: H256, indexed: usize, data_words: usize) -> Option<Self> {
+        let matches = log.topics.len() == indexed + 1
+            && log.topics[0] == signature
+            && log.data.len() == data_words * 32;
+        if matches {
+            Some(Self { topics: &log.topics[1..], data: &log.data[..] })
+        } else {
+            None
+        }
+    }
+
+    /// Decode the next indexed field.
+    ///
+    pub fn indexed<W: Word>(&mut self) -> Option<W> {
+        let (topic,rest) = self.topics.split_first()?;
+        self.topics = rest;
+        W::from_word(topic.as_ref()).ok()
+    }
+
+    /// Decode the next non-indexed field.
+    ///
+    pub fn data<W: Word>(&mut self) -> Option<W> {
+        if self.data.len() < 32 {
+            return None;
+        }
+        let (word,rest) = self.data.split_at(32);
+        self.data = rest;
+        W::from_word(word).ok()
+    }
+}
+
+
+/// Build a function specification.
+///
+pub fn function(name: &str, inputs: &[(&str,Token)]) -> Function {
+    let params = inputs.iter().map(|(name,kind)| {
+        FunctionParam { name: (*name).to_owned(), kind: *kind }
+    }).collect();
+    Function::from((name.to_owned(),params))
+}
+
+
+/// Build an event specification.
+///
+pub fn event(name: &str, inputs: &[(&str,Token,bool)]) -> Event {
+    let params = inputs.iter().map(|(name,kind,indexed)| {
+        EventParam { name: (*name).to_owned(), kind: *kind, indexed: *indexed }
+    }).collect();
+    Event::from((name.to_owned(),params))
+}
+
+
+/// Build a transaction-call with the specified calldata.
+///
+pub fn tx_call(to: Address, data: Bytes) -> TxCall {
+    TxCall {
+        nonce: None,
+        gas_price: None,
+        gas_limit: None,
+        from: None,
+        to,
+        value: None,
+        data: Some(data),
+    }
+}
+
+
+/// Error for `eth_call` output which could not be decoded.
+///
+pub(crate) fn unexpected(reason: String, got: &[u8]) -> Error {
+    Unexpected {
+        method: "eth_call",
+        expecting: "abi-encoded function output",
+        reason,
+        got: Json::String(Bytes::from(got.to_vec()).to_string()),
+    }.into()
+}
+
+
+fn word_at<W: Word>(data: &[u8], index: usize) -> Result<W,String> {
+    let start = index * 32;
+    let word = data.get(start..start + 32).ok_or_else(|| {
+        format!("expected at least {} bytes, got {}",start + 32,data.len())
+    })?;
+    W::from_word(word)
+}
+
+
+/// Decode the word at `at` as an offset/length within `data`.
+///
+fn decode_len(data: &[u8], at: usize) -> Result<usize,String> {
+    let word = data.get(at..at + 32).ok_or_else(|| {
+        format!("return data truncated at {} bytes",data.len())
+    })?;
+    match util::as_u64(word) {
+        Some(len) if len <= data.len() as u64 => Ok(len as usize),
+        _other => Err(format!("invalid offset or length (return data is {} bytes)",data.len())),
+    }
+}
@@ -0,0 +1,339 @@
+//! Typed ERC-20 token client.
+//!
+//! ## Example
+//!
+//! Query a token balance:
+//!
+//! ```
+//! extern crate ethrpc;
+//! extern crate tokio;
+//!
+//! use ethrpc::types::BlockId;
+//! use tokio::prelude::*;
+//!
+//! # fn example() {
+//!
+//! let token = "0x00a329c0648769a73afac7f9381e08fb43dbea72".parse().unwrap();
+//! let owner = "0x00000000000000000000000000000000deadbeef".parse().unwrap();
+//!
+//! let work = ethrpc::autoconnect().and_then(move |api| {
+//!     let erc20 = api.erc20(token);
+//!     let get_balance = erc20.balance_of(owner,BlockId::Latest);
+//!     let get_decimals = erc20.decimals(BlockId::Latest);
+//!     get_balance.join(get_decimals).map(|(balance,decimals)| {
+//!         println!("Balance: {} ({} decimals)",balance,decimals);
+//!     })
+//! }).map_err(drop);
+//!
+//! tokio::run(work);
+//!
+//! # }
+//! # fn main() { }
+//! ```
+//!
+//! Build transactions & decode logs:
+//!
+//! ```
+//! #[macro_use]
+//! extern crate serde_json;
+//! extern crate ethrpc;
+//!
+//! use ethrpc::erc20::{self,TokenEvent};
+//! use ethrpc::types::Log;
+//!
+//! # fn main() {
+//!
+//! let token = "0x00a329c0648769a73afac7f9381e08fb43dbea72".parse().unwrap();
+//! let recipient = "0x00000000000000000000000000000000deadbeef".parse().unwrap();
+//!
+//! let tx = erc20::transfer(token,recipient,"0x3e8".parse().unwrap());
+//!
+//! let expected = "0xa9059cbb\
+//! 00000000000000000000000000000000000000000000000000000000deadbeef\
+//! 00000000000000000000000000000000000000000000000000000000000003e8";
+//!
+//! assert_eq!(tx.to,token);
+//! assert_eq!(tx.data.unwrap().to_string(),expected);
+//!
+//! let log: Log = serde_json::from_value(json!({
+//!     "address": "0x00a329c0648769a73afac7f9381e08fb43dbea72",
+//!     "topics": [
+//!         "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+//!         "0x000000000000000000000000000000000000000000000000000000000000beef",
+//!         "0x00000000000000000000000000000000000000000000000000000000deadbeef",
+//!     ],
+//!     "data": "0x00000000000000000000000000000000000000000000000000000000000003e8",
+//! })).unwrap();
+//!
+//! assert_eq!(log.topics[0],erc20::transfer_event().signature());
+//!
+//! match TokenEvent::from_log(&log) {
+//!     Some(TokenEvent::Transfer(transfer)) => {
+//!         assert_eq!(transfer.token,token);
+//!         assert_eq!(transfer.to,recipient);
+//!         assert_eq!(transfer.value,"0x3e8".parse().unwrap());
+//!     },
+//!     other => panic!("unexpected decoding: {:?}",other),
+//! }
+//! # }
+//! ```
+//!
+use types::{Bytes,BlockId,Log,TxCall,Uint8,U256};
+use abi::{Function,Event,Token,Value};
+use contract::{self,Output};
+use api::error::Unexpected;
+use api::{Request,Response,Error,Eth};
+use crypto::Address;
+use rpc;
+use serde_json::Value as Json;
+use tokio::prelude::*;
+
+
+/// The ERC-20 contract interface of a specific token.
+///
+/// Calls are executed via `eth_call` against the specified block.
+///
+pub struct Erc20<'a,T: 'a> {
+    transport: &'a T,
+    token: Address,
+}
+
+
+impl<'a,T> Erc20<'a,T> {
+
+    pub fn new(transport: &'a T, token: Address) -> Self { Self { transport, token } }
+
+    /// Address of the token contract.
+    ///
+    pub fn token(&self) -> Address { self.token }
+}
+
+
+impl<'a,T> Erc20<'a,T> where T: rpc::Transport<Request,Response> {
+
+    /// Equivalent to the `balanceOf` method.
+    ///
+    pub fn balance_of(&self, owner: Address, block: BlockId) -> impl Future<Item=U256,Error=Error> {
+        let data = encode(&balance_of_fn(),&[owner.into()]);
+        self.call(data,block).and_then(|rsp| {
+            decode_uint(&rsp).map_err(|reason| unexpected("`balanceOf` result (uint256)",reason,&rsp))
+        })
+    }
+
+    /// Equivalent to the `totalSupply` method.
+    ///
+    pub fn total_supply(&self, block: BlockId) -> impl Future<Item=U256,Error=Error> {
+        let data = encode(&total_supply_fn(),&[]);
+        self.call(data,block).and_then(|rsp| {
+            decode_uint(&rsp).map_err(|reason| unexpected("`totalSupply` result (uint256)",reason,&rsp))
+        })
+    }
+
+    /// Equivalent to the `allowance` method.
+    ///
+    pub fn allowance(&self, owner: Address, spender: Address, block: BlockId) -> impl Future<Item=U256,Error=Error> {
+        let data = encode(&allowance_fn(),&[owner.into(),spender.into()]);
+        self.call(data,block).and_then(|rsp| {
+            decode_uint(&rsp).map_err(|reason| unexpected("`allowance` result (uint256)",reason,&rsp))
+        })
+    }
+
+    /// Equivalent to the `decimals` method.
+    ///
+    pub fn decimals(&self, block: BlockId) -> impl Future<Item=u8,Error=Error> {
+        let data = encode(&decimals_fn(),&[]);
+        self.call(data,block).and_then(|rsp| {
+            decode_uint8(&rsp).map_err(|reason| unexpected("`decimals` result (uint8)",reason,&rsp))
+        })
+    }
+
+    /// Equivalent to the `symbol` method.
+    ///
+    /// Tokens which predate the standard and return `bytes32` (e.g. MKR) are
+    /// also supported.
+    ///
+    pub fn symbol(&self, block: BlockId) -> impl Future<Item=String,Error=Error> {
+        let data = encode(&symbol_fn(),&[]);
+        self.call(data,block).and_then(|rsp| {
+            decode_string(&rsp).map_err(|reason| unexpected("`symbol` result (string)",reason,&rsp))
+        })
+    }
+
+    fn call(&self, data: Bytes, block: BlockId) -> impl Future<Item=Bytes,Error=Error> {
+        let call = TxCall {
+            nonce: None,
+            gas_price: None,
+            gas_limit: None,
+            from: None,
+            to: self.token,
+            value: None,
+            data: Some(data),
+        };
+        Eth::new(self.transport).call(call,block)
+    }
+}
+
+
+/// Build a `transfer` transaction.
+///
+pub fn transfer(token: Address, to: Address, amount: U256) -> TxCall {
+    let data = encode(&transfer_fn(),&[to.into(),amount.into()]);
+    contract::tx_call(token,data)
+}
+
+
+/// Build an `approve` transaction.
+///
+pub fn approve(token: Address, spender: Address, amount: U256) -> TxCall {
+    let data = encode(&approve_fn(),&[spender.into(),amount.into()]);
+    contract::tx_call(token,data)
+}
+
+
+/// Build a `transferFrom` transaction.
+///
+pub fn transfer_from(token: Address, from: Address, to: Address, amount: U256) -> TxCall {
+    let data = encode(&transfer_from_fn(),&[from.into(),to.into(),amount.into()]);
+    contract::tx_call(token,data)
+}
+
+
+/// Specification of the `Transfer(address,address,uint256)` event.
+///
+pub fn transfer_event() -> Event {
+    contract::event("Transfer",&[("from",Token::Addr,true),("to",Token::Addr,true),("value",Token::Uint,false)])
+}
+
+
+/// Specification of the `Approval(address,address,uint256)` event.
+///
+pub fn approval_event() -> Event {
+    contract::event("Approval",&[("owner",Token::Addr,true),("spender",Token::Addr,true),("value",Token::Uint,false)])
+}
+
+
+/// An ERC-20 event.
+///
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum TokenEvent {
+    Transfer(Transfer),
+    Approval(Approval),
+}
+
+
+impl TokenEvent {
+
+    /// Decode a log as an ERC-20 event.
+    ///
+    /// Yields `None` if the log is not a well-formed `Transfer` or `Approval`
+    /// event.  Note that ERC-721 `Transfer` events share a signature with their
+    /// ERC-20 counterpart, but are rejected since they index the token id.
+    ///
+    pub fn from_log(log: &Log) -> Option<Self> {
+        Transfer::from_log(log).map(TokenEvent::Transfer)
+            .or_else(|| Approval::from_log(log).map(TokenEvent::Approval))
+    }
+}
+
+
+/// A decoded `Transfer` event.
+///
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub struct Transfer {
+    /// Token contract which emitted the event
+    pub token: Address,
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+}
+
+
+impl Transfer {
+
+    /// Decode a log as a `Transfer` event.
+    ///
+    pub fn from_log(log: &Log) -> Option<Self> {
+        let (from,to,value) = decode_log(&transfer_event(),log)?;
+        Some(Self { token: log.address, from, to, value })
+    }
+}
+
+
+/// A decoded `Approval` event.
+///
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub struct Approval {
+    /// Token contract which emitted the event
+    pub token: Address,
+    pub owner: Address,
+    pub spender: Address,
+    pub value: U256,
+}
+
+
+impl Approval {
+
+    /// Decode a log as an `Approval` event.
+    ///
+    pub fn from_log(log: &Log) -> Option<Self> {
+        let (owner,spender,value) = decode_log(&approval_event(),log)?;
+        Some(Self { token: log.address, owner, spender, value })
+    }
+}
+
+
+fn balance_of_fn() -> Function { contract::function("balanceOf",&[("owner",Token::Addr)]) }
+
+fn total_supply_fn() -> Function { contract::function("totalSupply",&[]) }
+
+fn allowance_fn() -> Function { contract::function("allowance",&[("owner",Token::Addr),("spender",Token::Addr)]) }
+
+fn decimals_fn() -> Function { contract::function("decimals",&[]) }
+
+fn symbol_fn() -> Function { contract::function("symbol",&[]) }
+
+fn transfer_fn() -> Function { contract::function("transfer",&[("to",Token::Addr),("value",Token::Uint)]) }
+
+fn approve_fn() -> Function { contract::function("approve",&[("spender",Token::Addr),("value",Token::Uint)]) }
+
+fn transfer_from_fn() -> Function {
+    contract::function("transferFrom",&[("from",Token::Addr),("to",Token::Addr),("value",Token::Uint)])
+}
+
+
+fn encode(function: &Function, args: &[Value]) -> Bytes {
+    function.encode(args).expect("erc20 args must match function spec")
+}
+
+
+/// Decode a log with two indexed addresses and one non-indexed uint.
+///
+fn decode_log(event: &Event, log: &Log) -> Option<(Address,Address,U256)> {
+    if log.topics.len() != 3 || log.topics[0] != event.signature() || log.data.len() != 32 {
+        return None;
+    }
+    let mut decoded = event.decode(&log.topics[1..]).map(|(_,value)| value);
+    let first = decoded.next()?.as_addr()?;
+    let second = decoded.next()?.as_addr()?;
+    let value = decode_uint(&log.data).ok()?;
+    Some((first,second,value))
+}
+
+
+fn decode_uint(data: &[u8]) -> Result<U256,String> { U256::decode(data) }
+
+
+fn decode_uint8(data: &[u8]) -> Result<u8,String> { Uint8::decode(data).map(Uint8::into_inner) }
+
+
+fn decode_string(data: &[u8]) -> Result<String,String> { String::decode(data) }
+
+
+fn unexpected(expecting: &'static str, reason: String, got: &Bytes) -> Error {
+    Unexpected {
+        method: "eth_call",
+        expecting,
+        reason,
+        got: Json::String(got.to_string()),
+    }.into()
+}
@@ -74,6 +74,9 @@ pub mod types;
 pub mod util;
 pub mod api;
 pub mod abi;
+pub mod erc20;
+pub mod contract;
+pub mod trie;
 
 
 pub(crate) mod rpc;
@@ -83,5 +86,6 @@ pub use url::Url;
 
 pub use api::{
     connect,
+    connect_with_capacity,
     autoconnect,
 };
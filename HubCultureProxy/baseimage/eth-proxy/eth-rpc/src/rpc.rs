@@ -1,7 +1,9 @@
 use serde::de::DeserializeOwned;
 use serde::ser::Serialize;
 use std::{fmt,error};
+use std::time::Duration;
 use serde_json::Value;
+use transport::plex;
 
 use tokio::prelude::*;
 
@@ -10,7 +12,7 @@ pub type Result<T> = ::std::result::Result<T,Error>;
 
 pub trait Transport<Req,Rsp>: Clone {
 
-    type Error: fmt::Display;
+    type Error: TransportError;
 
     type Future: Future<Item=Result<Rsp>,Error=Self::Error> + Send + 'static;
 
@@ -21,7 +23,7 @@ pub trait Transport<Req,Rsp>: Clone {
 impl<T,F,Req,Rsp> Transport<Req,Rsp> for T where
         T: Fn(Req) -> F + Clone, F: IntoFuture<Item=Result<Rsp>>,
         <F as IntoFuture>::Future: Send + 'static,
-        <F as IntoFuture>::Error: fmt::Display {
+        <F as IntoFuture>::Error: TransportError {
 
     type Error = <F as IntoFuture>::Error;
 
@@ -33,6 +35,32 @@ impl<T,F,Req,Rsp> Transport<Req,Rsp> for T where
 }
 
 
+/// Error produced by a transport.
+///
+pub trait TransportError: fmt::Display {
+
+    /// Check if this error indicates that the request deadline elapsed.
+    ///
+    fn is_timeout(&self) -> bool { false }
+
+    /// Check if this error indicates that the transport was at capacity.
+    ///
+    fn is_overloaded(&self) -> bool { false }
+}
+
+
+impl TransportError for &'static str { }
+
+impl TransportError for String { }
+
+impl TransportError for plex::Error {
+
+    fn is_timeout(&self) -> bool { plex::Error::is_timeout(self) }
+
+    fn is_overloaded(&self) -> bool { *self == plex::Error::Overloaded }
+}
+
+
 /// An RPC request payload (method & params)
 ///
 /// *note*: it is the responsibility of the implementer to ensure that
@@ -45,6 +73,10 @@ pub trait Request {
     fn method(&self) -> &str;
 
     fn params(&self) -> Option<&Self::Params>;
+
+    /// Deadline for this request (if it differs from the transport's default).
+    ///
+    fn deadline(&self) -> Option<Duration> { None }
 }
 
 
@@ -71,7 +103,7 @@ impl<T> Response for T where T: DeserializeOwned { }
 /// ```
 /// # extern crate ethrpc;
 /// # extern crate serde_json;
-/// # use ethrpc::Error;
+/// # use ethrpc::{Error,ErrorKind};
 ///
 /// let rpc_error = r#"{
 ///     "code":-32010,
@@ -84,6 +116,8 @@ impl<T> Response for T where T: DeserializeOwned { }
 ///
 /// assert!(err.data.is_none());
 ///
+/// assert_eq!(err.kind(),ErrorKind::InsufficientFunds);
+///
 /// ```
 #[derive(Debug,Clone,Serialize,Deserialize)]
 pub struct Error {
@@ -93,6 +127,14 @@ pub struct Error {
     pub data: Option<Value>,
 }
 
+impl Error {
+
+    /// Classify this error by the failure it describes.
+    ///
+    pub fn kind(&self) -> ErrorKind { ErrorKind::classify(self) }
+}
+
+
 impl fmt::Display for Error {
 
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -105,3 +147,241 @@ impl error::Error for Error {
 
     fn description(&self) -> &str { &self.message }
 }
+
+
+/// Typed classification of node errors.
+///
+/// Nodes report most transaction-pool rejections with generic error codes
+/// (`-32000` for geth & erigon, `-32010` for parity & nethermind), so
+/// classification is primarily based upon the error message (nethermind
+/// messages lead with the name of the rejection, e.g. `OldNonce`).  Anything
+/// unrecognized maps to `ErrorKind::Other`.
+///
+/// ```
+/// # extern crate ethrpc;
+/// # extern crate serde_json;
+/// # use ethrpc::{Error,ErrorKind};
+/// # fn main() {
+/// let geth: Error = serde_json::from_str(r#"{"code":-32000,"message":"replacement transaction underpriced"}"#).unwrap();
+/// assert_eq!(geth.kind(),ErrorKind::Underpriced);
+///
+/// let parity: Error = serde_json::from_str(r#"{"code":-32010,"message":"Transaction with the same hash was already imported."}"#).unwrap();
+/// assert_eq!(parity.kind(),ErrorKind::AlreadyKnown);
+///
+/// let revert: Error = serde_json::from_str(r#"{"code":3,"message":"execution reverted","data":"0x"}"#).unwrap();
+/// assert_eq!(revert.kind(),ErrorKind::Reverted);
+/// # }
+/// ```
+///
+#[derive(Hash,Debug,Copy,Clone,PartialEq,Eq)]
+pub enum ErrorKind {
+    /// Nonce has already been used by a mined transaction
+    NonceTooLow,
+    /// Gas price too low (either below node minimum, or insufficient
+    /// to replace a pending transaction with the same nonce)
+    Underpriced,
+    /// Identical transaction is already in the pool
+    AlreadyKnown,
+    /// Sender cannot cover `gas * price + value`
+    InsufficientFunds,
+    /// EVM execution reverted
+    Reverted,
+    /// Unrecognized error
+    Other,
+}
+
+
+impl ErrorKind {
+
+    fn classify(err: &Error) -> Self {
+        let message = err.message.to_lowercase();
+        let matches = |patterns: &[&str]| patterns.iter().any(|p| message.contains(p));
+        if err.code == 3 || matches(&["execution reverted"]) || is_parity_revert(err) {
+            ErrorKind::Reverted
+        } else if matches(&["nonce too low","nonce is too low","oldnonce"]) {
+            ErrorKind::NonceTooLow
+        } else if matches(&["already known","known transaction","already imported","alreadyknown"]) {
+            ErrorKind::AlreadyKnown
+        } else if matches(&["underpriced","gas price is too low","gas price too low","fee too low","feetoolow","less than block base fee"]) {
+            ErrorKind::Underpriced
+        } else if matches(&["insufficient funds","insufficientfunds"]) {
+            ErrorKind::InsufficientFunds
+        } else {
+            ErrorKind::Other
+        }
+    }
+}
+
+
+/// Parity reports reverts as a generic vm error, with the
+/// revert reason in `data` (e.g. `"Reverted 0x..."`).
+///
+fn is_parity_revert(err: &Error) -> bool {
+    match err.data {
+        Some(Value::String(ref data)) if err.code == -32015 => data.starts_with("Reverted"),
+        _ => false,
+    }
+}
+
+
+impl fmt::Display for ErrorKind {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::NonceTooLow => f.write_str("nonce too low"),
+            ErrorKind::Underpriced => f.write_str("gas price too low"),
+            ErrorKind::AlreadyKnown => f.write_str("transaction already known"),
+            ErrorKind::InsufficientFunds => f.write_str("insufficient funds"),
+            ErrorKind::Reverted => f.write_str("execution reverted"),
+            ErrorKind::Other => f.write_str("other"),
+        }
+    }
+}
+
+
+/// Error types which may wrap a classifiable node error.
+///
+pub trait Classify {
+
+    /// Get the classification of the underlying node error (if any).
+    ///
+    fn kind(&self) -> ErrorKind;
+}
+
+
+impl Classify for Error {
+
+    fn kind(&self) -> ErrorKind { Error::kind(self) }
+}
+
+
+#[cfg(test)]
+mod test {
+    use rpc::{Error,ErrorKind,Classify};
+    use serde_json::Value;
+
+
+    fn error(code: i64, message: &str) -> Error {
+        Error { code, message: message.into(), data: None }
+    }
+
+
+    fn assert_kind(kind: ErrorKind, errors: &[(i64,&str)]) {
+        for &(code,message) in errors.iter() {
+            let err = error(code,message);
+            assert_eq!(err.kind(),kind,"{}",err);
+            assert_eq!(Classify::kind(&err),kind,"{}",err);
+        }
+    }
+
+
+    #[test]
+    fn nonce_too_low() {
+        assert_kind(ErrorKind::NonceTooLow,&[
+            // geth
+            (-32000,"nonce too low"),
+            (-32000,"nonce too low: address 0x71562b71999873DB5b286dF957af199Ec94617F7, tx: 3 state: 5"),
+            // erigon
+            (-32000,"nonce too low"),
+            // nethermind
+            (-32010,"OldNonce"),
+            (-32010,"OldNonce, Current nonce: 5, nonce of rejected tx: 3"),
+            // parity
+            (-32010,"Transaction nonce is too low. Try incrementing the nonce."),
+        ]);
+    }
+
+
+    #[test]
+    fn underpriced() {
+        assert_kind(ErrorKind::Underpriced,&[
+            // geth
+            (-32000,"transaction underpriced"),
+            (-32000,"replacement transaction underpriced"),
+            (-32000,"transaction underpriced: tip needed 1000000000, tip permitted 1"),
+            (-32000,"max fee per gas less than block base fee: address 0x71562b71999873DB5b286dF957af199Ec94617F7, maxFeePerGas: 1000000000, baseFee: 7000000000"),
+            // erigon
+            (-32000,"underpriced"),
+            (-32000,"replacement transaction underpriced"),
+            (-32000,"fee too low"),
+            // nethermind
+            (-32010,"FeeTooLow, MaxFeePerGas for 0x5a1c...4c22 is 1000000000, less than base fee 7000000000"),
+            (-32010,"FeeTooLowToCompete"),
+            // parity
+            (-32010,"Transaction gas price is too low. It does not satisfy your node's minimal gas price (minimal: 1000000000, got: 1). Try increasing the gas price."),
+            (-32010,"Transaction gas price is too low. There is another transaction with same nonce in the queue. Try increasing the gas price or incrementing the nonce."),
+        ]);
+    }
+
+
+    #[test]
+    fn already_known() {
+        assert_kind(ErrorKind::AlreadyKnown,&[
+            // geth
+            (-32000,"already known"),
+            (-32000,"known transaction: 6a1b8f2c0e3d4b5a69788796a5b4c3d2e1f00112233445566778899aabbccdd"),
+            // erigon
+            (-32000,"already known"),
+            // nethermind
+            (-32010,"AlreadyKnown"),
+            (-32010,"AlreadyKnown, transaction already in the pool"),
+            // parity
+            (-32010,"Transaction with the same hash was already imported."),
+        ]);
+    }
+
+
+    #[test]
+    fn insufficient_funds() {
+        assert_kind(ErrorKind::InsufficientFunds,&[
+            // geth
+            (-32000,"insufficient funds for gas * price + value"),
+            (-32000,"insufficient funds for gas * price + value: address 0x71562b71999873DB5b286dF957af199Ec94617F7 have 0 want 21000000000000"),
+            // erigon
+            (-32000,"insufficient funds"),
+            // nethermind
+            (-32010,"InsufficientFunds, Account balance: 0, cumulative cost: 21000000000000"),
+            // parity
+            (-32010,"Insufficient funds. The account you tried to send transaction from does not have enough funds. Required 270000 and got: 209850."),
+        ]);
+    }
+
+
+    #[test]
+    fn reverted() {
+        assert_kind(ErrorKind::Reverted,&[
+            // geth & erigon
+            (3,"execution reverted"),
+            (3,"execution reverted: Ownable: caller is not the owner"),
+            (-32000,"execution reverted"),
+            // nethermind
+            (3,"execution reverted: ERC20: transfer amount exceeds balance"),
+        ]);
+        // parity reports the revert reason in `data`
+        let parity = Error { code: -32015, message: "VM execution error.".into(), data: Some(Value::String("Reverted 0x08c379a0".into())) };
+        assert_eq!(parity.kind(),ErrorKind::Reverted);
+        let other = Error { code: -32015, message: "VM execution error.".into(), data: Some(Value::String("Out of gas".into())) };
+        assert_eq!(other.kind(),ErrorKind::Other);
+    }
+
+
+    #[test]
+    fn other() {
+        assert_kind(ErrorKind::Other,&[
+            // geth
+            (-32000,"nonce too high"),
+            (-32000,"intrinsic gas too low"),
+            (-32000,"exceeds block gas limit"),
+            (-32000,"invalid sender"),
+            (-32000,"tx fee (1.50 ether) exceeds the configured cap (1.00 ether)"),
+            // erigon
+            (-32000,"oversized data"),
+            // nethermind
+            (-32010,"NonceGap, Future nonce. Expected nonce: 5"),
+            (-32010,"GasLimitExceeded"),
+            // generic jsonrpc errors
+            (-32601,"the method eth_foo does not exist/is not available"),
+            (-32602,"invalid argument 0: json: cannot unmarshal hex string without 0x prefix"),
+        ]);
+    }
+}
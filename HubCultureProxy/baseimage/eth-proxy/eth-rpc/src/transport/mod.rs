@@ -1,6 +1,7 @@
 //! For when where u is ain't where u tryin' to be
 //!
 pub(crate) mod helpers;
+pub(crate) mod reconnect;
 pub mod plex;
 pub mod ws;
 
@@ -1,14 +1,15 @@
 //! Spawn multiplexed services
 //!
-use tokio_channel::{mpsc,oneshot};
+use tokio_channel::oneshot;
 pub use tokio_util::Never;
+use util::queue;
 use tokio::prelude::*;
 use tokio;
 use smallvec::SmallVec;
 use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::time::Duration;
-use std::fmt;
+use std::{fmt,error};
 
 
 pub trait Service<Req,Rsp>: Sink<SinkItem=(u64,Req)> + Stream<Item=(u64,Rsp)> {
@@ -25,17 +26,40 @@ impl<T,Req,Rsp> Service<Req,Rsp> for T where
 }
 
 
+/// Default maximum number of calls which may be queued for dispatch.
+///
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+
 pub fn spawn<Srv,Req,Rsp>(service: Srv) -> impl Future<Item=Handle<Req,Rsp>,Error=Never> where
         Srv: Service<Req,Rsp> + Send + 'static,
         Req: Send + 'static, Rsp: Send + 'static {
-    future::lazy(|| Ok(spawn_sync(service)))
+    spawn_with_capacity(service,DEFAULT_CAPACITY)
+}
+
+
+pub fn spawn_with_capacity<Srv,Req,Rsp>(service: Srv, capacity: usize) -> impl Future<Item=Handle<Req,Rsp>,Error=Never> where
+        Srv: Service<Req,Rsp> + Send + 'static,
+        Req: Send + 'static, Rsp: Send + 'static {
+    future::lazy(move || Ok(spawn_sync_with_capacity(service,capacity)))
 }
 
 
 pub fn spawn_sync<Srv,Req,Rsp>(service: Srv) -> Handle<Req,Rsp> where
         Srv: Service<Req,Rsp> + Send + 'static,
         Req: Send + 'static, Rsp: Send + 'static {
-    let (tx,rx) = mpsc::unbounded();
+    spawn_sync_with_capacity(service,DEFAULT_CAPACITY)
+}
+
+
+/// Spawn service, allowing at most `capacity` calls to be queued for
+/// dispatch at any given time (calls made while the queue is full fail
+/// with `Error::Overloaded`).
+///
+pub fn spawn_sync_with_capacity<Srv,Req,Rsp>(service: Srv, capacity: usize) -> Handle<Req,Rsp> where
+        Srv: Service<Req,Rsp> + Send + 'static,
+        Req: Send + 'static, Rsp: Send + 'static {
+    let (tx,rx) = queue::bounded(capacity);
     let handle = Handle::new(tx);
     let plex = Plex::new(rx.map_err(drop)).sink_map_err(drop);
     let (p_tx,p_rx) = plex.split();
@@ -58,6 +82,12 @@ pub struct Call<Req,Rsp> {
 }
 
 
+/// Handle which applies a deadline to each call.
+///
+/// Calls which exceed their deadline fail with `Error::Timeout`; the
+/// pending entry is released by the multiplexer as soon as the call
+/// is dropped.
+///
 #[derive(Debug,Clone)]
 pub struct TimeoutHandle<Req,Rsp> {
     inner: Handle<Req,Rsp>,
@@ -67,21 +97,43 @@ pub struct TimeoutHandle<Req,Rsp> {
 
 impl<Req,Rsp> TimeoutHandle<Req,Rsp> {
 
-    pub fn new(inner: mpsc::UnboundedSender<Call<Req,Rsp>>) -> Self {
+    pub fn new(inner: queue::Sender<Call<Req,Rsp>>) -> Self {
         let inner = Handle::new(inner);
         let timeout = Duration::from_secs(37);
         Self { inner, timeout }
     }
 
+    /// Set the default deadline (applies to calls made via `call`).
+    ///
     pub fn set_timeout(&mut self, timeout: Duration) {
         self.timeout = timeout;
     }
 
-    pub fn call(&self, req: Req) -> impl Future<Item=Rsp,Error=&'static str> {
-        self.inner.call(req).timeout(self.timeout).map_err(|e| {
-            match e.into_inner() {
-                Some(e) => e,
-                None => "request failed; timeout exceeded"
+    /// Get the default deadline.
+    ///
+    pub fn timeout(&self) -> Duration { self.timeout }
+
+    /// Number of calls waiting to be dispatched.
+    ///
+    pub fn depth(&self) -> usize { self.inner.depth() }
+
+    /// Make a call using the default deadline.
+    ///
+    pub fn call(&self, req: Req) -> impl Future<Item=Rsp,Error=Error> {
+        self.call_with_deadline(req,self.timeout)
+    }
+
+    /// Make a call which must complete within `deadline`.
+    ///
+    pub fn call_with_deadline(&self, req: Req, deadline: Duration) -> impl Future<Item=Rsp,Error=Error> {
+        self.inner.call(req).timeout(deadline).map_err(move |e| {
+            if e.is_elapsed() {
+                Error::Timeout(deadline)
+            } else if e.is_timer() {
+                error!("Timer failure during call: {}",e.into_timer().expect("timer error must exist"));
+                Error::Timer
+            } else {
+                e.into_inner().expect("inner error must exist")
             }
         })
     }
@@ -100,7 +152,7 @@ impl<Req,Rsp> From<Handle<Req,Rsp>> for TimeoutHandle<Req,Rsp> {
 
 #[derive(Debug)]
 pub struct Handle<Req,Rsp> {
-    inner: mpsc::UnboundedSender<Call<Req,Rsp>>,
+    inner: queue::Sender<Call<Req,Rsp>>,
 }
 
 
@@ -115,24 +167,98 @@ impl<Req,Rsp> Clone for Handle<Req,Rsp> {
 
 impl<Req,Rsp> Handle<Req,Rsp> {
 
-    pub fn new(inner: mpsc::UnboundedSender<Call<Req,Rsp>>) -> Self {
+    pub fn new(inner: queue::Sender<Call<Req,Rsp>>) -> Self {
         Self { inner }
     }
+
+    /// Number of calls waiting to be dispatched.
+    ///
+    pub fn depth(&self) -> usize { self.inner.depth() }
+
+    /// Maximum number of calls which may wait to be dispatched.
+    ///
+    pub fn capacity(&self) -> usize { self.inner.capacity() }
 }
 
 
 impl<Req,Rsp> Handle<Req,Rsp> {
 
-    pub fn call(&self, req: Req) -> impl Future<Item=Rsp,Error=&'static str> {
+    pub fn call(&self, req: Req) -> impl Future<Item=Rsp,Error=Error> {
         let (tx,rx) = oneshot::channel();
         let call = Call { req, tx };
-        self.inner.unbounded_send(call).map_err(|_| "unable to enqueue request; recv handle dropped")
-            .map(|_| rx.map_err(|_| "request failed; respose channel dropped")).into_future()
+        let capacity = self.inner.capacity();
+        self.inner.try_send(call).map_err(|err| {
+                if err.is_overloaded() {
+                    warn!("Rejecting call; dispatch queue full (capacity {})",capacity);
+                    Error::Overloaded
+                } else {
+                    Error::Closed
+                }
+            })
+            .map(|_| rx.map_err(|_| Error::Dropped)).into_future()
             .flatten()
     }
 }
 
 
+/// Indicates failure of a multiplexed call.
+///
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum Error {
+    /// Deadline elapsed before a response was received
+    Timeout(Duration),
+    /// Dispatch queue is full
+    Overloaded,
+    /// Multiplexer is no longer accepting requests
+    Closed,
+    /// Response channel dropped before a response was received
+    Dropped,
+    /// Timer failure (e.g. runtime is shutting down)
+    Timer,
+}
+
+
+impl Error {
+
+    /// Check if this error indicates an elapsed deadline.
+    ///
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            Error::Timeout(_) => true,
+            _other => false,
+        }
+    }
+}
+
+
+impl fmt::Display for Error {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Timeout(deadline) => write!(f,"request failed; deadline of {:?} exceeded",deadline),
+            Error::Overloaded => f.write_str("unable to enqueue request; overloaded"),
+            Error::Closed => f.write_str("unable to enqueue request; recv handle dropped"),
+            Error::Dropped => f.write_str("request failed; response channel dropped"),
+            Error::Timer => f.write_str("request failed; timer error"),
+        }
+    }
+}
+
+
+impl error::Error for Error {
+
+    fn description(&self) -> &str {
+        match self {
+            Error::Timeout(_) => "request deadline exceeded",
+            Error::Overloaded => "overloaded",
+            Error::Closed => "unable to enqueue request",
+            Error::Dropped => "response channel dropped",
+            Error::Timer => "timer error",
+        }
+    }
+}
+
+
 pub struct Plex<Inc,Req,Rsp> {
     incoming: Option<Inc>,
     plex: MultiPlex<Rsp>,
@@ -176,15 +302,25 @@ impl<Inc,Req,Rsp> Stream for Plex<Inc,Req,Rsp> where Inc: Stream<Item=Call<Req,R
     type Error = <Inc as Stream>::Error;
 
     fn poll(&mut self) -> Poll<Option<Self::Item>,Self::Error> {
-        if let Some(Call { req, tx }) = try_ready!(self.poll_incoming()) {
-            let id = self.plex.reserve_pending(tx);
-            Ok(Async::Ready(Some((id,req))))
-        } else {
-            // Inner stream has terminated; ensure all pending `tx` handles are
-            // resolved before actually terminating this stream (allows this stream
-            // to function as proxy for graceful shutdown trigger).
-            try_ready!(self.plex.poll_pending().map_err(|e|e.into()));
-            Ok(Async::Ready(None))
+        // Release calls which have been dropped by their callers (e.g. due to an
+        // elapsed deadline).  This also ensures that we are woken as soon as any
+        // other pending call is dropped.
+        self.plex.reap_canceled();
+        loop {
+            if let Some(Call { req, tx }) = try_ready!(self.poll_incoming()) {
+                if tx.is_canceled() {
+                    debug!("Skipping call canceled prior to dispatch");
+                    continue;
+                }
+                let id = self.plex.reserve_pending(tx);
+                return Ok(Async::Ready(Some((id,req))));
+            } else {
+                // Inner stream has terminated; ensure all pending `tx` handles are
+                // resolved before actually terminating this stream (allows this stream
+                // to function as proxy for graceful shutdown trigger).
+                try_ready!(self.plex.poll_pending().map_err(|e|e.into()));
+                return Ok(Async::Ready(None));
+            }
         }
     }
 }
@@ -230,6 +366,21 @@ impl<T> MultiPlex<T> {
     /// If `Async::Ready(())` is returned, no pending handles exist.
     ///   
     pub fn poll_pending(&mut self) -> Poll<(),Never> {
+        self.reap_canceled();
+        // If no `tx` handles remain, then nothing is pending; if any do remain,
+        // they must be pending since all cancellations have now been removed.
+        if self.inner.is_empty() {
+            Ok(Async::Ready(()))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+
+    /// Remove cancelled `tx` handles & register task for wake on future cancellations.
+    ///
+    /// Returns the number of handles removed.
+    ///
+    pub fn reap_canceled(&mut self) -> usize {
         // Set up collector for cancellation ids
         let mut cancelled: SmallVec<[u64;32]> = smallvec![];
         // Poll inner `tx` handles, recording cancellations 
@@ -239,16 +390,11 @@ impl<T> MultiPlex<T> {
             }
         }
         // Remove any cancelled `tx` handles
-        for id in cancelled.into_iter() {
-            self.inner.remove(&id);
-        }
-        // If no `tx` handles remain, then nothing is pending; if any do remain,
-        // they must be pending since all cancellations have now been removed.
-        if self.inner.is_empty() {
-            Ok(Async::Ready(()))
-        } else {
-            Ok(Async::NotReady)
+        for id in cancelled.iter() {
+            debug!("Releasing canceled call {}",id);
+            self.inner.remove(id);
         }
+        cancelled.len()
     }
 
     /// Get next available id
@@ -272,6 +418,9 @@ impl<T> MultiPlex<T> {
             if let Err(_) = sender.send(value) {
                 warn!("Receiver dropped for id {}",id);
             }
+        } else if id < self.next {
+            // ids are issued sequentially, so this call was previously canceled.
+            debug!("Discarding late response for canceled id {}",id);
         } else {
             warn!("No receiver found for id {}",id);
         }
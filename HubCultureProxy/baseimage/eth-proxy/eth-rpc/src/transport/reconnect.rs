@@ -0,0 +1,431 @@
+//! Self-healing websocket service.
+//!
+//! Wraps a websocket connection such that lost connections are re-established
+//! (with exponential backoff & jitter) rather than terminating the service.  Each
+//! request is held until its response is seen; when a connection is lost, all
+//! requests which have not yet been answered are re-sent over the new connection
+//! under their original ids, unless their caller has since given up on them (see
+//! `Tracked`).  Callers are responsible for failing calls which cannot be served
+//! in time (see `plex::TimeoutHandle`).
+//!
+//! Only request/response traffic is carried; the transport has no support for
+//! subscriptions (`eth_subscribe`), so there is no subscription state to restore
+//! after a reconnect.  Streams built on top of it (e.g. `api::Util::log_stream`)
+//! poll the node, and must handle errors reported by the node themselves.
+//!
+use rpc::{self,Request,Response};
+use transport::helpers;
+use tokio::prelude::*;
+use tokio::timer::Delay;
+use proxy::ws::{self,Message,WebSocketConn};
+use rand::{self,Rng};
+use std::collections::{BTreeMap,VecDeque};
+use std::time::{Duration,Instant};
+use std::sync::{Arc,Weak};
+use std::marker::PhantomData;
+use std::{cmp,fmt};
+use serde_json::Value;
+use super::ws::Error;
+
+
+/// Unanswered requests older than this are not re-sent after a reconnect; no
+/// request deadline is longer than this, so no caller can still be waiting.
+///
+const REPLAY_WINDOW: Duration = Duration::from_secs(180);
+
+/// Maximum number of requests which may await transmission (e.g. while
+/// reconnecting) before backpressure is applied.
+///
+const MAX_UNSENT: usize = 256;
+
+
+/// Produces new websocket connections.
+///
+pub trait Connector {
+
+    type Conn: WebSocketConn;
+
+    type Future: Future<Item=Self::Conn,Error=ws::Error> + Send;
+
+    fn connect(&self) -> Self::Future;
+}
+
+
+impl<T,F> Connector for T where T: Fn() -> F, F: IntoFuture<Error=ws::Error>, F::Item: WebSocketConn, F::Future: Send {
+
+    type Conn = <F as IntoFuture>::Item;
+
+    type Future = <F as IntoFuture>::Future;
+
+    fn connect(&self) -> Self::Future { (self)().into_future() }
+}
+
+
+/// A request, along with a means of checking if its caller is still waiting.
+///
+/// The caller holds the `Arc` yielded by `Tracked::new` for as long as it awaits
+/// a response; once that is dropped (e.g. due to an elapsed deadline), the request
+/// is no longer re-sent after a reconnect.
+///
+#[derive(Debug)]
+pub struct Tracked<Req> {
+    req: Req,
+    caller: Weak<()>,
+}
+
+
+impl<Req> Tracked<Req> {
+
+    pub fn new(req: Req) -> (Self,Arc<()>) {
+        let caller = Arc::new(());
+        let tracked = Self { req, caller: Arc::downgrade(&caller) };
+        (tracked,caller)
+    }
+}
+
+
+/// Connection state of a `Reconnect` service.
+///
+enum State<C,F> {
+    /// Connection is live
+    Connected(C),
+    /// Waiting out backoff prior to next connection attempt
+    Waiting(Delay),
+    /// Connection attempt is in progress
+    Connecting(F),
+}
+
+
+/// JSON-RPC websocket service which reconnects on failure.
+///
+/// Implements `plex::Service`, so it may be spawned as a long-lived
+/// multiplexer which outlives any individual connection.
+///
+pub struct Reconnect<C: Connector,Req,Rsp> {
+    connector: C,
+    state: State<C::Conn,C::Future>,
+    backoff: Backoff,
+    /// Encoded requests which have not yet been answered
+    in_flight: BTreeMap<u64,(Instant,Weak<()>,Message)>,
+    /// Ids of requests not yet sent over the current connection
+    unsent: VecDeque<u64>,
+    _r: PhantomData<(Req,Rsp)>,
+}
+
+
+impl<C: Connector,Req,Rsp> Reconnect<C,Req,Rsp> {
+
+    /// Wrap an established connection.
+    ///
+    pub fn new(connector: C, conn: C::Conn) -> Self {
+        let state = State::Connected(conn);
+        let backoff = Backoff::default();
+        let (in_flight,unsent) = Default::default();
+        Self { connector, state, backoff, in_flight, unsent, _r: PhantomData }
+    }
+
+    /// Drop current connection & schedule reconnect.
+    ///
+    fn disconnect(&mut self, reason: &str) {
+        let delay = self.backoff.next();
+        warn!("Websocket connection lost ({}); reconnecting in {:?}",reason,delay);
+        self.state = State::Waiting(Delay::new(Instant::now() + delay));
+        // Everything not yet answered must be re-sent over the next connection,
+        // unless nobody is waiting on it anymore.
+        let abandoned: Vec<u64> = self.in_flight.iter()
+            .filter(|(_,(sent,caller,_))| sent.elapsed() > REPLAY_WINDOW || caller.upgrade().is_none())
+            .map(|(id,_)| *id)
+            .collect();
+        for id in abandoned.iter() {
+            debug!("Abandoning expired or canceled request {}",id);
+            self.in_flight.remove(id);
+        }
+        self.unsent = self.in_flight.keys().cloned().collect();
+        if !self.unsent.is_empty() {
+            info!("Will retry {} unanswered request(s) after reconnect",self.unsent.len());
+        }
+    }
+
+    /// Drive reconnection (if needed) until a connection is live.
+    ///
+    fn poll_connected(&mut self) -> Poll<(),Error> {
+        loop {
+            let next_state = match self.state {
+                State::Connected(_) => { return Ok(Async::Ready(())); },
+                State::Waiting(ref mut delay) => {
+                    try_ready!(delay.poll().map_err(|e| {
+                        error!("Timer failure during reconnect: {}",e);
+                        ws::Error::message("timer failure during reconnect")
+                    }));
+                    debug!("Attempting websocket reconnect");
+                    State::Connecting(self.connector.connect())
+                },
+                State::Connecting(ref mut work) => {
+                    match work.poll() {
+                        Ok(Async::Ready(conn)) => {
+                            info!("Websocket connection re-established");
+                            State::Connected(conn)
+                        },
+                        Ok(Async::NotReady) => { return Ok(Async::NotReady); },
+                        Err(err) => {
+                            let delay = self.backoff.next();
+                            warn!("Websocket reconnect failed ({}); retrying in {:?}",err,delay);
+                            State::Waiting(Delay::new(Instant::now() + delay))
+                        },
+                    }
+                },
+            };
+            self.state = next_state;
+        }
+    }
+
+    /// Drive transmission of unsent requests.
+    ///
+    fn poll_flush(&mut self) -> Poll<(),Error> {
+        loop {
+            try_ready!(self.poll_connected());
+            let failure = match self.send_unsent() {
+                Ok(Async::Ready(())) => match self.conn().poll_complete() {
+                    Ok(ready) => { return Ok(ready); },
+                    Err(err) => err,
+                },
+                Ok(Async::NotReady) => { return Ok(Async::NotReady); },
+                Err(err) => err,
+            };
+            self.disconnect(&failure.to_string());
+        }
+    }
+
+    fn send_unsent(&mut self) -> Poll<(),ws::Error> {
+        while let Some(id) = self.unsent.front().cloned() {
+            let message = match self.in_flight.get(&id) {
+                Some((_,caller,message)) if caller.upgrade().is_some() => message.clone(),
+                // canceled while awaiting reconnect
+                Some(_) => {
+                    debug!("Abandoning canceled request {}",id);
+                    self.in_flight.remove(&id);
+                    let _ = self.unsent.pop_front();
+                    continue;
+                },
+                // answered prior to being re-sent
+                None => { let _ = self.unsent.pop_front(); continue; },
+            };
+            if let AsyncSink::NotReady(_) = self.conn().start_send(message)? {
+                return Ok(Async::NotReady);
+            }
+            let _ = self.unsent.pop_front();
+        }
+        Ok(Async::Ready(()))
+    }
+
+    fn conn(&mut self) -> &mut C::Conn {
+        match self.state {
+            State::Connected(ref mut conn) => conn,
+            _ => panic!("no live websocket connection"),
+        }
+    }
+}
+
+
+impl<C: Connector,Req,Rsp> Sink for Reconnect<C,Req,Rsp> where Req: Request {
+
+    type SinkItem = (u64,Tracked<Req>);
+
+    type SinkError = Error;
+
+    fn start_send(&mut self, item: Self::SinkItem) -> StartSend<Self::SinkItem,Self::SinkError> {
+        let _: Async<()> = self.poll_flush()?;
+        if self.unsent.len() >= MAX_UNSENT {
+            return Ok(AsyncSink::NotReady(item));
+        }
+        let (id,Tracked { req, caller }) = item;
+        let request = helpers::Request::new(req.method(),req.params(),id);
+        let message = ws::Message::encode_json(&request)?;
+        debug!("Sending {:?}",message);
+        self.in_flight.insert(id,(Instant::now(),caller,message));
+        self.unsent.push_back(id);
+        let _: Async<()> = self.poll_flush()?;
+        Ok(AsyncSink::Ready)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(),Self::SinkError> {
+        self.poll_flush()
+    }
+}
+
+
+impl<C: Connector,Req,Rsp> Stream for Reconnect<C,Req,Rsp> where Rsp: Response + fmt::Debug {
+
+    type Item = (u64,rpc::Result<Rsp>);
+
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>,Self::Error> {
+        loop {
+            // Unsent requests must be pushed out here as well; the sink half may be
+            // idle (e.g. waiting on responses) when a reconnect completes.
+            let _: Async<()> = self.poll_flush()?;
+            try_ready!(self.poll_connected());
+            let failure = match self.conn().poll() {
+                Ok(Async::Ready(Some(msg))) => {
+                    let rsp: helpers::Response<Rsp> = match msg.parse_json() {
+                        Ok(rsp) => rsp,
+                        Err(err) => {
+                            match msg.parse_json::<Value>() {
+                                Ok(other) => warn!("Unexpected json: {} ({})",other,err),
+                                Err(err) => warn!("Non-json message: {}",err),
+                            }
+                            continue;
+                        },
+                    };
+                    debug!("Got {:?}",rsp);
+                    self.backoff.reset();
+                    let id = rsp.id();
+                    self.in_flight.remove(&id);
+                    return Ok(Async::Ready(Some((id,rsp.as_result()))));
+                },
+                Ok(Async::Ready(None)) => "connection closed by peer".to_owned(),
+                Ok(Async::NotReady) => { return Ok(Async::NotReady); },
+                Err(err) => err.to_string(),
+            };
+            self.disconnect(&failure);
+        }
+    }
+}
+
+
+/// Exponential backoff with jitter.
+///
+/// Each delay is chosen uniformly from the upper half of the current backoff
+/// window, so that many clients which lost their connections simultaneously
+/// (e.g. due to a node restart) do not all reconnect in lockstep.
+///
+#[derive(Debug,Copy,Clone)]
+struct Backoff {
+    attempt: u32,
+    base: Duration,
+    max: Duration,
+}
+
+
+impl Default for Backoff {
+
+    fn default() -> Self {
+        let attempt = 0;
+        let base = Duration::from_millis(128);
+        let max = Duration::from_secs(32);
+        Self { attempt, base, max }
+    }
+}
+
+
+impl Backoff {
+
+    /// Get the next delay, widening the window for subsequent calls.
+    ///
+    fn next(&mut self) -> Duration {
+        let window = self.base * (1u32 << cmp::min(self.attempt,16));
+        let window = cmp::min(window,self.max);
+        self.attempt = self.attempt.saturating_add(1);
+        let millis = window.as_secs() * 1000 + (window.subsec_nanos() / 1_000_000) as u64;
+        let half = millis / 2;
+        let jitter = rand::thread_rng().gen_range(0,half + 1);
+        Duration::from_millis(half + jitter)
+    }
+
+    /// Reset the backoff window (called once a connection is known to be healthy).
+    ///
+    fn reset(&mut self) { self.attempt = 0; }
+}
+
+
+#[cfg(test)]
+mod test {
+    use transport::reconnect::{Reconnect,Tracked};
+    use tokio::runtime::current_thread::Runtime;
+    use tokio::prelude::*;
+    use tokio_channel::mpsc;
+    use proxy::ws::{self,Message};
+    use serde_json::Value;
+    use std::sync::{Arc,Mutex};
+
+    /// In-memory websocket connection.
+    struct Conn {
+        rx: mpsc::UnboundedReceiver<Message>,
+        tx: mpsc::UnboundedSender<Message>,
+    }
+
+    impl Stream for Conn {
+
+        type Item = Message;
+
+        type Error = ws::Error;
+
+        fn poll(&mut self) -> Poll<Option<Message>,ws::Error> {
+            self.rx.poll().map_err(|()| ws::Error::message("recv failed"))
+        }
+    }
+
+    impl Sink for Conn {
+
+        type SinkItem = Message;
+
+        type SinkError = ws::Error;
+
+        fn start_send(&mut self, msg: Message) -> StartSend<Message,ws::Error> {
+            self.tx.start_send(msg).map_err(|_| ws::Error::message("send failed"))
+        }
+
+        fn poll_complete(&mut self) -> Poll<(),ws::Error> {
+            self.tx.poll_complete().map_err(|_| ws::Error::message("send failed"))
+        }
+    }
+
+    /// Yields a connection, and the peer's ends of its channels.
+    fn conn() -> (Conn,(mpsc::UnboundedSender<Message>,mpsc::UnboundedReceiver<Message>)) {
+        let (to_conn,rx) = mpsc::unbounded();
+        let (tx,from_conn) = mpsc::unbounded();
+        (Conn { rx, tx },(to_conn,from_conn))
+    }
+
+    fn id_of(msg: &Message) -> u64 {
+        let value: Value = msg.parse_json().unwrap();
+        value["id"].as_u64().unwrap()
+    }
+
+    #[test]
+    fn replay_after_disconnect() {
+        let mut runtime = Runtime::new().unwrap();
+        let (first,first_peer) = conn();
+        let (second,(to_second,from_second)) = conn();
+        let spare = Mutex::new(Some(second));
+        let connector = move || spare.lock().unwrap().take().ok_or_else(|| ws::Error::message("no more connections"));
+        let mut service: Reconnect<_,(&str,Vec<u8>),Value> = Reconnect::new(connector,first);
+        let (canceled,canceled_caller) = Tracked::new(("eth_blockNumber",Vec::new()));
+        let (live,_live_caller) = Tracked::new(("eth_gasPrice",Vec::new()));
+        runtime.block_on(future::lazy(|| -> Result<(),ws::Error> {
+            assert!(service.start_send((1,canceled)).unwrap().is_ready());
+            assert!(service.start_send((2,live)).unwrap().is_ready());
+            Ok(())
+        })).unwrap();
+        // caller of request 1 gives up, then the connection drops
+        drop(canceled_caller);
+        drop(first_peer);
+        let replayed = Arc::new(Mutex::new(Vec::new()));
+        let respond = {
+            let replayed = replayed.clone();
+            from_second.for_each(move |msg| {
+                let id = id_of(&msg);
+                replayed.lock().unwrap().push(id);
+                let rsp = format!(r#"{{"jsonrpc":"2.0","id":{},"result":"0x1"}}"#,id);
+                to_second.unbounded_send(Message::Text(rsp)).map_err(drop)
+            })
+        };
+        runtime.spawn(respond);
+        let (item,_service) = runtime.block_on(service.into_future()).map_err(|(err,_)| err).unwrap();
+        let (id,rslt) = item.unwrap();
+        assert_eq!(id,2);
+        assert_eq!(rslt.unwrap(),Value::from("0x1"));
+        assert_eq!(*replayed.lock().unwrap(),vec![2]);
+    }
+}
@@ -30,12 +30,13 @@
 //! ```
 //!
 use rpc::{self,Request,Response};
-use transport::{helpers,plex};
+use transport::reconnect::{Reconnect,Connector,Tracked};
+use transport::plex;
 use tokio::prelude::*;
 use proxy::ws;
 use url::Url;
 use std::fmt;
-use serde_json::{self,Value};
+use serde_json;
 
 
 wrap_errs!(
@@ -44,45 +45,48 @@ wrap_errs!(
 );
 
 
-pub type Handle<Req,Rsp> = plex::TimeoutHandle<Req,rpc::Result<Rsp>>;
+pub type Handle<Req,Rsp> = plex::TimeoutHandle<Tracked<Req>,rpc::Result<Rsp>>;
 
 
 /// Initialize a JSONRPC style multiplexed websocket connection.
 ///
+/// The initial connection must succeed, but any subsequent connection loss is
+/// handled transparently: the transport reconnects with exponential backoff and
+/// jitter, and re-sends all requests which had not yet been answered.  Calls which
+/// cannot be completed within their deadline fail with `plex::Error::Timeout`.
+///
 /// See module-level docs for example usage.
 ///
 pub fn connect<Req,Rsp>(url: Url) -> impl Future<Item=impl rpc::Transport<Req,Rsp>,Error=Error> where
         Req: Request + fmt::Debug + Send + 'static,
         Rsp: Response + fmt::Debug + Send + 'static {
+    connect_with_capacity(url,plex::DEFAULT_CAPACITY)
+}
+
+
+/// Initialize a multiplexed websocket connection which buffers up to `capacity`
+/// pending requests.
+///
+pub fn connect_with_capacity<Req,Rsp>(url: Url, capacity: usize) -> impl Future<Item=impl rpc::Transport<Req,Rsp>,Error=Error> where
+        Req: Request + fmt::Debug + Send + 'static,
+        Rsp: Response + fmt::Debug + Send + 'static {
     
     future::lazy(move || {
-        ws::connect(url).from_err().and_then(|conn| {
-            let conn = conn.sink_from_err::<Error>().with(|(id,req): (u64,Req)| -> Result<_,Error> {
-                let request = helpers::Request::new(
-                    req.method(),
-                    req.params(),
-                    id
-                    );
-                debug!("Sending {:?}",request);
-                let ser = ws::Message::encode_json(&request)?;
-                Ok(ser.into())
-            });
-            let conn = conn.from_err().and_then(|msg| -> Result<(u64,rpc::Result<Rsp>),Error> {
-                let rsp: helpers::Response<Rsp> = msg.parse_json().map_err(|err| {
-                    match msg.parse_json::<Value>() {
-                        Ok(other) => { warn!("Unexpected json: {}",other); err },
-                        Err(err) => { warn!("Non-json message: {}",err); err },
-                    }
-                })?;
-                debug!("Got {:?}",rsp);
-                let id = rsp.id();
-                Ok((id,rsp.as_result()))
-            });
-            plex::spawn(conn).map_err(|e| e.into()).map(|handle| {
-                let transport = move |req| { handle.call(req) };
+        let connector = move || ws::connect(url.clone());
+        connector.connect().from_err().and_then(move |conn| {
+            let service = Reconnect::new(connector,conn);
+            plex::spawn_with_capacity(service,capacity).map_err(|e| e.into()).map(|handle| {
+                let handle: Handle<Req,Rsp> = handle.into();
+                let transport = move |req: Req| {
+                    let deadline = req.deadline().unwrap_or(handle.timeout());
+                    let (req,caller) = Tracked::new(req);
+                    handle.call_with_deadline(req,deadline).then(move |rslt| {
+                        drop(caller);
+                        rslt
+                    })
+                };
                 transport
             })
         })
     })
 }
-
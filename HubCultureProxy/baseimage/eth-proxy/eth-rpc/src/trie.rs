@@ -0,0 +1,549 @@
+//! Merkle-Patricia trie proof verification.
+//!
+//! Verifies the proofs returned by `eth_getProof` against a known root
+//! (e.g. a block's `state_root`), such that account and storage values
+//! may be trusted without trusting the node which served them.  Also
+//! rebuilds the receipts trie of a block, such that logs may be checked
+//! against its `receipts_root`.
+//!
+//! ## Example
+//!
+//! ```
+//! extern crate ethrpc;
+//!
+//! use ethrpc::trie::{self,ProofError};
+//! use ethrpc::types::{Bytes,H256};
+//!
+//! # fn main() {
+//! // A storage trie with three slots; the proof for slot zero passes through a
+//! // branch node, then an extension node holding an inline branch & inline leaves.
+//! let root: H256 = "0xca03db0c4172ca063931aa257e37a49d2d2c313f58bd31b635b2a7155d1b7d3e".parse().unwrap();
+//! let proof: Vec<Bytes> = vec![
+//!     "0xf8518080a081a0befad2e91f9a62edd51fc59c7b7eee5de61296619dbb4ca572c4399517f7a091d97d425047a8dd41d7d1489d01ddea8b20e7191ddbcab8eb0b4ca152a1858980808080808080808080808080".parse().unwrap(),
+//!     "0xf8399e190decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3d98080808080808080808080808080c48235632ac48235630780".parse().unwrap(),
+//! ];
+//! let slot = [0u8;32];
+//! assert_eq!(trie::verify_proof(root,&slot,&proof),Ok(Some(vec![0x2a])));
+//!
+//! // Slot one falls on an empty branch of the root, so the root alone proves its absence
+//! let mut other = [0u8;32];
+//! other[31] = 1;
+//! assert_eq!(trie::verify_proof(root,&other,&proof[..1]),Ok(None));
+//!
+//! // Proofs cannot be verified against any other root
+//! let bad_root: H256 = "0x91d97d425047a8dd41d7d1489d01ddea8b20e7191ddbcab8eb0b4ca152a18589".parse().unwrap();
+//! assert_eq!(trie::verify_proof(bad_root,&slot,&proof),Err(ProofError::BadHash));
+//! # }
+//! ```
+//!
+use types::{Bytes,Receipt,Status,U256,H256};
+use crypto;
+use util;
+use _rlp::{Rlp,RlpStream,DecoderError};
+use std::{fmt,error};
+
+
+/// Root hash of an empty trie (`keccak(rlp(""))`).
+///
+pub const EMPTY_ROOT: [u8;32] = [
+    0x56,0xe8,0x1f,0x17,0x1b,0xcc,0x55,0xa6,0xff,0x83,0x45,0xe6,0x92,0xc0,0xf8,0x6e,
+    0x5b,0x48,0xe0,0x1b,0x99,0x6c,0xad,0xc0,0x01,0x62,0x2f,0xb5,0xe3,0x63,0xb4,0x21,
+];
+
+
+/// Hash of empty contract code (`keccak("")`).
+///
+pub const EMPTY_CODE: [u8;32] = [
+    0xc5,0xd2,0x46,0x01,0x86,0xf7,0x23,0x3c,0x92,0x7e,0x7d,0xb2,0xdc,0xc7,0x03,0xc0,
+    0xe5,0x00,0xb6,0x53,0xca,0x82,0x27,0x3b,0x7b,0xfa,0xd8,0x04,0x5d,0x85,0xa4,0x70,
+];
+
+
+/// Verify a proof for `key` in the secure trie with the given root.
+///
+/// Keys are hashed before lookup, as in the state & storage tries.  Yields
+/// the raw value stored under `key`, or `None` if the proof shows that the
+/// key is absent.  Fails if the proof is invalid, incomplete, or contains
+/// nodes which do not lie on the key's path.
+///
+pub fn verify_proof(root: H256, key: &[u8], proof: &[Bytes]) -> Result<Option<Vec<u8>>,ProofError> {
+    let path = nibbles(crypto::keccak(key).as_ref());
+    let mut nodes = proof.iter();
+    let mut next = if root == H256::from(EMPTY_ROOT) { None } else { Some(NodeRef::Hash(root)) };
+    let mut offset = 0;
+    let value = loop {
+        let node: &[u8] = match next.take() {
+            Some(NodeRef::Hash(hash)) => {
+                let node = nodes.next().ok_or(ProofError::Incomplete)?;
+                if crypto::keccak(&node[..]) != hash {
+                    return Err(ProofError::BadHash);
+                }
+                node
+            },
+            Some(NodeRef::Inline(node)) => node,
+            None => { break None; },
+        };
+        let rlp = Rlp::new(node);
+        match rlp.item_count()? {
+            17 => {
+                if offset == path.len() {
+                    break non_empty(rlp.at(16)?.data()?);
+                }
+                next = child(rlp.at(path[offset] as usize)?)?;
+                offset += 1;
+            },
+            2 => {
+                let (partial,is_leaf) = decode_path(rlp.at(0)?.data()?)?;
+                let remaining = &path[offset..];
+                if is_leaf {
+                    if remaining == &partial[..] {
+                        break non_empty(rlp.at(1)?.data()?);
+                    } else {
+                        break None;
+                    }
+                }
+                if !remaining.starts_with(&partial) {
+                    break None;
+                }
+                offset += partial.len();
+                next = child(rlp.at(1)?)?;
+            },
+            other => {
+                return Err(ProofError::Malformed(format!("trie node with {} items",other)));
+            },
+        }
+    };
+    if nodes.next().is_some() {
+        return Err(ProofError::Trailing);
+    }
+    Ok(value)
+}
+
+
+/// Compute the root of the trie containing the given key/value pairs.
+///
+/// Keys are used as-is (i.e. this is not a secure trie).
+///
+pub fn trie_root(pairs: &[(&[u8],&[u8])]) -> H256 {
+    let mut items: Vec<(Vec<u8>,&[u8])> = pairs.iter().map(|(key,value)| (nibbles(key),*value)).collect();
+    items.sort_by(|a,b| a.0.cmp(&b.0));
+    items.dedup_by(|a,b| a.0 == b.0);
+    let root = encode_node(&items,0);
+    crypto::keccak(root.as_slice())
+}
+
+
+/// Compute the root of a trie keyed by the rlp-encoded index of each value,
+/// as used for the transactions & receipts of a block.
+///
+pub fn ordered_root(values: &[Vec<u8>]) -> H256 {
+    let keys: Vec<Vec<u8>> = (0..values.len()).map(|index| {
+        let mut stream = RlpStream::new();
+        stream.append(&(index as u64));
+        stream.out()
+    }).collect();
+    let pairs: Vec<(&[u8],&[u8])> = keys.iter().zip(values.iter())
+        .map(|(key,value)| (&key[..],&value[..])).collect();
+    trie_root(&pairs)
+}
+
+
+/// Compute the receipts root of a block from its receipts (in block order).
+///
+/// Fails if a receipt lacks the fields needed for its consensus encoding.
+///
+pub fn receipts_root(receipts: &[Receipt]) -> Result<H256,ProofError> {
+    let encoded = receipts.iter().map(encode_receipt).collect::<Result<Vec<_>,_>>()?;
+    Ok(ordered_root(&encoded))
+}
+
+
+/// Consensus encoding of a receipt (including the type prefix for
+/// non-legacy receipts).
+///
+pub fn encode_receipt(receipt: &Receipt) -> Result<Vec<u8>,ProofError> {
+    let bloom = receipt.logs_bloom.as_ref().ok_or_else(|| {
+        ProofError::Malformed("receipt missing `logsBloom`".into())
+    })?;
+    let mut stream = RlpStream::new_list(4);
+    match (receipt.status,receipt.root) {
+        (Some(Status::Success),_) => { stream.append(&&[1u8][..]); },
+        (Some(Status::Failure),_) => { stream.append_empty_data(); },
+        (None,Some(root)) => {
+            let root: &[u8] = root.as_ref();
+            stream.append(&root);
+        },
+        (None,None) => {
+            return Err(ProofError::Malformed("receipt missing `status` & `root`".into()));
+        },
+    }
+    stream.append(&util::trim(&receipt.cumulative_gas_used));
+    stream.append(&&bloom[..]);
+    stream.begin_list(receipt.logs.len());
+    for log in receipt.logs.iter() {
+        let address: &[u8] = log.address.as_ref();
+        stream.begin_list(3);
+        stream.append(&address);
+        stream.begin_list(log.topics.len());
+        for topic in log.topics.iter() {
+            let topic: &[u8] = topic.as_ref();
+            stream.append(&topic);
+        }
+        stream.append(&&log.data[..]);
+    }
+    let encoded = stream.out();
+    match receipt.tx_type.as_ref().map(|kind| util::as_u64(kind)) {
+        None | Some(Some(0)) => Ok(encoded),
+        Some(Some(kind)) if kind < 0x80 => {
+            let mut typed = Vec::with_capacity(encoded.len() + 1);
+            typed.push(kind as u8);
+            typed.extend_from_slice(&encoded);
+            Ok(typed)
+        },
+        Some(_) => Err(ProofError::Malformed("invalid receipt type".into())),
+    }
+}
+
+
+/// An account, as stored in the state trie.
+///
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub struct Account {
+    pub nonce: U256,
+    pub balance: U256,
+    pub storage_root: H256,
+    pub code_hash: H256,
+}
+
+
+impl Account {
+
+    /// The implicit value of an account which does not exist.
+    ///
+    pub fn empty() -> Self {
+        Account {
+            nonce: U256::from(0u64),
+            balance: U256::from(0u64),
+            storage_root: H256::from(EMPTY_ROOT),
+            code_hash: H256::from(EMPTY_CODE),
+        }
+    }
+
+    /// Decode an rlp-encoded account.
+    ///
+    pub fn decode(encoded: &[u8]) -> Result<Self,ProofError> {
+        let rlp = Rlp::new(encoded);
+        if rlp.item_count()? != 4 {
+            return Err(ProofError::Malformed("account must have 4 fields".into()));
+        }
+        Ok(Account {
+            nonce: decode_uint(rlp.at(0)?.data()?)?,
+            balance: decode_uint(rlp.at(1)?.data()?)?,
+            storage_root: decode_hash(rlp.at(2)?.data()?)?,
+            code_hash: decode_hash(rlp.at(3)?.data()?)?,
+        })
+    }
+}
+
+
+/// Decode an rlp-encoded storage value.
+///
+pub fn decode_storage(encoded: &[u8]) -> Result<U256,ProofError> {
+    decode_uint(Rlp::new(encoded).data()?)
+}
+
+
+/// Error raised when a proof cannot be verified.
+///
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum ProofError {
+    /// A proof node did not match the hash of its parent's reference
+    BadHash,
+    /// Proof ended before the key's path was resolved
+    Incomplete,
+    /// Proof contained nodes beyond the key's path
+    Trailing,
+    /// Proof node was not a valid trie node
+    Malformed(String),
+    /// Proven value did not match the claimed value of the named field
+    Mismatch(&'static str),
+}
+
+
+impl fmt::Display for ProofError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProofError::BadHash => f.write_str("invalid proof; node hash mismatch"),
+            ProofError::Incomplete => f.write_str("invalid proof; missing nodes"),
+            ProofError::Trailing => f.write_str("invalid proof; unexpected trailing nodes"),
+            ProofError::Malformed(reason) => write!(f,"invalid proof; {}",reason),
+            ProofError::Mismatch(field) => write!(f,"proof does not match claimed {}",field),
+        }
+    }
+}
+
+
+impl error::Error for ProofError {
+
+    fn description(&self) -> &str {
+        match self {
+            ProofError::BadHash => "node hash mismatch",
+            ProofError::Incomplete => "missing proof nodes",
+            ProofError::Trailing => "trailing proof nodes",
+            ProofError::Malformed(_) => "malformed proof node",
+            ProofError::Mismatch(_) => "proven value mismatch",
+        }
+    }
+}
+
+
+impl From<DecoderError> for ProofError {
+
+    fn from(err: DecoderError) -> Self { ProofError::Malformed(err.to_string()) }
+}
+
+
+/// Reference from a parent node to its child.
+///
+enum NodeRef<'a> {
+    /// Hash of a node which appears later in the proof
+    Hash(H256),
+    /// Node under 32 bytes, embedded directly in its parent
+    Inline(&'a [u8]),
+}
+
+
+fn child<'a>(rlp: Rlp<'a>) -> Result<Option<NodeRef<'a>>,ProofError> {
+    if rlp.is_list() {
+        return Ok(Some(NodeRef::Inline(rlp.as_raw())));
+    }
+    let data = rlp.data()?;
+    match data.len() {
+        0 => Ok(None),
+        32 => Ok(Some(NodeRef::Hash(decode_hash(data)?))),
+        other => Err(ProofError::Malformed(format!("child reference of {} bytes",other))),
+    }
+}
+
+
+/// Rlp-encode the node containing `items`, all of which share the
+/// first `depth` nibbles of their keys.
+///
+fn encode_node(items: &[(Vec<u8>,&[u8])], depth: usize) -> Vec<u8> {
+    match items {
+        [] => { return vec![0x80]; },
+        [(key,value)] => {
+            let mut stream = RlpStream::new_list(2);
+            stream.append(&&encode_path(&key[depth..],true)[..]);
+            stream.append(value);
+            return stream.out();
+        },
+        _ => { },
+    }
+    // items are sorted, so the first & last share the prefix common to all
+    let (first,last) = (&items[0].0[depth..],&items[items.len() - 1].0[depth..]);
+    let shared = first.iter().zip(last.iter()).take_while(|(a,b)| a == b).count();
+    if shared > 0 {
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&&encode_path(&first[..shared],false)[..]);
+        append_child(&mut stream,encode_node(items,depth + shared));
+        return stream.out();
+    }
+    let mut stream = RlpStream::new_list(17);
+    let (value,mut rest) = if first.is_empty() {
+        (Some(items[0].1),&items[1..])
+    } else {
+        (None,items)
+    };
+    for nibble in 0..16 {
+        let count = rest.iter().take_while(|(key,_)| key[depth] == nibble).count();
+        let (group,remaining) = rest.split_at(count);
+        rest = remaining;
+        if group.is_empty() {
+            stream.append_empty_data();
+        } else {
+            append_child(&mut stream,encode_node(group,depth + 1));
+        }
+    }
+    match value {
+        Some(value) => { stream.append(&value); },
+        None => { stream.append_empty_data(); },
+    }
+    stream.out()
+}
+
+
+/// Append a reference to `node`, embedding it directly if it is
+/// under 32 bytes.
+///
+fn append_child(stream: &mut RlpStream, node: Vec<u8>) {
+    if node.len() < 32 {
+        stream.append_raw(&node,1);
+    } else {
+        let hash = crypto::keccak(node.as_slice());
+        let hash: &[u8] = hash.as_ref();
+        stream.append(&hash);
+    }
+}
+
+
+/// Hex-prefix encode a path of nibbles.
+///
+fn encode_path(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 2 } else { 0 };
+    let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        encoded.push((flag + 1) << 4 | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        encoded.push(flag << 4);
+        nibbles
+    };
+    for pair in rest.chunks(2) {
+        encoded.push(pair[0] << 4 | pair[1]);
+    }
+    encoded
+}
+
+
+/// Decode a hex-prefix encoded path into its nibbles, and whether it
+/// belongs to a leaf (as opposed to an extension).
+///
+fn decode_path(encoded: &[u8]) -> Result<(Vec<u8>,bool),ProofError> {
+    let (first,rest) = encoded.split_first()
+        .ok_or_else(|| ProofError::Malformed("empty node path".into()))?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return Err(ProofError::Malformed(format!("invalid path flag {}",flag)));
+    }
+    let mut partial = Vec::with_capacity(rest.len() * 2 + 1);
+    if flag & 1 == 1 {
+        partial.push(first & 0x0f);
+    }
+    partial.extend(nibbles(rest));
+    Ok((partial,flag & 2 == 2))
+}
+
+
+fn nibbles(bytes: &[u8]) -> Vec<u8> {
+    let mut nibbles = Vec::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        nibbles.push(byte >> 4);
+        nibbles.push(byte & 0x0f);
+    }
+    nibbles
+}
+
+
+fn non_empty(data: &[u8]) -> Option<Vec<u8>> {
+    if data.is_empty() { None } else { Some(data.to_vec()) }
+}
+
+
+fn decode_uint(data: &[u8]) -> Result<U256,ProofError> {
+    if data.len() > 32 {
+        return Err(ProofError::Malformed(format!("integer of {} bytes",data.len())));
+    }
+    let mut buf = [0u8;32];
+    buf[32 - data.len()..].copy_from_slice(data);
+    Ok(U256::from(buf))
+}
+
+
+fn decode_hash(data: &[u8]) -> Result<H256,ProofError> {
+    if data.len() != 32 {
+        return Err(ProofError::Malformed(format!("hash of {} bytes",data.len())));
+    }
+    let mut buf = [0u8;32];
+    buf.copy_from_slice(data);
+    Ok(H256::from(buf))
+}
+
+
+#[cfg(test)]
+mod test {
+    use trie::{self,ProofError};
+    use types::{Bytes,H256};
+
+    /// Storage trie with slots `0`, plus two slots whose hashed keys share its first
+    /// nibble and first 60 nibbles respectively.  Values are `0x2a`, `0x0100` & `0x07`.
+    const ROOT: &str = "0xca03db0c4172ca063931aa257e37a49d2d2c313f58bd31b635b2a7155d1b7d3e";
+
+    /// Branch node at the root of the trie.
+    const BRANCH: &str = "0xf8518080a081a0befad2e91f9a62edd51fc59c7b7eee5de61296619dbb4ca572c4399517f7a091d97d425047a8dd41d7d1489d01ddea8b20e7191ddbcab8eb0b4ca152a1858980808080808080808080808080";
+
+    /// Extension node holding an inline branch, which in turn holds two inline leaves.
+    const EXTENSION: &str = "0xf8399e190decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3d98080808080808080808080808080c48235632ac48235630780";
+
+
+    fn proof(nodes: &[&str]) -> Vec<Bytes> {
+        nodes.iter().map(|node| node.parse().unwrap()).collect()
+    }
+
+
+    fn slot(index: u8) -> [u8;32] {
+        let mut slot = [0u8;32];
+        slot[31] = index;
+        slot
+    }
+
+
+    #[test]
+    fn present_value() {
+        let root: H256 = ROOT.parse().unwrap();
+        let nodes = proof(&[BRANCH,EXTENSION]);
+        assert_eq!(trie::verify_proof(root,&slot(0),&nodes),Ok(Some(vec![0x2a])));
+    }
+
+
+    #[test]
+    fn absent_value() {
+        let root: H256 = ROOT.parse().unwrap();
+        // hashed key of slot one starts with nibble `0xb`, which is empty in the root branch
+        assert_eq!(trie::verify_proof(root,&slot(1),&proof(&[BRANCH])),Ok(None));
+        // any key proves absent in the empty trie, given an empty proof
+        let empty = H256::from(trie::EMPTY_ROOT);
+        assert_eq!(trie::verify_proof(empty,&slot(0),&[]),Ok(None));
+    }
+
+
+    #[test]
+    fn invalid_proofs() {
+        let root: H256 = ROOT.parse().unwrap();
+        assert_eq!(trie::verify_proof(root,&slot(0),&proof(&[BRANCH])),Err(ProofError::Incomplete));
+        assert_eq!(trie::verify_proof(root,&slot(0),&proof(&[BRANCH,EXTENSION,BRANCH])),Err(ProofError::Trailing));
+        assert_eq!(trie::verify_proof(root,&slot(1),&proof(&[BRANCH,EXTENSION])),Err(ProofError::Trailing));
+        assert_eq!(trie::verify_proof(root,&slot(0),&proof(&[EXTENSION])),Err(ProofError::BadHash));
+        // flip the last byte of the extension node
+        let mut tampered = proof(&[BRANCH,EXTENSION]);
+        let mut node = tampered[1][..].to_vec();
+        *node.last_mut().unwrap() ^= 1;
+        tampered[1] = Bytes::from(node);
+        assert_eq!(trie::verify_proof(root,&slot(0),&tampered),Err(ProofError::BadHash));
+        assert_eq!(trie::verify_proof(root,&slot(0),&[]),Err(ProofError::Incomplete));
+    }
+
+
+    #[test]
+    fn trie_root_vectors() {
+        assert_eq!(trie::trie_root(&[]),H256::from(trie::EMPTY_ROOT));
+        // `dogs` vector from the ethereum/tests trie suite
+        let pairs: &[(&[u8],&[u8])] = &[
+            (b"doe",b"reindeer"),
+            (b"dog",b"puppy"),
+            (b"dogglesworth",b"cat"),
+        ];
+        let expect: H256 = "0x8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3".parse().unwrap();
+        assert_eq!(trie::trie_root(pairs),expect);
+        // insertion order does not matter
+        let pairs: &[(&[u8],&[u8])] = &[
+            (b"horse",b"stallion"),
+            (b"do",b"verb"),
+            (b"doge",b"coin"),
+            (b"dog",b"puppy"),
+        ];
+        let expect: H256 = "0x5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84".parse().unwrap();
+        assert_eq!(trie::trie_root(pairs),expect);
+    }
+}
@@ -1,10 +1,17 @@
-use types::{H256,U256,Bytes};
+use types::{H256,U256,Bytes,TxInfo};
 use crypto::Address;
+use serde::de::DeserializeOwned;
+use serde_json::{Map,Value};
 
 
 /// The block type returned from RPC calls.
 /// This is generic over a `TX` type.
-#[derive(Hash,Debug,Clone,PartialEq,Eq,Deserialize,Serialize)]
+///
+/// Fields which were introduced by later forks (or which are not reported by
+/// all clients) are optional.  Fields not known to this type are preserved
+/// in `extra`.
+///
+#[derive(Debug,Clone,PartialEq,Deserialize,Serialize)]
 pub struct Block<TX> {
     /// Hash of the block
     pub hash: Option<H256>,
@@ -37,16 +44,48 @@ pub struct Block<TX> {
     /// Extra data
     #[serde(rename = "extraData")]
     pub extra_data: Bytes,
-    ///// Logs bloom
-    //#[serde(rename = "logsBloom")]
-    //pub logs_bloom: H2048,
+    /// Logs bloom
+    #[serde(rename = "logsBloom")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub logs_bloom: Option<Bytes>,
     /// Timestamp
     pub timestamp: U256,
     /// Difficulty
     pub difficulty: U256,
-    /// Total difficulty
+    /// Total difficulty (removed from post-merge node output)
     #[serde(rename = "totalDifficulty")]
-    pub total_difficulty: U256,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub total_difficulty: Option<U256>,
+    /// Mix hash (`prevRandao` post-merge)
+    #[serde(rename = "mixHash")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub mix_hash: Option<H256>,
+    /// Proof-of-work nonce (zero post-merge)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<Bytes>,
+    /// Base fee per gas (post-london)
+    #[serde(rename = "baseFeePerGas")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub base_fee_per_gas: Option<U256>,
+    /// Validator withdrawals (post-shanghai)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub withdrawals: Option<Vec<Withdrawal>>,
+    /// Withdrawals root hash (post-shanghai)
+    #[serde(rename = "withdrawalsRoot")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub withdrawals_root: Option<H256>,
+    /// Total blob gas consumed by this block (post-cancun)
+    #[serde(rename = "blobGasUsed")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub blob_gas_used: Option<U256>,
+    /// Running excess blob gas (post-cancun)
+    #[serde(rename = "excessBlobGas")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub excess_blob_gas: Option<U256>,
+    /// Parent beacon block root (post-cancun)
+    #[serde(rename = "parentBeaconBlockRoot")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub parent_beacon_block_root: Option<H256>,
     /// Seal fields
     #[serde(default, rename = "sealFields")]
     pub seal_fields: Vec<Bytes>,
@@ -56,5 +95,42 @@ pub struct Block<TX> {
     pub transactions: Vec<TX>,
     /// Size in bytes
     pub size: Option<U256>,
+    /// Fields not otherwise represented by this type
+    #[serde(flatten)]
+    pub extra: Map<String,Value>,
+}
+
+
+/// Representation of the transactions included in a `Block`.
+///
+/// Blocks may be loaded with transaction hashes (`H256`), or with full
+/// transaction objects (`TxInfo`).
+///
+pub trait BlockTx: DeserializeOwned + Send + 'static {
+
+    /// Whether the node must be asked for full transaction objects
+    const FULL: bool;
+}
+
+
+impl BlockTx for H256 { const FULL: bool = false; }
+
+
+impl BlockTx for TxInfo { const FULL: bool = true; }
+
+
+/// A validator withdrawal from the beacon chain.
+///
+#[derive(Debug,Clone,PartialEq,Deserialize,Serialize)]
+pub struct Withdrawal {
+    /// Monotonically increasing withdrawal index
+    pub index: U256,
+    /// Index of the withdrawing validator
+    #[serde(rename = "validatorIndex")]
+    pub validator_index: U256,
+    /// Recipient of the withdrawn funds
+    pub address: Address,
+    /// Withdrawn amount (in gwei)
+    pub amount: U256,
 }
 
@@ -10,6 +10,8 @@ mod filter;
 mod block;
 mod uint8;
 mod log;
+mod sync;
+mod proof;
 
 pub use self::uint8::Uint8;
 pub use self::transaction::{
@@ -17,7 +19,8 @@ pub use self::transaction::{
     TxInfo,
     TxCall,
     Receipt,
-    Status, 
+    Status,
+    AccessListItem,
 };
 pub use self::filter::{
     Filter,
@@ -26,8 +29,10 @@ pub use self::filter::{
     Topics,
     Origin,
 };
-pub use self::block::Block;
+pub use self::block::{Block,BlockTx,Withdrawal};
 pub use self::log::Log;
+pub use self::sync::{SyncState,SyncProgress};
+pub use self::proof::{AccountProof,StorageProof};
 
 use serde::de::{Deserialize,Deserializer};
 use serde::ser::{Serialize,Serializer};
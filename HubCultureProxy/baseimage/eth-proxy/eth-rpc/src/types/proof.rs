@@ -0,0 +1,115 @@
+use types::{Bytes,U256,H256};
+use trie::{self,Account,ProofError};
+use crypto::Address;
+
+
+/// Account & storage proofs, as returned by `eth_getProof`.
+///
+/// Values are as claimed by the node; call `verify` before trusting them.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct AccountProof {
+    /// Address of the account
+    pub address: Address,
+    /// Balance (in wei)
+    pub balance: U256,
+    /// Hash of the account's code
+    #[serde(rename = "codeHash")]
+    pub code_hash: H256,
+    /// Account nonce
+    pub nonce: U256,
+    /// Root of the account's storage trie
+    #[serde(rename = "storageHash")]
+    pub storage_hash: H256,
+    /// Nodes along the account's path in the state trie
+    #[serde(rename = "accountProof")]
+    pub account_proof: Vec<Bytes>,
+    /// Proofs for each requested storage slot
+    #[serde(rename = "storageProof")]
+    pub storage_proof: Vec<StorageProof>,
+}
+
+
+impl AccountProof {
+
+    /// Verify the account, and all storage slots, against the state root
+    /// of the block at which the proof was requested.
+    ///
+    pub fn verify(&self, state_root: H256) -> Result<(),ProofError> {
+        let proven = match trie::verify_proof(state_root,self.address.as_ref(),&self.account_proof)? {
+            Some(encoded) => Account::decode(&encoded)?,
+            None => Account::empty(),
+        };
+        if proven.nonce != self.nonce {
+            return Err(ProofError::Mismatch("nonce"));
+        }
+        if proven.balance != self.balance {
+            return Err(ProofError::Mismatch("balance"));
+        }
+        if proven.storage_root != self.storage_hash {
+            return Err(ProofError::Mismatch("storage hash"));
+        }
+        if proven.code_hash != self.code_hash {
+            return Err(ProofError::Mismatch("code hash"));
+        }
+        for storage in self.storage_proof.iter() {
+            storage.verify(self.storage_hash)?;
+        }
+        Ok(())
+    }
+
+    /// Verify the proof as the response to a request for the storage `keys`
+    /// of `address`, against the state root of the block at which it was
+    /// requested.
+    ///
+    /// `verify` alone only shows that the proof is internally consistent; a
+    /// valid proof of some other account or slot passes it.
+    ///
+    pub fn verify_request(&self, address: Address, keys: &[H256], state_root: H256) -> Result<(),ProofError> {
+        if self.address != address {
+            return Err(ProofError::Mismatch("address"));
+        }
+        let same_keys = self.storage_proof.len() == keys.len()
+            && self.storage_proof.iter().zip(keys).all(|(proof,key)| proof.key[..] == key[..]);
+        if !same_keys {
+            return Err(ProofError::Mismatch("storage keys"));
+        }
+        self.verify(state_root)
+    }
+
+    /// Get the claimed value of a storage slot, if it was requested.
+    ///
+    pub fn storage(&self, key: U256) -> Option<U256> {
+        self.storage_proof.iter().find(|proof| proof.key == key).map(|proof| proof.value)
+    }
+}
+
+
+/// Proof of a single storage slot.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct StorageProof {
+    /// Storage slot
+    pub key: U256,
+    /// Value of the slot (zero if unset)
+    pub value: U256,
+    /// Nodes along the slot's path in the storage trie
+    pub proof: Vec<Bytes>,
+}
+
+
+impl StorageProof {
+
+    /// Verify the slot's value against the root of its storage trie.
+    ///
+    pub fn verify(&self, storage_root: H256) -> Result<(),ProofError> {
+        let proven = match trie::verify_proof(storage_root,&self.key,&self.proof)? {
+            Some(encoded) => trie::decode_storage(&encoded)?,
+            None => U256::from(0u64),
+        };
+        if proven != self.value {
+            return Err(ProofError::Mismatch("storage value"));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use types::U256;
+use serde::de::{Deserialize,Deserializer,Error};
+use serde::ser::{Serialize,Serializer};
+use serde_json::{Map,Value};
+
+
+/// Sync state of a node (as reported by `eth_syncing`).
+///
+/// ```
+/// # extern crate ethrpc;
+/// # extern crate serde_json;
+/// # use ethrpc::types::SyncState;
+/// # fn main() {
+/// let idle: SyncState = serde_json::from_str("false").unwrap();
+/// assert_eq!(idle,SyncState::NotSyncing);
+///
+/// let syncing: SyncState = serde_json::from_str(r#"{
+///     "startingBlock": "0x0",
+///     "currentBlock": "0x10",
+///     "highestBlock": "0x20"
+/// }"#).unwrap();
+/// assert!(syncing.is_syncing());
+/// # }
+/// ```
+///
+#[derive(Debug,Clone,PartialEq)]
+pub enum SyncState {
+    /// Node is not syncing (encoded as `false`)
+    NotSyncing,
+    /// Node is syncing
+    Syncing(SyncProgress),
+}
+
+
+impl SyncState {
+
+    /// Check if the node is currently syncing.
+    ///
+    pub fn is_syncing(&self) -> bool {
+        match self {
+            SyncState::NotSyncing => false,
+            SyncState::Syncing(_) => true,
+        }
+    }
+}
+
+
+impl Serialize for SyncState {
+
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok,S::Error> {
+        match self {
+            SyncState::NotSyncing => serializer.serialize_bool(false),
+            SyncState::Syncing(progress) => progress.serialize(serializer),
+        }
+    }
+}
+
+
+impl<'de> Deserialize<'de> for SyncState {
+
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self,D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Flag(bool),
+            Progress(SyncProgress),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Flag(false) => Ok(SyncState::NotSyncing),
+            Raw::Flag(true) => Err(D::Error::custom("expected `false` or sync progress object")),
+            Raw::Progress(progress) => Ok(SyncState::Syncing(progress)),
+        }
+    }
+}
+
+
+/// Progress of an ongoing sync.
+///
+/// Clients report a variety of additional progress indicators (e.g. state
+/// download counts), which are preserved in `extra`.
+///
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct SyncProgress {
+    /// Block at which the sync started
+    #[serde(rename = "startingBlock")]
+    pub starting_block: U256,
+    /// Block the node has currently synced to
+    #[serde(rename = "currentBlock")]
+    pub current_block: U256,
+    /// Highest block known to the node
+    #[serde(rename = "highestBlock")]
+    pub highest_block: U256,
+    /// Client-specific progress fields
+    #[serde(flatten)]
+    pub extra: Map<String,Value>,
+}
@@ -1,5 +1,6 @@
 use types::{Log,Bytes,U256,H256};
 use crypto::Address;
+use serde_json::{Map,Value};
 
 /// Basic description of a transaction
 ///
@@ -95,6 +96,11 @@ pub struct TxCall {
 
 
 /// Description of a Transaction, pending or in the chain.
+///
+/// Fields which depend upon the transaction type (or which are not reported
+/// by all clients) are optional.  Fields not known to this type are preserved
+/// in `extra`.
+///
 #[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
 pub struct TxInfo {
     /// Hash
@@ -124,10 +130,87 @@ pub struct TxInfo {
     pub gas_limit: U256,
     /// Input data
     pub input: Bytes,
+    /// Transaction type (absent for legacy transactions on older nodes)
+    #[serde(rename = "type")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub tx_type: Option<U256>,
+    /// Chain id (absent for pre-EIP-155 transactions)
+    #[serde(rename = "chainId")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<U256>,
+    /// Maximum total fee per gas (EIP-1559)
+    #[serde(rename = "maxFeePerGas")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<U256>,
+    /// Maximum priority fee per gas (EIP-1559)
+    #[serde(rename = "maxPriorityFeePerGas")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<U256>,
+    /// Access list (EIP-2930)
+    #[serde(rename = "accessList")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub access_list: Option<Vec<AccessListItem>>,
+    /// Signature `v` value
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub v: Option<U256>,
+    /// Signature `r` value
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub r: Option<U256>,
+    /// Signature `s` value
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub s: Option<U256>,
+    /// Signature y-parity (typed transactions)
+    #[serde(rename = "yParity")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub y_parity: Option<U256>,
+    /// Fields not otherwise represented by this type
+    #[serde(flatten)]
+    pub extra: Map<String,Value>,
+}
+
+
+/// Entry of an EIP-2930 access list.
+///
+#[derive(Hash,Debug,Clone,PartialEq,Eq,Deserialize,Serialize)]
+pub struct AccessListItem {
+    /// Accessed account
+    pub address: Address,
+    /// Accessed storage slots
+    #[serde(rename = "storageKeys")]
+    pub storage_keys: Vec<H256>,
 }
 
 
 /// "Receipt" of an executed transaction: details of its execution.
+///
+/// Fields not known to this type are preserved in `extra`.
+///
+/// ```
+/// # extern crate ethrpc;
+/// # extern crate serde_json;
+/// # use ethrpc::types::Receipt;
+/// # fn main() {
+/// let receipt: Receipt = serde_json::from_str(r#"{
+///     "transactionHash": "0x9fc76417374aa880d4449a1f7f31ec597f00b1f6f3dd2d66f4c9c6c445836d8b",
+///     "transactionIndex": "0x0",
+///     "blockHash": "0xb3b20624f8f0f86eb50dd04688409e5cea4bd02d700bf6e79e9384d47d6a5a35",
+///     "blockNumber": "0x5bad55",
+///     "cumulativeGasUsed": "0x5208",
+///     "gasUsed": "0x5208",
+///     "contractAddress": null,
+///     "logs": [],
+///     "status": "0x1",
+///     "type": "0x2",
+///     "effectiveGasPrice": "0x3b9aca00",
+///     "l1Fee": "0x10"
+/// }"#).unwrap();
+///
+/// assert_eq!(receipt.tx_type,Some(2u64.into()));
+/// assert_eq!(receipt.effective_gas_price,Some(1_000_000_000u64.into()));
+/// assert_eq!(receipt.extra["l1Fee"],"0x10");
+/// # }
+/// ```
+///
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Receipt {
     /// Transaction hash.
@@ -154,7 +237,32 @@ pub struct Receipt {
     /// Logs generated within this transaction.
     pub logs: Vec<Log>,
     /// Execution status (post-byzantium)
+    #[serde(default)]
     pub status: Option<Status>,
+    /// Post-transaction state root (pre-byzantium)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub root: Option<H256>,
+    /// Sender
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub from: Option<Address>,
+    /// Recipient (None when contract creation)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub to: Option<Address>,
+    /// Price per gas actually paid (post-london)
+    #[serde(rename = "effectiveGasPrice")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub effective_gas_price: Option<U256>,
+    /// Transaction type
+    #[serde(rename = "type")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub tx_type: Option<U256>,
+    /// Bloom filter of logs generated within this transaction
+    #[serde(rename = "logsBloom")]
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub logs_bloom: Option<Bytes>,
+    /// Fields not otherwise represented by this type
+    #[serde(flatten)]
+    pub extra: Map<String,Value>,
 }
 
 
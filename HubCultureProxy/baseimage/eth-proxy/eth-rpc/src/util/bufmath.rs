@@ -31,6 +31,8 @@
 //!
 //! # }
 //! ```
+use util::trim;
+use std::cmp::Ordering;
 
 
 /// Overflowing addition.
@@ -78,6 +80,143 @@ pub fn sub(lhs: &mut [u8], rhs: &[u8]) -> bool {
     carry
 }
 
+
+/// Overflowing multiplication.
+///
+/// Performs overflowing mul-assign between two byte-arrays as if they were
+/// big-endian unsigned integers.  Returns `true` if overflow occurred (in
+/// which case `lhs` holds the truncated product).
+///
+/// ## Panics
+///
+/// This function panics if given buffers of differing lengths.
+///
+pub fn mul(lhs: &mut [u8], rhs: &[u8]) -> bool {
+    assert_eq!(lhs.len(),rhs.len(),"lhs and rhs buffers have different lengths");
+    let len = lhs.len();
+    // column sums, least-significant byte first.
+    let mut columns = vec![0u64;len * 2];
+    for (i,l) in lhs.iter().rev().cloned().enumerate().filter(|(_,l)| *l != 0) {
+        for (j,r) in rhs.iter().rev().cloned().enumerate() {
+            columns[i + j] += l as u64 * r as u64;
+        }
+    }
+    let mut carry = 0u64;
+    for column in columns.iter_mut() {
+        let sum = *column + carry;
+        *column = sum & 0xff;
+        carry = sum >> 8;
+    }
+    debug_assert!(carry == 0,"product cannot exceed twice the operand width");
+    for (l,column) in lhs.iter_mut().rev().zip(columns.iter()) {
+        *l = *column as u8;
+    }
+    columns[len..].iter().any(|column| *column != 0)
+}
+
+
+/// Division with remainder.
+///
+/// Performs div-assign between two byte-arrays as if they were big-endian
+/// unsigned integers, writing the remainder to `rem`.
+///
+/// ## Panics
+///
+/// This function panics if given buffers of differing lengths, or if
+/// `rhs` is zero.
+///
+pub fn div(lhs: &mut [u8], rhs: &[u8], rem: &mut [u8]) {
+    assert_eq!(lhs.len(),rhs.len(),"lhs and rhs buffers have different lengths");
+    assert_eq!(lhs.len(),rem.len(),"lhs and rem buffers have different lengths");
+    assert!(rhs.iter().any(|byte| *byte != 0),"attempt to divide by zero");
+    for byte in rem.iter_mut() { *byte = 0; }
+    for index in 0..lhs.len() {
+        let mut quotient = 0u8;
+        for bit in (0..8).rev() {
+            let overflow = shl_one(rem,(lhs[index] >> bit) & 1);
+            // if the shift overflowed, the true remainder exceeds `rhs`, and
+            // the wrapping subtraction below yields the correct result.
+            if overflow || cmp(rem,rhs) != Ordering::Less {
+                sub(rem,rhs);
+                quotient |= 1 << bit;
+            }
+        }
+        lhs[index] = quotient;
+    }
+}
+
+
+/// Compare two byte-arrays as if they were big-endian unsigned integers.
+///
+/// Unlike the arithmetic operations, buffers may be of differing lengths.
+///
+/// ```
+/// # extern crate ethrpc;
+/// # use ethrpc::util::bufmath;
+/// # use std::cmp::Ordering;
+/// # fn main() {
+/// assert_eq!(bufmath::cmp(&[0x00,0x00,0x01],&[0x01]),Ordering::Equal);
+/// assert_eq!(bufmath::cmp(&[0x01,0x00],&[0xff]),Ordering::Greater);
+/// # }
+/// ```
+///
+pub fn cmp(lhs: &[u8], rhs: &[u8]) -> Ordering {
+    let (lhs,rhs) = (trim(lhs),trim(rhs));
+    lhs.len().cmp(&rhs.len()).then_with(|| lhs.cmp(rhs))
+}
+
+
+/// Overflowing multiplication by a machine word.
+///
+/// Returns `true` if overflow occurred.
+///
+#[inline]
+pub fn mul_word(lhs: &mut [u8], rhs: u32) -> bool {
+    let mut carry = 0u64;
+    for l in lhs.iter_mut().rev() {
+        let product = *l as u64 * rhs as u64 + carry;
+        *l = product as u8;
+        carry = product >> 8;
+    }
+    carry != 0
+}
+
+
+/// Division by a machine word.
+///
+/// Performs div-assign and returns the remainder.
+///
+/// ## Panics
+///
+/// This function panics if `rhs` is zero.
+///
+#[inline]
+pub fn div_word(lhs: &mut [u8], rhs: u32) -> u32 {
+    assert!(rhs != 0,"attempt to divide by zero");
+    let mut rem = 0u64;
+    for l in lhs.iter_mut() {
+        let acc = (rem << 8) | *l as u64;
+        *l = (acc / rhs as u64) as u8;
+        rem = acc % rhs as u64;
+    }
+    rem as u32
+}
+
+
+/// Shift left by one bit, shifting `bit` in from the right.  Returns
+/// the bit shifted out on the left.
+///
+#[inline]
+fn shl_one(buf: &mut [u8], bit: u8) -> bool {
+    let mut carry = bit;
+    for byte in buf.iter_mut().rev() {
+        let next = *byte >> 7;
+        *byte = (*byte << 1) | carry;
+        carry = next;
+    }
+    carry != 0
+}
+
 #[cfg(test)]
 mod test {
     use util::bufmath;
@@ -163,6 +302,75 @@ mod test {
     }
 
 
+    #[test]
+    fn small_multiplication() {
+        for a in 0u64..512u64 {
+            for b in 0u64..512u64 {
+                test_mul(a,b);
+            }
+        }
+    }
+
+
+    #[test]
+    fn small_division() {
+        for a in 0u64..512u64 {
+            for b in 1u64..512u64 {
+                test_div(a,b);
+            }
+        }
+    }
+
+
+    #[test]
+    fn fuzz_multiplication() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..2048 {
+            test_mul(rng.gen(),rng.gen());
+            test_mul(rng.gen::<u32>() as u64,rng.gen::<u32>() as u64);
+        }
+    }
+
+
+    #[test]
+    fn fuzz_division() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..2048 {
+            test_div(rng.gen(),rng.gen::<u64>().max(1));
+            test_div(rng.gen(),rng.gen::<u16>().max(1) as u64);
+        }
+    }
+
+
+    #[test]
+    fn fuzz_words() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..2048 {
+            let (num,word) = (rng.gen::<u64>(),rng.gen::<u32>().max(1));
+            let mut buf = into_bytes(num);
+            let overflow = bufmath::mul_word(&mut buf,word);
+            let (expect,expect_overflow) = num.overflowing_mul(word as u64);
+            assert_eq!(from_bytes(buf),expect);
+            assert_eq!(overflow,expect_overflow);
+            let mut buf = into_bytes(num);
+            let rem = bufmath::div_word(&mut buf,word);
+            assert_eq!(from_bytes(buf),num / word as u64);
+            assert_eq!(rem as u64,num % word as u64);
+        }
+    }
+
+
+    #[test]
+    fn fuzz_comparison() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..2048 {
+            let (a,b) = (rng.gen::<u64>(),rng.gen::<u64>() >> rng.gen_range(0,64));
+            assert_eq!(bufmath::cmp(&into_bytes(a),&into_bytes(b)),a.cmp(&b));
+            assert_eq!(bufmath::cmp(&into_bytes(b),&into_bytes(b)[4..]),b.cmp(&(b & 0xffffffff)));
+        }
+    }
+
+
     #[inline]
     fn test_add(num_a: u64, num_b: u64) { 
         let buf_a = into_bytes(num_a);
@@ -191,4 +399,34 @@ mod test {
         assert_eq!(num_sub,from_bytes(buf_sub));
         assert_eq!(num_overflow,buf_overflow);
     }
+
+
+    #[inline]
+    fn test_mul(num_a: u64, num_b: u64) {
+        let buf_a = into_bytes(num_a);
+        let buf_b = into_bytes(num_b);
+        let (num_mul,num_overflow) = num_a.overflowing_mul(num_b);
+        let (buf_mul,buf_overflow) = {
+            let mut mul = buf_a.clone();
+            let overflow = bufmath::mul(&mut mul,&buf_b);
+            (mul,overflow)
+        };
+        assert_eq!(num_mul,from_bytes(buf_mul));
+        assert_eq!(num_overflow,buf_overflow);
+    }
+
+
+    #[inline]
+    fn test_div(num_a: u64, num_b: u64) {
+        let buf_a = into_bytes(num_a);
+        let buf_b = into_bytes(num_b);
+        let (buf_div,buf_rem) = {
+            let mut div = buf_a.clone();
+            let mut rem = [0u8;8];
+            bufmath::div(&mut div,&buf_b,&mut rem);
+            (div,rem)
+        };
+        assert_eq!(num_a / num_b,from_bytes(buf_div));
+        assert_eq!(num_a % num_b,from_bytes(buf_rem));
+    }
 }
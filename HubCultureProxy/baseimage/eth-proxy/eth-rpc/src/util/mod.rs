@@ -1,6 +1,8 @@
 //! Misc helper utilities
 //!
 pub mod bufmath;
+pub mod units;
+pub mod queue;
 
 
 /// Trim leading zeroes from a slice.
@@ -10,3 +12,16 @@ pub fn trim(bytes: &[u8]) -> &[u8] {
         .unwrap_or(bytes.len());
     &bytes[start..]
 }
+
+
+/// Interpret a big-endian unsigned integer as a `u64`.
+///
+/// Returns `None` if the value does not fit in 64 bits.
+///
+pub fn as_u64(bytes: &[u8]) -> Option<u64> {
+    let trimmed = trim(bytes);
+    if trimmed.len() > 8 {
+        return None;
+    }
+    Some(trimmed.iter().fold(0u64,|acc,b| (acc << 8) | *b as u64))
+}
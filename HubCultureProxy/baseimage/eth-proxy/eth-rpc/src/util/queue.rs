@@ -0,0 +1,219 @@
+//! Bounded work queues.
+//!
+//! Thin wrapper around an mpsc channel which enforces a fixed capacity shared
+//! by *all* senders, and which exposes the current queue depth.  Senders never
+//! block; a send to a full queue fails immediately with `SendError::Overloaded`.
+//!
+//! ## Example
+//!
+//! ```
+//! # extern crate ethrpc;
+//! # use ethrpc::util::queue::{self,SendError};
+//! # fn main() {
+//! let (tx,_rx) = queue::bounded(2);
+//!
+//! tx.try_send("spam").unwrap();
+//! tx.try_send("eggs").unwrap();
+//!
+//! assert_eq!(tx.depth(),2);
+//! assert_eq!(tx.try_send("cats"),Err(SendError::Overloaded("cats")));
+//! # }
+//! ```
+//!
+use tokio_channel::mpsc;
+use tokio::prelude::*;
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::sync::Arc;
+use std::{fmt,error};
+
+
+/// Create a new bounded queue.
+///
+/// ## Panics
+///
+/// This function panics if `capacity` is zero.
+///
+pub fn bounded<T>(capacity: usize) -> (Sender<T>,Receiver<T>) {
+    assert!(capacity > 0,"queue capacity must be nonzero");
+    let (tx,rx) = mpsc::unbounded();
+    let depth = Arc::new(AtomicUsize::new(0));
+    let sender = Sender { inner: tx, depth: depth.clone(), capacity };
+    let receiver = Receiver { inner: rx, depth };
+    (sender,receiver)
+}
+
+
+/// Sending half of a bounded queue.
+///
+#[derive(Debug)]
+pub struct Sender<T> {
+    inner: mpsc::UnboundedSender<T>,
+    depth: Arc<AtomicUsize>,
+    capacity: usize,
+}
+
+
+impl<T> Clone for Sender<T> {
+
+    fn clone(&self) -> Self {
+        let (inner,depth,capacity) = (self.inner.clone(),self.depth.clone(),self.capacity);
+        Self { inner, depth, capacity }
+    }
+}
+
+
+impl<T> Sender<T> {
+
+    /// Attempt to push an item onto the queue.
+    ///
+    pub fn try_send(&self, item: T) -> Result<(),SendError<T>> {
+        let depth = self.depth.fetch_add(1,Ordering::SeqCst);
+        if depth >= self.capacity {
+            self.depth.fetch_sub(1,Ordering::SeqCst);
+            return Err(SendError::Overloaded(item));
+        }
+        self.inner.unbounded_send(item).map_err(|err| {
+            self.depth.fetch_sub(1,Ordering::SeqCst);
+            SendError::Closed(err.into_inner())
+        })
+    }
+
+    /// Number of items currently waiting in the queue.
+    ///
+    pub fn depth(&self) -> usize { self.depth.load(Ordering::SeqCst) }
+
+    /// Maximum number of items which may wait in the queue.
+    ///
+    pub fn capacity(&self) -> usize { self.capacity }
+}
+
+
+/// Receiving half of a bounded queue.
+///
+#[derive(Debug)]
+pub struct Receiver<T> {
+    inner: mpsc::UnboundedReceiver<T>,
+    depth: Arc<AtomicUsize>,
+}
+
+
+impl<T> Receiver<T> {
+
+    /// Number of items currently waiting in the queue.
+    ///
+    pub fn depth(&self) -> usize { self.depth.load(Ordering::SeqCst) }
+}
+
+
+impl<T> Stream for Receiver<T> {
+
+    type Item = T;
+
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>,Self::Error> {
+        let item = try_ready!(self.inner.poll());
+        if item.is_some() {
+            self.depth.fetch_sub(1,Ordering::SeqCst);
+        }
+        Ok(Async::Ready(item))
+    }
+}
+
+
+/// Indicates failure to push an item onto a queue.
+///
+/// The rejected item is returned to the caller.
+///
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum SendError<T> {
+    /// Queue is at capacity
+    Overloaded(T),
+    /// Receiving half of the queue was dropped
+    Closed(T),
+}
+
+
+impl<T> SendError<T> {
+
+    /// Check if this error indicates that the queue was full.
+    ///
+    pub fn is_overloaded(&self) -> bool {
+        match self {
+            SendError::Overloaded(_) => true,
+            SendError::Closed(_) => false,
+        }
+    }
+
+    /// Recover the rejected item.
+    ///
+    pub fn into_inner(self) -> T {
+        match self {
+            SendError::Overloaded(item) => item,
+            SendError::Closed(item) => item,
+        }
+    }
+}
+
+
+impl<T> fmt::Display for SendError<T> {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SendError::Overloaded(_) => f.write_str("overloaded; queue is at capacity"),
+            SendError::Closed(_) => f.write_str("queue closed; receiver dropped"),
+        }
+    }
+}
+
+
+impl<T: fmt::Debug> error::Error for SendError<T> {
+
+    fn description(&self) -> &str {
+        match self {
+            SendError::Overloaded(_) => "overloaded",
+            SendError::Closed(_) => "queue closed",
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use util::queue::{self,SendError};
+    use tokio::prelude::*;
+
+
+    #[test]
+    fn depth_and_overload() {
+        let (tx,rx) = queue::bounded(2);
+        let other = tx.clone();
+        assert_eq!((tx.depth(),tx.capacity()),(0,2));
+        tx.try_send(1).unwrap();
+        other.try_send(2).unwrap();
+        // capacity is shared by all senders, & rejected items are not counted
+        assert_eq!(tx.try_send(3),Err(SendError::Overloaded(3)));
+        assert_eq!(other.try_send(4),Err(SendError::Overloaded(4)));
+        assert_eq!((tx.depth(),other.depth(),rx.depth()),(2,2,2));
+        // receiving frees capacity
+        let mut items = rx.wait();
+        assert_eq!(items.next(),Some(Ok(1)));
+        assert_eq!(tx.depth(),1);
+        tx.try_send(5).unwrap();
+        assert_eq!(items.get_ref().depth(),2);
+        assert_eq!(items.next(),Some(Ok(2)));
+        assert_eq!(items.next(),Some(Ok(5)));
+        assert_eq!(tx.depth(),0);
+        // sends to a dropped receiver fail without leaking depth
+        drop(items);
+        assert_eq!(tx.try_send(6),Err(SendError::Closed(6)));
+        assert_eq!(tx.depth(),0);
+    }
+
+
+    #[test]
+    #[should_panic(expected = "queue capacity must be nonzero")]
+    fn zero_capacity() {
+        let _ = queue::bounded::<()>(0);
+    }
+}
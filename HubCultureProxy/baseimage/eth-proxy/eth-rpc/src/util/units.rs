@@ -0,0 +1,381 @@
+//! Decimal formatting & unit conversion.
+//!
+//! Ethereum's rpc encodes all integers as hex, which is convenient for machines
+//! and miserable for humans.  This module converts 256-bit integers to and from
+//! their decimal representations, optionally scaled by a unit.
+//!
+//! ## Example
+//!
+//! ```
+//! # extern crate ethrpc;
+//! # use ethrpc::util::units::{self,Unit};
+//! # use ethrpc::types::U256;
+//! # fn main() {
+//! let one_ether: U256 = "0xde0b6b3a7640000".parse().unwrap();
+//!
+//! assert_eq!(units::to_decimal(&one_ether),"1000000000000000000");
+//! assert_eq!(units::format_units(&one_ether,Unit::Ether),"1");
+//! assert_eq!(units::format_units(&one_ether,Unit::Gwei),"1000000000");
+//!
+//! // Tokens with arbitrary decimals
+//! assert_eq!(units::format_units(&U256::from(1234u64),Unit::Decimals(2)),"12.34");
+//!
+//! assert_eq!(units::parse_units("1.0",Unit::Ether).unwrap(),one_ether);
+//! assert_eq!(units::parse_decimal("1000000000000000000").unwrap(),one_ether);
+//! assert!(units::parse_units("0.001",Unit::Decimals(2)).is_err());
+//! # }
+//! ```
+//!
+use serde::de::{self,Visitor};
+use util::{bufmath,trim};
+use types::U256;
+use std::str::FromStr;
+use std::{fmt,error};
+
+
+/// A denomination of some token.
+///
+#[derive(Hash,Debug,Copy,Clone,PartialEq,Eq)]
+pub enum Unit {
+    /// Base unit (no decimals)
+    Wei,
+    /// 10^9 wei
+    Gwei,
+    /// 10^18 wei
+    Ether,
+    /// Arbitrary number of decimals (e.g. for erc20 tokens)
+    Decimals(u8),
+}
+
+
+impl Unit {
+
+    /// Get the number of decimal places represented by this unit.
+    ///
+    pub fn decimals(&self) -> u8 {
+        match self {
+            Unit::Wei => 0,
+            Unit::Gwei => 9,
+            Unit::Ether => 18,
+            Unit::Decimals(decimals) => *decimals,
+        }
+    }
+}
+
+
+impl From<u8> for Unit {
+
+    fn from(decimals: u8) -> Self { Unit::Decimals(decimals) }
+}
+
+
+impl fmt::Display for Unit {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unit::Wei => f.write_str("wei"),
+            Unit::Gwei => f.write_str("gwei"),
+            Unit::Ether => f.write_str("ether"),
+            Unit::Decimals(decimals) => decimals.fmt(f),
+        }
+    }
+}
+
+
+impl FromStr for Unit {
+
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "wei" => Ok(Unit::Wei),
+            "gwei" => Ok(Unit::Gwei),
+            "ether" | "eth" => Ok(Unit::Ether),
+            other => other.parse().map(Unit::Decimals).map_err(|_| ParseError::UnknownUnit),
+        }
+    }
+}
+
+
+/// Render a big-endian unsigned integer as a decimal string.
+///
+pub fn to_decimal(num: &[u8]) -> String {
+    let mut buf = num.to_owned();
+    let mut digits = Vec::new();
+    while !trim(&buf).is_empty() {
+        let rem = bufmath::div_word(&mut buf,10);
+        digits.push(b'0' + rem as u8);
+    }
+    if digits.is_empty() {
+        digits.push(b'0');
+    }
+    digits.reverse();
+    String::from_utf8(digits).expect("decimal digits are always valid utf-8")
+}
+
+
+/// Parse a decimal string as a 256-bit integer.
+///
+pub fn parse_decimal(s: &str) -> Result<U256,ParseError> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    let mut num = U256::from([0u8;32]);
+    for c in s.chars() {
+        let digit = c.to_digit(10).ok_or(ParseError::InvalidDigit(c))?;
+        let overflow = bufmath::mul_word(&mut num,10) ||
+            bufmath::add(&mut num,&U256::from(digit as u64));
+        if overflow {
+            return Err(ParseError::Overflow);
+        }
+    }
+    Ok(num)
+}
+
+
+/// Parse a string as a 256-bit integer, interpreting it as hex if it is
+/// prefixed with `0x` and decimal otherwise.
+///
+pub fn parse_uint(s: &str) -> Result<U256,ParseError> {
+    let s = s.trim();
+    if s.starts_with("0x") {
+        s.parse().map_err(|_| ParseError::InvalidHex)
+    } else {
+        parse_decimal(s)
+    }
+}
+
+
+/// Render `num` as a decimal string, scaled by `unit`.
+///
+/// Trailing zeroes in the fractional part are omitted.
+///
+pub fn format_units(num: &[u8], unit: impl Into<Unit>) -> String {
+    let decimals = unit.into().decimals() as usize;
+    let mut digits = to_decimal(num);
+    if decimals == 0 {
+        return digits;
+    }
+    if digits.len() <= decimals {
+        let padding = "0".repeat(decimals + 1 - digits.len());
+        digits.insert_str(0,&padding);
+    }
+    let split = digits.len() - decimals;
+    let fraction = digits[split..].trim_right_matches('0').to_owned();
+    digits.truncate(split);
+    if !fraction.is_empty() {
+        digits.push('.');
+        digits.push_str(&fraction);
+    }
+    digits
+}
+
+
+/// Parse a (possibly fractional) decimal string scaled by `unit`.
+///
+/// Fails if the string specifies more fractional digits than `unit`
+/// is able to represent.
+///
+pub fn parse_units(s: &str, unit: impl Into<Unit>) -> Result<U256,ParseError> {
+    let decimals = unit.into().decimals();
+    let s = s.trim();
+    let (whole,fraction) = match s.find('.') {
+        Some(index) => (&s[..index],&s[index + 1..]),
+        None => (s,""),
+    };
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    let fraction = fraction.trim_right_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(ParseError::Precision { decimals });
+    }
+    let mut digits = String::with_capacity(whole.len() + decimals as usize);
+    digits.push_str(whole);
+    digits.push_str(fraction);
+    for _ in fraction.len()..decimals as usize {
+        digits.push('0');
+    }
+    parse_decimal(&digits)
+}
+
+
+/// Serde helpers for 256-bit integers which accept decimal input.
+///
+/// Deserialization accepts `0x`-prefixed hex strings, decimal strings,
+/// and integer literals.  Serialization uses the standard hex encoding.
+///
+pub mod serde_uint {
+    use serde::de::Deserializer;
+    use serde::ser::{Serialize,Serializer};
+    use types::U256;
+    use super::UintVisitor;
+
+    pub fn serialize<S: Serializer>(num: &U256, serializer: S) -> Result<S::Ok,S::Error> {
+        num.serialize(serializer)
+    }
+
+    pub fn deserialize<'de,D: Deserializer<'de>>(deserializer: D) -> Result<U256,D::Error> {
+        deserializer.deserialize_any(UintVisitor)
+    }
+}
+
+
+struct UintVisitor;
+
+
+impl<'de> Visitor<'de> for UintVisitor {
+
+    type Value = U256;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("hex string, decimal string, or unsigned integer")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value,E> {
+        Ok(U256::from(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value,E> {
+        parse_uint(value).map_err(E::custom)
+    }
+}
+
+
+/// Indicates failure to parse a decimal or unit string.
+///
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum ParseError {
+    /// Empty input
+    Empty,
+    /// Non-decimal character in input
+    InvalidDigit(char),
+    /// Malformed hex input
+    InvalidHex,
+    /// Value does not fit in 256 bits
+    Overflow,
+    /// More fractional digits than the unit supports
+    Precision {
+        decimals: u8,
+    },
+    /// Unrecognized unit name
+    UnknownUnit,
+}
+
+
+impl fmt::Display for ParseError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Empty => f.write_str("cannot parse integer from empty string"),
+            ParseError::InvalidDigit(c) => write!(f,"invalid decimal digit `{}`",c),
+            ParseError::InvalidHex => f.write_str("invalid hex integer"),
+            ParseError::Overflow => f.write_str("integer exceeds 256 bits"),
+            ParseError::Precision { decimals } => {
+                write!(f,"too many fractional digits (max {})",decimals)
+            },
+            ParseError::UnknownUnit => f.write_str("unknown unit (expected wei, gwei, ether, or decimals)"),
+        }
+    }
+}
+
+
+impl error::Error for ParseError {
+
+    fn description(&self) -> &str {
+        match self {
+            ParseError::Empty => "cannot parse integer from empty string",
+            ParseError::InvalidDigit(_) => "invalid decimal digit",
+            ParseError::InvalidHex => "invalid hex integer",
+            ParseError::Overflow => "integer exceeds 256 bits",
+            ParseError::Precision { .. } => "too many fractional digits",
+            ParseError::UnknownUnit => "unknown unit",
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use util::units::{self,Unit,ParseError};
+    use types::U256;
+
+    const MAX: &str = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+
+
+    #[test]
+    fn decimal_roundtrip() {
+        for num in [0u64,1,9,10,255,1000000007,u64::max_value()].iter() {
+            let decimal = units::to_decimal(&U256::from(*num));
+            assert_eq!(decimal,num.to_string());
+            assert_eq!(units::parse_decimal(&decimal).unwrap(),U256::from(*num));
+        }
+        let max = U256::from([0xffu8;32]);
+        assert_eq!(units::to_decimal(&max),MAX);
+        assert_eq!(units::parse_decimal(MAX).unwrap(),max);
+    }
+
+
+    #[test]
+    fn decimal_overflow() {
+        // 2^256
+        let over = "115792089237316195423570985008687907853269984665640564039457584007913129639936";
+        assert_eq!(units::parse_decimal(over),Err(ParseError::Overflow));
+        assert_eq!(units::parse_decimal(&format!("{}0",MAX)),Err(ParseError::Overflow));
+        assert_eq!(units::parse_units(MAX,Unit::Decimals(1)),Err(ParseError::Overflow));
+    }
+
+
+    #[test]
+    fn invalid_input() {
+        assert_eq!(units::parse_decimal(""),Err(ParseError::Empty));
+        assert_eq!(units::parse_decimal("   "),Err(ParseError::Empty));
+        assert_eq!(units::parse_decimal("12a"),Err(ParseError::InvalidDigit('a')));
+        assert_eq!(units::parse_decimal("-1"),Err(ParseError::InvalidDigit('-')));
+        assert_eq!(units::parse_decimal("1.5"),Err(ParseError::InvalidDigit('.')));
+        assert_eq!(units::parse_uint("0xzz"),Err(ParseError::InvalidHex));
+        assert_eq!(units::parse_units(".",Unit::Ether),Err(ParseError::Empty));
+        assert_eq!(units::parse_units("1.2.3",Unit::Ether),Err(ParseError::InvalidDigit('.')));
+        assert_eq!(units::parse_units("1e18",Unit::Wei),Err(ParseError::InvalidDigit('e')));
+        assert_eq!("furlongs".parse::<Unit>(),Err(ParseError::UnknownUnit));
+    }
+
+
+    #[test]
+    fn format_decimal_places() {
+        let num = U256::from(1234500u64);
+        assert_eq!(units::format_units(&num,Unit::Wei),"1234500");
+        assert_eq!(units::format_units(&num,Unit::Decimals(2)),"12345");
+        assert_eq!(units::format_units(&num,Unit::Decimals(3)),"1234.5");
+        assert_eq!(units::format_units(&num,Unit::Decimals(7)),"0.12345");
+        assert_eq!(units::format_units(&num,Unit::Decimals(9)),"0.0012345");
+        assert_eq!(units::format_units(&U256::from(0u64),Unit::Ether),"0");
+        assert_eq!(units::format_units(&U256::from(1u64),Unit::Ether),"0.000000000000000001");
+    }
+
+
+    #[test]
+    fn parse_decimal_places() {
+        let gwei = U256::from(1000000000u64);
+        assert_eq!(units::parse_units("1",Unit::Gwei).unwrap(),gwei);
+        assert_eq!(units::parse_units("1.",Unit::Gwei).unwrap(),gwei);
+        assert_eq!(units::parse_units("1.000",Unit::Gwei).unwrap(),gwei);
+        assert_eq!(units::parse_units(".5",Unit::Gwei).unwrap(),U256::from(500000000u64));
+        assert_eq!(units::parse_units("0.000000001",Unit::Gwei).unwrap(),U256::from(1u64));
+        assert_eq!(units::parse_units("0.0000000001",Unit::Gwei),Err(ParseError::Precision { decimals: 9 }));
+        // trailing zeroes beyond the unit's precision are harmless
+        assert_eq!(units::parse_units("12.340000",Unit::Decimals(2)).unwrap(),U256::from(1234u64));
+        assert_eq!(units::parse_units("1.5",Unit::Wei),Err(ParseError::Precision { decimals: 0 }));
+    }
+
+
+    #[test]
+    fn parse_unit_names() {
+        assert_eq!("wei".parse::<Unit>(),Ok(Unit::Wei));
+        assert_eq!(" GWEI ".parse::<Unit>(),Ok(Unit::Gwei));
+        assert_eq!("eth".parse::<Unit>(),Ok(Unit::Ether));
+        assert_eq!("6".parse::<Unit>(),Ok(Unit::Decimals(6)));
+        assert_eq!("256".parse::<Unit>(),Err(ParseError::UnknownUnit));
+    }
+}
@@ -0,0 +1,88 @@
+extern crate serde_json;
+extern crate ethrpc;
+
+
+use ethrpc::types::{AccountProof,U256,H256};
+use ethrpc::trie::ProofError;
+use serde_json::Value;
+
+
+/// `eth_getProof` responses against a state trie of 201 accounts, one of which
+/// holds 40 storage slots.  The proof of `proof` covers one present & one absent
+/// slot, and `missing` proves an account which does not exist.
+const FIXTURE: &str = include_str!("get-proof.json");
+
+
+fn load() -> (H256,AccountProof,AccountProof) {
+    let fixture: Value = serde_json::from_str(FIXTURE).unwrap();
+    let state_root = serde_json::from_value(fixture["stateRoot"].clone()).unwrap();
+    let proof = serde_json::from_value(fixture["proof"].clone()).unwrap();
+    let missing = serde_json::from_value(fixture["missing"].clone()).unwrap();
+    (state_root,proof,missing)
+}
+
+
+#[test]
+fn account_proof_sanity() {
+    let (state_root,proof,missing) = load();
+    assert_eq!(proof.verify(state_root),Ok(()));
+    assert_eq!(proof.storage(U256::from(1u64)),Some(U256::from(0x84u64)));
+    assert_eq!(proof.storage(U256::from(0x1234u64)),Some(U256::from(0u64)));
+    assert_eq!(missing.verify(state_root),Ok(()));
+}
+
+
+#[test]
+fn account_proof_mismatch() {
+    let (state_root,proof,missing) = load();
+    let mut bad = proof.clone();
+    bad.balance = U256::from(1u64);
+    assert_eq!(bad.verify(state_root),Err(ProofError::Mismatch("balance")));
+    let mut bad = proof.clone();
+    bad.storage_proof[0].value = U256::from(0x85u64);
+    assert_eq!(bad.verify(state_root),Err(ProofError::Mismatch("storage value")));
+    let mut bad = proof.clone();
+    bad.storage_proof[1].value = U256::from(1u64);
+    assert_eq!(bad.verify(state_root),Err(ProofError::Mismatch("storage value")));
+    // a missing account cannot be claimed to hold a balance
+    let mut bad = missing.clone();
+    bad.balance = U256::from(1u64);
+    assert_eq!(bad.verify(state_root),Err(ProofError::Mismatch("balance")));
+    // nor can the account's proof be presented as the proof of another address
+    let mut bad = proof.clone();
+    bad.address = missing.address;
+    assert!(bad.verify(state_root).is_err());
+    // proofs never verify against another block's state root
+    assert_eq!(proof.verify(proof.storage_hash),Err(ProofError::BadHash));
+}
+
+
+fn slot(index: u64) -> H256 {
+    format!("0x{:064x}",index).parse().unwrap()
+}
+
+
+#[test]
+fn account_proof_request() {
+    let (state_root,proof,missing) = load();
+    let keys = [slot(1),slot(0x1234)];
+    assert_eq!(proof.verify_request(proof.address,&keys,state_root),Ok(()));
+    assert_eq!(missing.verify_request(missing.address,&[],state_root),Ok(()));
+    // a valid proof of another account is not a proof of the requested one
+    assert_eq!(missing.verify_request(proof.address,&[],state_root),Err(ProofError::Mismatch("address")));
+    assert_eq!(proof.verify_request(missing.address,&keys,state_root),Err(ProofError::Mismatch("address")));
+    // nor is a valid proof of other slots (or of the same slots, out of order)
+    let mismatched = [
+        vec![slot(1),slot(0x1235)],
+        vec![slot(0x1234),slot(1)],
+        vec![slot(1)],
+        vec![slot(1),slot(0x1234),slot(2)],
+    ];
+    for keys in mismatched.iter() {
+        assert_eq!(proof.verify_request(proof.address,keys,state_root),Err(ProofError::Mismatch("storage keys")));
+    }
+    let mut swapped = proof.clone();
+    swapped.storage_proof.swap(0,1);
+    assert_eq!(swapped.verify(state_root),Ok(()));
+    assert_eq!(swapped.verify_request(proof.address,&keys,state_root),Err(ProofError::Mismatch("storage keys")));
+}
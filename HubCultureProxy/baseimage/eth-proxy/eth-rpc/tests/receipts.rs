@@ -0,0 +1,64 @@
+extern crate serde_json;
+extern crate ethrpc;
+
+
+use ethrpc::types::{Receipt,Bytes,H256};
+use ethrpc::trie::{self,ProofError};
+use serde_json::Value;
+
+
+/// Receipts of a block holding a legacy receipt, an EIP-1559 receipt with two logs,
+/// and a failed EIP-2930 receipt (in that order), along with their consensus
+/// encodings.  Also holds a pre-byzantium receipt, which commits to a state root
+/// in place of a status.
+const FIXTURE: &str = include_str!("receipts.json");
+
+
+fn load() -> (H256,Vec<(Receipt,Bytes)>,(Receipt,Bytes)) {
+    let fixture: Value = serde_json::from_str(FIXTURE).unwrap();
+    let parse = |entry: &Value| -> (Receipt,Bytes) {
+        let receipt = serde_json::from_value(entry["receipt"].clone()).unwrap();
+        let encoded = serde_json::from_value(entry["encoded"].clone()).unwrap();
+        (receipt,encoded)
+    };
+    let root = serde_json::from_value(fixture["receiptsRoot"].clone()).unwrap();
+    let receipts = fixture["receipts"].as_array().unwrap().iter().map(parse).collect();
+    (root,receipts,parse(&fixture["preByzantium"]))
+}
+
+
+#[test]
+fn receipt_encoding() {
+    let (_,receipts,(pre_byzantium,expect)) = load();
+    for (receipt,expect) in receipts.iter() {
+        let encoded = trie::encode_receipt(receipt).unwrap();
+        assert_eq!(&encoded[..],&expect[..],"Encoding of receipt {:?} must match",receipt.transaction_hash);
+    }
+    let encoded = trie::encode_receipt(&pre_byzantium).unwrap();
+    assert_eq!(&encoded[..],&expect[..]);
+    let mut no_bloom = pre_byzantium.clone();
+    no_bloom.logs_bloom = None;
+    match trie::encode_receipt(&no_bloom) {
+        Err(ProofError::Malformed(_)) => { },
+        other => panic!("Expected malformed receipt, got {:?}",other),
+    }
+}
+
+
+#[test]
+fn receipts_root_sanity() {
+    let (root,receipts,_) = load();
+    let mut receipts: Vec<Receipt> = receipts.into_iter().map(|(receipt,_)| receipt).collect();
+    assert_eq!(trie::receipts_root(&receipts),Ok(root));
+    assert_eq!(trie::receipts_root(&[]),Ok(H256::from(trie::EMPTY_ROOT)));
+    // receipts are committed to in block order...
+    receipts.swap(0,2);
+    assert!(trie::receipts_root(&receipts).unwrap() != root);
+    receipts.swap(0,2);
+    // ...along with their type & logs
+    receipts[2].tx_type = None;
+    assert!(trie::receipts_root(&receipts).unwrap() != root);
+    receipts[2] = load().1[2].0.clone();
+    receipts[1].logs.pop();
+    assert!(trie::receipts_root(&receipts).unwrap() != root);
+}
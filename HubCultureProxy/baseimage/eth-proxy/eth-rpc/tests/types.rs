@@ -0,0 +1,309 @@
+#[macro_use]
+extern crate serde_json;
+extern crate serde;
+extern crate ethrpc;
+
+
+use ethrpc::types::{Block,TxInfo,Receipt,Status,U256,H256};
+use ethrpc::crypto::Address;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+
+fn val<T: DeserializeOwned>(hex: &str) -> T { serde_json::from_value(Value::String(hex.into())).unwrap() }
+
+
+fn uint(num: u64) -> U256 { U256::from(num) }
+
+
+fn hash(byte: u8) -> String { format!("0x{}",(0..32).map(|_| format!("{:02x}",byte)).collect::<String>()) }
+
+
+/// An EIP-1559 transaction, as reported by geth for a mined block.
+fn dynamic_fee_tx() -> Value {
+    json!({
+        "blockHash": hash(0xbb),
+        "blockNumber": "0x12a05f2",
+        "from": "0x95222290dd7278aa3ddd389cc1e1d165cc4bafe5",
+        "gas": "0x5208",
+        "gasPrice": "0x2540be400",
+        "maxFeePerGas": "0x4a817c800",
+        "maxPriorityFeePerGas": "0x3b9aca00",
+        "hash": hash(0x01),
+        "input": "0xa9059cbb",
+        "nonce": "0x1a",
+        "to": "0xdac17f958d2ee523a2206206994597c13d831ec7",
+        "transactionIndex": "0x0",
+        "value": "0x0",
+        "type": "0x2",
+        "accessList": [
+            { "address": "0xdac17f958d2ee523a2206206994597c13d831ec7", "storageKeys": [hash(0x02)] }
+        ],
+        "chainId": "0x1",
+        "v": "0x1",
+        "yParity": "0x1",
+        "r": "0x2c7a2a3a5c9a1a1e6d5b4c2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60",
+        "s": "0x1e5c3b7a9d8f6e4c2a0b1c3d5e7f9a8b6c4d2e0f1a3b5c7d9e8f6a4b2c0d1e3f"
+    })
+}
+
+
+/// An EIP-4844 blob transaction, as reported by geth for a mined block.
+fn blob_tx() -> Value {
+    json!({
+        "blockHash": hash(0xbb),
+        "blockNumber": "0x12a05f2",
+        "from": "0x6887246668a3b87f54deb3b94ba47a6f63f32985",
+        "gas": "0x5208",
+        "gasPrice": "0x2540be400",
+        "maxFeePerGas": "0x4a817c800",
+        "maxPriorityFeePerGas": "0x3b9aca00",
+        "maxFeePerBlobGas": "0x3b9aca00",
+        "hash": hash(0x03),
+        "input": "0x",
+        "nonce": "0x2f",
+        "to": "0xff00000000000000000000000000000000000010",
+        "transactionIndex": "0x1",
+        "value": "0x0",
+        "type": "0x3",
+        "accessList": [],
+        "chainId": "0x1",
+        "blobVersionedHashes": [
+            "0x01a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5d6e7f80",
+            "0x01f0e1d2c3b4a5968778695a4b3c2d1e0ff0e1d2c3b4a5968778695a4b3c2d1e"
+        ],
+        "v": "0x0",
+        "yParity": "0x0",
+        "r": "0x5d3c1b9a7f8e6d4c2b0a1f3e5d7c9b8a6f4e2d0c1b3a5f7e9d8c6b4a2f0e1d3c",
+        "s": "0x3a1f5e7d9c8b6a4f2e0d1c3b5a7f9e8d6c4b2a0f1e3d5c7b9a8f6e4d2c0b1a3f"
+    })
+}
+
+
+/// A post-merge (post-cancun) block, as reported by geth.
+fn post_merge_block(transactions: Value) -> Value {
+    json!({
+        "baseFeePerGas": "0x1dcd6500",
+        "blobGasUsed": "0x20000",
+        "difficulty": "0x0",
+        "excessBlobGas": "0x60000",
+        "extraData": "0x6265617665726275696c642e6f7267",
+        "gasLimit": "0x1c9c380",
+        "gasUsed": "0xa410",
+        "hash": hash(0xbb),
+        "logsBloom": format!("0x{}","00".repeat(256)),
+        "miner": "0x95222290dd7278aa3ddd389cc1e1d165cc4bafe5",
+        "mixHash": hash(0x04),
+        "nonce": "0x0000000000000000",
+        "number": "0x12a05f2",
+        "parentBeaconBlockRoot": hash(0x05),
+        "parentHash": hash(0x06),
+        "receiptsRoot": hash(0x07),
+        "requestsHash": hash(0x08),
+        "sha3Uncles": "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+        "size": "0x3e8",
+        "stateRoot": hash(0x09),
+        "timestamp": "0x65f1b057",
+        "transactions": transactions,
+        "transactionsRoot": hash(0x0a),
+        "uncles": [],
+        "withdrawals": [
+            { "index": "0x2a6f7c1", "validatorIndex": "0x10f3a", "address": "0xb9d7934878b5fb9610b3fe8a5e441e8fad7e293f", "amount": "0x11d0a2c" }
+        ],
+        "withdrawalsRoot": hash(0x0b)
+    })
+}
+
+
+#[test]
+fn post_merge_block_hashes() {
+    let block: Block<H256> = serde_json::from_value(post_merge_block(json!([hash(0x01),hash(0x03)]))).unwrap();
+    assert_eq!(block.hash,Some(val(&hash(0xbb))));
+    assert_eq!(block.number,Some(uint(0x12a05f2)));
+    assert_eq!(block.difficulty,uint(0));
+    assert_eq!(block.total_difficulty,None);
+    assert_eq!(block.mix_hash,Some(val(&hash(0x04))));
+    assert_eq!(block.nonce.as_ref().map(|nonce| nonce[..].len()),Some(8));
+    assert_eq!(block.base_fee_per_gas,Some(uint(0x1dcd6500)));
+    assert_eq!(block.withdrawals_root,Some(val(&hash(0x0b))));
+    assert_eq!(block.blob_gas_used,Some(uint(0x20000)));
+    assert_eq!(block.excess_blob_gas,Some(uint(0x60000)));
+    assert_eq!(block.parent_beacon_block_root,Some(val(&hash(0x05))));
+    assert_eq!(block.logs_bloom.as_ref().map(|bloom| bloom[..].len()),Some(256));
+    assert!(block.seal_fields.is_empty());
+    assert!(block.uncles.is_empty());
+    assert_eq!(block.transactions,vec![val::<H256>(&hash(0x01)),val(&hash(0x03))]);
+    let withdrawals = block.withdrawals.as_ref().unwrap();
+    assert_eq!(withdrawals.len(),1);
+    assert_eq!(withdrawals[0].validator_index,uint(0x10f3a));
+    assert_eq!(withdrawals[0].address,val::<Address>("0xb9d7934878b5fb9610b3fe8a5e441e8fad7e293f"));
+    assert_eq!(withdrawals[0].amount,uint(0x11d0a2c));
+    // fields from later forks are preserved
+    assert_eq!(block.extra.len(),1);
+    assert_eq!(block.extra["requestsHash"],json!(hash(0x08)));
+}
+
+
+#[test]
+fn post_merge_block_transactions() {
+    let block: Block<TxInfo> = serde_json::from_value(post_merge_block(json!([dynamic_fee_tx(),blob_tx()]))).unwrap();
+    assert_eq!(block.transactions.len(),2);
+    assert_eq!(block.transactions[0].tx_type,Some(uint(2)));
+    assert_eq!(block.transactions[1].tx_type,Some(uint(3)));
+    for tx in block.transactions.iter() {
+        assert_eq!(tx.block_hash,block.hash);
+        assert_eq!(tx.block_number,block.number);
+    }
+}
+
+
+#[test]
+fn pre_merge_block() {
+    let mut json = post_merge_block(json!([]));
+    {
+        let fields = json.as_object_mut().unwrap();
+        for field in ["baseFeePerGas","blobGasUsed","excessBlobGas","parentBeaconBlockRoot","requestsHash","withdrawals","withdrawalsRoot"].iter() {
+            fields.remove(*field);
+        }
+        fields.insert("difficulty".into(),json!("0xbfabcdbd93dda"));
+        fields.insert("totalDifficulty".into(),json!("0x2a3c5d9e8f1b4c7d6e5"));
+        fields.insert("nonce".into(),json!("0x5ae1c8f3a9d2b4e6"));
+    }
+    let block: Block<H256> = serde_json::from_value(json).unwrap();
+    assert_eq!(block.difficulty,uint(0xbfabcdbd93dda));
+    assert_eq!(block.total_difficulty,Some(val("0x2a3c5d9e8f1b4c7d6e5")));
+    assert_eq!(block.base_fee_per_gas,None);
+    assert_eq!(block.withdrawals,None);
+    assert_eq!(block.blob_gas_used,None);
+    assert!(block.extra.is_empty());
+}
+
+
+#[test]
+fn pending_block() {
+    let mut json = post_merge_block(json!([]));
+    json["hash"] = Value::Null;
+    json["number"] = Value::Null;
+    json["size"] = Value::Null;
+    let block: Block<H256> = serde_json::from_value(json).unwrap();
+    assert_eq!(block.hash,None);
+    assert_eq!(block.number,None);
+    assert_eq!(block.size,None);
+}
+
+
+#[test]
+fn dynamic_fee_tx_info() {
+    let tx: TxInfo = serde_json::from_value(dynamic_fee_tx()).unwrap();
+    assert_eq!(tx.hash,val(&hash(0x01)));
+    assert_eq!(tx.nonce,uint(0x1a));
+    assert_eq!(tx.transaction_index,Some(uint(0)));
+    assert_eq!(tx.to,Some(val("0xdac17f958d2ee523a2206206994597c13d831ec7")));
+    assert_eq!(tx.gas_price,uint(10_000_000_000));
+    assert_eq!(tx.gas_limit,uint(21000));
+    assert_eq!(&tx.input[..],&[0xa9,0x05,0x9c,0xbb][..]);
+    assert_eq!(tx.tx_type,Some(uint(2)));
+    assert_eq!(tx.chain_id,Some(uint(1)));
+    assert_eq!(tx.max_fee_per_gas,Some(uint(20_000_000_000)));
+    assert_eq!(tx.max_priority_fee_per_gas,Some(uint(1_000_000_000)));
+    assert_eq!(tx.v,Some(uint(1)));
+    assert_eq!(tx.y_parity,Some(uint(1)));
+    assert!(tx.r.is_some() && tx.s.is_some());
+    let access_list = tx.access_list.as_ref().unwrap();
+    assert_eq!(access_list.len(),1);
+    assert_eq!(access_list[0].address,val("0xdac17f958d2ee523a2206206994597c13d831ec7"));
+    assert_eq!(access_list[0].storage_keys,vec![val::<H256>(&hash(0x02))]);
+    assert!(tx.extra.is_empty());
+}
+
+
+#[test]
+fn blob_tx_info() {
+    let tx: TxInfo = serde_json::from_value(blob_tx()).unwrap();
+    assert_eq!(tx.tx_type,Some(uint(3)));
+    assert_eq!(tx.max_fee_per_gas,Some(uint(20_000_000_000)));
+    assert_eq!(tx.access_list,Some(Vec::new()));
+    assert_eq!(tx.y_parity,Some(uint(0)));
+    // blob fields are not represented by `TxInfo`, but are preserved
+    assert_eq!(tx.extra.len(),2);
+    assert_eq!(tx.extra["maxFeePerBlobGas"],"0x3b9aca00");
+    assert_eq!(tx.extra["blobVersionedHashes"].as_array().map(Vec::len),Some(2));
+}
+
+
+#[test]
+fn legacy_tx_info() {
+    // pre-EIP-155 transaction, as reported by a node which predates typed transactions
+    let mut json = dynamic_fee_tx();
+    {
+        let fields = json.as_object_mut().unwrap();
+        for field in ["maxFeePerGas","maxPriorityFeePerGas","type","accessList","chainId","yParity"].iter() {
+            fields.remove(*field);
+        }
+        fields.insert("v".into(),json!("0x1b"));
+    }
+    let tx: TxInfo = serde_json::from_value(json).unwrap();
+    assert_eq!(tx.tx_type,None);
+    assert_eq!(tx.chain_id,None);
+    assert_eq!(tx.max_fee_per_gas,None);
+    assert_eq!(tx.access_list,None);
+    assert_eq!(tx.v,Some(uint(27)));
+    assert!(tx.extra.is_empty());
+}
+
+
+#[test]
+fn pre_byzantium_receipt() {
+    // receipts prior to byzantium commit to the post-transaction state root, rather than a status
+    let receipt: Receipt = serde_json::from_value(json!({
+        "blockHash": hash(0xcc),
+        "blockNumber": "0x3d0900",
+        "contractAddress": null,
+        "cumulativeGasUsed": "0x1e8480",
+        "from": "0x32be343b94f860124dc4fee278fdcbd38c102d88",
+        "gasUsed": "0x5208",
+        "logs": [],
+        "logsBloom": format!("0x{}","00".repeat(256)),
+        "root": hash(0x0c),
+        "to": "0xdf190dc7190dfba737d7777a163445b7fff16133",
+        "transactionHash": hash(0x0d),
+        "transactionIndex": "0x3"
+    })).unwrap();
+    assert_eq!(receipt.root,Some(val(&hash(0x0c))));
+    assert_eq!(receipt.status,None);
+    assert_eq!(receipt.tx_type,None);
+    assert_eq!(receipt.effective_gas_price,None);
+    assert_eq!(receipt.contract_address,None);
+    assert_eq!(receipt.transaction_index,uint(3));
+    assert_eq!(receipt.gas_used,uint(21000));
+    assert!(receipt.extra.is_empty());
+}
+
+
+#[test]
+fn post_byzantium_receipt() {
+    let receipt: Receipt = serde_json::from_value(json!({
+        "blockHash": hash(0xbb),
+        "blockNumber": "0x12a05f2",
+        "contractAddress": null,
+        "cumulativeGasUsed": "0xa410",
+        "effectiveGasPrice": "0x2540be400",
+        "from": "0x6887246668a3b87f54deb3b94ba47a6f63f32985",
+        "gasUsed": "0x5208",
+        "blobGasUsed": "0x20000",
+        "blobGasPrice": "0x1",
+        "logs": [],
+        "logsBloom": format!("0x{}","00".repeat(256)),
+        "status": "0x0",
+        "to": "0xff00000000000000000000000000000000000010",
+        "transactionHash": hash(0x03),
+        "transactionIndex": "0x1",
+        "type": "0x3"
+    })).unwrap();
+    assert_eq!(receipt.status,Some(Status::Failure));
+    assert_eq!(receipt.root,None);
+    assert_eq!(receipt.tx_type,Some(uint(3)));
+    assert_eq!(receipt.effective_gas_price,Some(uint(10_000_000_000)));
+    assert_eq!(receipt.extra["blobGasUsed"],"0x20000");
+    assert_eq!(receipt.extra["blobGasPrice"],"0x1");
+}
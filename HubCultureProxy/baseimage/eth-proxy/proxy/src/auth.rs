@@ -0,0 +1,614 @@
+//! Client authentication & per-client request permissions.
+//!
+//! Clients are declared in a toml file (see `ServerOptions::load_auth`):
+//!
+//! ```toml
+//! replay-window = 30
+//!
+//! [[client]]
+//! name = "vault"
+//! scheme = "hmac"
+//! secret-var = "VAULT_SECRET"
+//! methods = ["sign-tx-call", "get-address"]
+//! functions = ["releaseDeposit", "revokeDeposit"]
+//! keys = ["vault"]
+//! ```
+//!
+//! Every request names its client via the `X-Auth-Client` header.  Clients using
+//! the `api-key` scheme present their secret via `X-Auth-Key`.  Clients using the
+//! `hmac` scheme instead supply a unix timestamp via `X-Auth-Timestamp`, and the
+//! hex-encoded hmac-sha256 of `"<timestamp>\n<body>"` (keyed by their secret) via
+//! `X-Auth-Signature`.  Signed requests are refused if their timestamp is more
+//! than `replay-window` seconds from the current time, or if the same signature
+//! has already been seen.
+//!
+//! Authenticated clients may only invoke the listed `methods` (request variants)
+//! and, if `functions` is specified, only call the listed functions.  Since the
+//! calldata of raw transactions is not decoded, clients restricted to `functions`
+//! may not be granted `sign-raw-tx`.  If `keys` is specified, every request must
+//! be directed at one of the listed keys (by the name or address given there).
+//!
+use hmac::{Hmac,Mac,NewMac};
+use sha2::Sha256;
+use _http::HeaderMap;
+use util::{self,constant_time_eq,from_hex,to_hex,unix_time};
+use std::collections::{HashMap,HashSet};
+use std::sync::{Arc,Mutex};
+use std::str::FromStr;
+use std::{env,fmt,error};
+
+
+pub const CLIENT_HEADER: &str = "X-Auth-Client";
+
+pub const KEY_HEADER: &str = "X-Auth-Key";
+
+pub const TIMESTAMP_HEADER: &str = "X-Auth-Timestamp";
+
+pub const SIGNATURE_HEADER: &str = "X-Auth-Signature";
+
+
+fn default_replay_window() -> u64 { 30 }
+
+
+/// Requests subject to per-client permissions.
+///
+pub trait Restricted {
+
+    /// Name of the requested method (e.g. `sign-tx-call`)
+    fn method(&self) -> &str;
+
+    /// Name of the function called by the request (if any)
+    fn function(&self) -> Option<&str> { None }
+
+    /// Name or address of the key at which the request is directed (if any)
+    fn key(&self) -> Option<String> { None }
+}
+
+
+#[derive(Debug,Clone,Serialize,Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Maximum age (in seconds) of a signed request
+    #[serde(default = "default_replay_window",rename = "replay-window")]
+    pub replay_window: u64,
+    #[serde(default,rename = "client")]
+    pub clients: Vec<ClientConfig>,
+}
+
+
+/// Means by which a client proves its identity.
+///
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scheme {
+    ApiKey,
+    Hmac,
+}
+
+
+impl FromStr for Scheme {
+
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        match s {
+            "api-key" => Ok(Scheme::ApiKey),
+            "hmac" => Ok(Scheme::Hmac),
+            other => Err(Error::Config(format!("unknown scheme `{}` (expected `api-key` or `hmac`)",other))),
+        }
+    }
+}
+
+
+#[derive(Debug,Clone,Serialize,Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientConfig {
+    pub name: String,
+    pub scheme: Scheme,
+    #[serde(default,skip_serializing)]
+    pub secret: Option<String>,
+    #[serde(default,rename = "secret-var")]
+    pub secret_var: Option<String>,
+    pub methods: HashSet<String>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub functions: Option<HashSet<String>>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub keys: Option<HashSet<String>>,
+}
+
+
+/// An authenticated client.
+///
+#[derive(Clone)]
+pub struct Client {
+    name: String,
+    scheme: Scheme,
+    secret: Vec<u8>,
+    methods: HashSet<String>,
+    functions: Option<HashSet<String>>,
+    keys: Option<HashSet<String>>,
+}
+
+
+impl Client {
+
+    pub fn name(&self) -> &str { &self.name }
+
+    /// Check if this client may invoke `request`.
+    ///
+    pub fn allows(&self, request: &impl Restricted) -> Result<(),Error> {
+        let method = request.method();
+        if !self.methods.contains(method) {
+            return Err(Error::Forbidden { client: self.name.clone(), what: format!("method `{}`",method) });
+        }
+        if let (Some(allowed),Some(function)) = (self.functions.as_ref(),request.function()) {
+            if !allowed.contains(function) {
+                return Err(Error::Forbidden { client: self.name.clone(), what: format!("function `{}`",function) });
+            }
+        }
+        if let Some(allowed) = self.keys.as_ref() {
+            match request.key() {
+                Some(ref key) if allowed.iter().any(|other| same_key(other,key)) => { },
+                Some(key) => {
+                    return Err(Error::Forbidden { client: self.name.clone(), what: format!("key `{}`",key) });
+                },
+                None => {
+                    return Err(Error::Forbidden { client: self.name.clone(), what: "the default key".into() });
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// Check if two key identifiers are the same (addresses match regardless of
+/// case or `0x` prefix).
+///
+fn same_key(a: &str, b: &str) -> bool {
+    let as_addr = |id: &str| {
+        let hex = id.trim_start_matches("0x");
+        if hex.len() == 40 && hex.bytes().all(|b| b.is_ascii_hexdigit()) { Some(hex.to_ascii_lowercase()) } else { None }
+    };
+    match (as_addr(a),as_addr(b)) {
+        (Some(a),Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+
+impl fmt::Debug for Client {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("name",&self.name)
+            .field("scheme",&self.scheme)
+            .field("methods",&self.methods)
+            .field("functions",&self.functions)
+            .field("keys",&self.keys)
+            .finish()
+    }
+}
+
+
+/// Request authenticator, shared by all clones.
+///
+/// An `open` authenticator accepts all requests without checks.
+///
+#[derive(Debug,Clone)]
+pub struct Auth {
+    inner: Option<Arc<Inner>>,
+}
+
+
+#[derive(Debug)]
+struct Inner {
+    clients: HashMap<String,Client>,
+    replay_window: u64,
+    /// Signatures seen within the replay window, with their timestamps
+    seen: Mutex<HashMap<String,u64>>,
+}
+
+
+impl Auth {
+
+    /// An authenticator which performs no checks.
+    ///
+    pub fn open() -> Self { Self { inner: None } }
+
+    pub fn from_config(config: AuthConfig) -> Result<Self,Error> {
+        let mut clients = HashMap::with_capacity(config.clients.len());
+        for client in config.clients {
+            let ClientConfig { name, scheme, secret, secret_var, methods, functions, keys } = client;
+            let secret = match (secret,secret_var) {
+                (Some(secret),None) => secret,
+                (None,Some(var)) => env::var(&var).map_err(|_| {
+                    Error::Config(format!("unable to read secret of client `{}` from `{}`",name,var))
+                })?,
+                _ => return Err(Error::Config(format!("client `{}` requires exactly one of `secret` or `secret-var`",name))),
+            };
+            if secret.is_empty() {
+                return Err(Error::Config(format!("client `{}` has empty secret",name)));
+            }
+            if clients.contains_key(&name) {
+                return Err(Error::Config(format!("duplicate client `{}`",name)));
+            }
+            if functions.is_some() && methods.contains("sign-raw-tx") {
+                let msg = format!("client `{}` is restricted to `functions`, so may not be granted `sign-raw-tx`",name);
+                return Err(Error::Config(msg));
+            }
+            info!("Configured client `{}` ({:?}) with methods {:?}",name,scheme,methods);
+            let secret = secret.into_bytes();
+            clients.insert(name.clone(),Client { name, scheme, secret, methods, functions, keys });
+        }
+        let seen = Default::default();
+        let inner = Inner { clients, replay_window: config.replay_window, seen };
+        Ok(Self { inner: Some(Arc::new(inner)) })
+    }
+
+    pub fn is_open(&self) -> bool { self.inner.is_none() }
+
+    /// Authenticate a request from its headers & body.
+    ///
+    /// Yields `None` if this authenticator is open.
+    ///
+    pub fn authenticate(&self, headers: &HeaderMap, body: &[u8]) -> Result<Option<&Client>,Error> {
+        let inner = match self.inner.as_ref() {
+            Some(inner) => inner,
+            None => return Ok(None),
+        };
+        let name = header(headers,CLIENT_HEADER)?;
+        let client = inner.clients.get(name).ok_or_else(|| Error::unauthenticated("unknown client"))?;
+        match client.scheme {
+            Scheme::ApiKey => {
+                let key = header(headers,KEY_HEADER)?;
+                if !constant_time_eq(key.as_bytes(),&client.secret) {
+                    return Err(Error::unauthenticated("invalid api key"));
+                }
+            },
+            Scheme::Hmac => {
+                let timestamp = header(headers,TIMESTAMP_HEADER)?;
+                let signature = header(headers,SIGNATURE_HEADER)?.trim_start_matches("0x").to_ascii_lowercase();
+                let time: u64 = timestamp.parse().map_err(|_| Error::unauthenticated("malformed timestamp"))?;
+                let now = unix_time();
+                let age = if now > time { now - time } else { time - now };
+                if age > inner.replay_window {
+                    return Err(Error::unauthenticated("timestamp outside replay window"));
+                }
+                let code = from_hex(&signature).ok_or_else(|| Error::unauthenticated("malformed signature"))?;
+                let mut mac = Hmac::<Sha256>::new_varkey(&client.secret).expect("hmac accepts keys of any length");
+                mac.update(timestamp.as_bytes());
+                mac.update(b"\n");
+                mac.update(body);
+                mac.verify(&code).map_err(|_| Error::unauthenticated("invalid signature"))?;
+                let mut seen = util::lock(&inner.seen);
+                let start = now.saturating_sub(inner.replay_window);
+                seen.retain(|_,time| *time >= start);
+                if seen.insert(signature,time).is_some() {
+                    return Err(Error::unauthenticated("replayed request"));
+                }
+            },
+        }
+        Ok(Some(client))
+    }
+}
+
+
+/// Credentials with which a client authenticates its requests to a server.
+///
+#[derive(Clone)]
+pub struct Credentials {
+    client: String,
+    scheme: Scheme,
+    secret: Vec<u8>,
+}
+
+
+impl Credentials {
+
+    pub fn new(client: impl Into<String>, scheme: Scheme, secret: impl Into<Vec<u8>>) -> Self {
+        Self { client: client.into(), scheme, secret: secret.into() }
+    }
+
+    /// Headers which authenticate a request with `body` (see module docs).
+    ///
+    pub fn headers(&self, body: &[u8]) -> Vec<(&'static str,String)> {
+        let mut headers = vec![(CLIENT_HEADER,self.client.clone())];
+        match self.scheme {
+            Scheme::ApiKey => {
+                headers.push((KEY_HEADER,String::from_utf8_lossy(&self.secret).into_owned()));
+            },
+            Scheme::Hmac => {
+                let timestamp = unix_time().to_string();
+                let mut mac = Hmac::<Sha256>::new_varkey(&self.secret).expect("hmac accepts keys of any length");
+                mac.update(timestamp.as_bytes());
+                mac.update(b"\n");
+                mac.update(body);
+                let signature = to_hex(&mac.finalize().into_bytes());
+                headers.push((TIMESTAMP_HEADER,timestamp));
+                headers.push((SIGNATURE_HEADER,signature));
+            },
+        }
+        headers
+    }
+}
+
+
+impl fmt::Debug for Credentials {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("client",&self.client)
+            .field("scheme",&self.scheme)
+            .finish()
+    }
+}
+
+
+fn header<'a>(headers: &'a HeaderMap, name: &'static str) -> Result<&'a str,Error> {
+    headers.get(name)
+        .ok_or_else(|| Error::unauthenticated(format!("missing `{}` header",name)))?
+        .to_str()
+        .map_err(|_| Error::unauthenticated(format!("malformed `{}` header",name)))
+}
+
+
+#[derive(Debug,Clone)]
+pub enum Error {
+    /// Request could not be attributed to a configured client
+    Unauthenticated(String),
+    /// Client may not invoke the requested method or function
+    Forbidden {
+        client: String,
+        what: String,
+    },
+    Config(String),
+}
+
+
+impl Error {
+
+    fn unauthenticated(msg: impl Into<String>) -> Self { Error::Unauthenticated(msg.into()) }
+}
+
+
+impl fmt::Display for Error {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Unauthenticated(msg) => write!(f,"authentication failed: {}",msg),
+            Error::Forbidden { client, what } => write!(f,"client `{}` may not invoke {}",client,what),
+            Error::Config(msg) => write!(f,"invalid auth config: {}",msg),
+        }
+    }
+}
+
+
+impl error::Error for Error {
+
+    fn description(&self) -> &str {
+        match self {
+            Error::Unauthenticated(_) => "authentication failed",
+            Error::Forbidden { .. } => "client not permitted",
+            Error::Config(_) => "invalid auth config",
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use auth::{self,Auth,AuthConfig,Credentials,Scheme,Restricted,Error};
+    use util::unix_time;
+    use hmac::{Hmac,Mac,NewMac};
+    use sha2::Sha256;
+    use _http::HeaderMap;
+    use _http::header::HeaderName;
+
+    const SECRET: &str = "correct horse battery staple";
+
+    const VAULT: &str = "0x00000000000000000000000000000000000a11ce";
+
+
+    struct Req {
+        method: &'static str,
+        function: Option<&'static str>,
+        key: Option<&'static str>,
+    }
+
+    impl Restricted for Req {
+
+        fn method(&self) -> &str { self.method }
+
+        fn function(&self) -> Option<&str> { self.function }
+
+        fn key(&self) -> Option<String> { self.key.map(String::from) }
+    }
+
+
+    fn config(extra: &str) -> Result<Auth,Error> {
+        let buf = format!(r#"
+            replay-window = 30
+
+            [[client]]
+            name = "vault"
+            scheme = "hmac"
+            secret = "{}"
+            methods = ["sign-tx-call", "get-address"]
+            functions = ["releaseDeposit"]
+            keys = ["vault", "{}"]
+
+            [[client]]
+            name = "monitor"
+            scheme = "api-key"
+            secret = "{}"
+            methods = ["get-address", "sign-raw-tx"]
+            {}
+        "#,SECRET,VAULT,SECRET,extra);
+        let config: AuthConfig = ::toml::from_str(&buf).unwrap();
+        Auth::from_config(config)
+    }
+
+
+    fn sign(secret: &str, time: u64, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_varkey(secret.as_bytes()).unwrap();
+        mac.update(time.to_string().as_bytes());
+        mac.update(b"\n");
+        mac.update(body);
+        mac.finalize().into_bytes().iter().map(|b| format!("{:02x}",b)).collect()
+    }
+
+
+    fn set(headers: &mut HeaderMap, name: &str, value: &str) {
+        headers.insert(HeaderName::from_bytes(name.as_bytes()).unwrap(),value.parse().unwrap());
+    }
+
+
+    fn signed_headers(time: u64, signature: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        set(&mut headers,auth::CLIENT_HEADER,"vault");
+        set(&mut headers,auth::TIMESTAMP_HEADER,&time.to_string());
+        set(&mut headers,auth::SIGNATURE_HEADER,signature);
+        headers
+    }
+
+
+    fn rejection(auth: &Auth, headers: &HeaderMap, body: &[u8]) -> String {
+        match auth.authenticate(headers,body) {
+            Err(Error::Unauthenticated(msg)) => msg,
+            other => panic!("expected authentication failure, got {:?}",other),
+        }
+    }
+
+
+    #[test]
+    fn hmac_verification() {
+        let auth = config("").unwrap();
+        let now = unix_time();
+        let body = br#"{"get-address":{}}"#;
+        let headers = signed_headers(now,&sign(SECRET,now,body));
+        assert_eq!(auth.authenticate(&headers,body).unwrap().unwrap().name(),"vault");
+        // prefixed & upper-case signatures are accepted
+        let other = br#"{"key":"vault","get-address":{}}"#;
+        let headers = signed_headers(now,&format!("0x{}",sign(SECRET,now,other).to_uppercase()));
+        assert!(auth.authenticate(&headers,other).is_ok());
+        // modified body, timestamp or key
+        let headers = signed_headers(now,&sign(SECRET,now,body));
+        assert_eq!(rejection(&auth,&headers,br#"{"get-contracts":{}}"#),"invalid signature");
+        let headers = signed_headers(now - 1,&sign(SECRET,now,body));
+        assert_eq!(rejection(&auth,&headers,body),"invalid signature");
+        let headers = signed_headers(now,&sign("incorrect",now,body));
+        assert_eq!(rejection(&auth,&headers,body),"invalid signature");
+        let headers = signed_headers(now,"not-hex");
+        assert_eq!(rejection(&auth,&headers,body),"malformed signature");
+        // missing headers & unknown clients
+        let mut headers = signed_headers(now,&sign(SECRET,now,body));
+        headers.remove(auth::SIGNATURE_HEADER);
+        assert_eq!(rejection(&auth,&headers,body),"missing `X-Auth-Signature` header");
+        set(&mut headers,auth::CLIENT_HEADER,"mallory");
+        assert_eq!(rejection(&auth,&headers,body),"unknown client");
+        // api keys
+        let mut headers = HeaderMap::new();
+        set(&mut headers,auth::CLIENT_HEADER,"monitor");
+        set(&mut headers,auth::KEY_HEADER,SECRET);
+        assert_eq!(auth.authenticate(&headers,body).unwrap().unwrap().name(),"monitor");
+        set(&mut headers,auth::KEY_HEADER,"incorrect");
+        assert_eq!(rejection(&auth,&headers,body),"invalid api key");
+        // open authenticators perform no checks
+        assert!(Auth::open().authenticate(&HeaderMap::new(),body).unwrap().is_none());
+    }
+
+
+    #[test]
+    fn client_credentials() {
+        let auth = config("").unwrap();
+        let body = br#"{"get-address":{}}"#;
+        let authenticate = |credentials: &Credentials, signed: &[u8]| {
+            let mut headers = HeaderMap::new();
+            for (name,value) in credentials.headers(signed) {
+                set(&mut headers,name,&value);
+            }
+            auth.authenticate(&headers,body).map(|client| client.unwrap().name().to_owned())
+        };
+        let vault = Credentials::new("vault",Scheme::Hmac,SECRET);
+        assert_eq!(authenticate(&vault,body).unwrap(),"vault");
+        assert!(authenticate(&vault,br#"{"get-contracts":{}}"#).is_err());
+        let monitor = Credentials::new("monitor",Scheme::ApiKey,SECRET);
+        assert_eq!(authenticate(&monitor,body).unwrap(),"monitor");
+        let mallory = Credentials::new("monitor",Scheme::ApiKey,"incorrect");
+        assert!(authenticate(&mallory,body).is_err());
+        // secrets are not exposed by debug formatting
+        assert!(!format!("{:?}",vault).contains(SECRET));
+        assert_eq!("api-key".parse::<Scheme>().unwrap(),Scheme::ApiKey);
+        assert!("hmac-sha256".parse::<Scheme>().is_err());
+    }
+
+
+    #[test]
+    fn replay_window() {
+        let auth = config("").unwrap();
+        let now = unix_time();
+        let body = br#"{"get-address":{}}"#;
+        for &time in [now - 40,now + 40].iter() {
+            let headers = signed_headers(time,&sign(SECRET,time,body));
+            assert_eq!(rejection(&auth,&headers,body),"timestamp outside replay window");
+        }
+        let time = now - 20;
+        let signature = sign(SECRET,time,body);
+        assert!(auth.authenticate(&signed_headers(time,&signature),body).is_ok());
+        assert_eq!(rejection(&auth,&signed_headers(time,&signature),body),"replayed request");
+        // re-encoding the signature does not evade replay detection
+        let reencoded = format!("0x{}",signature.to_uppercase());
+        assert_eq!(rejection(&auth,&signed_headers(time,&reencoded),body),"replayed request");
+        // a fresh timestamp yields a fresh signature
+        let time = now - 19;
+        assert!(auth.authenticate(&signed_headers(time,&sign(SECRET,time,body)),body).is_ok());
+    }
+
+
+    #[test]
+    fn permissions() {
+        let auth = config("").unwrap();
+        let mut headers = HeaderMap::new();
+        set(&mut headers,auth::CLIENT_HEADER,"monitor");
+        set(&mut headers,auth::KEY_HEADER,SECRET);
+        let monitor = auth.authenticate(&headers,b"").unwrap().unwrap();
+        let now = unix_time();
+        let vault = auth.authenticate(&signed_headers(now,&sign(SECRET,now,b"")),b"").unwrap().unwrap();
+        let forbidden = |rslt: Result<(),Error>| match rslt {
+            Err(Error::Forbidden { what, .. }) => what,
+            other => panic!("expected forbidden request, got {:?}",other),
+        };
+        let req = |method,function,key| Req { method, function, key };
+        // methods
+        assert!(monitor.allows(&req("get-address",None,None)).is_ok());
+        assert!(monitor.allows(&req("sign-raw-tx",None,Some("vault"))).is_ok());
+        assert_eq!(forbidden(monitor.allows(&req("sign-tx-call",Some("releaseDeposit"),None))),"method `sign-tx-call`");
+        // functions
+        assert!(vault.allows(&req("sign-tx-call",Some("releaseDeposit"),Some("vault"))).is_ok());
+        assert_eq!(forbidden(vault.allows(&req("sign-tx-call",Some("deposit"),Some("vault")))),"function `deposit`");
+        assert_eq!(forbidden(vault.allows(&req("sign-raw-tx",None,Some("vault")))),"method `sign-raw-tx`");
+        // keys, by name or address
+        assert!(vault.allows(&req("get-address",None,Some(VAULT))).is_ok());
+        assert!(vault.allows(&req("get-address",None,Some("00000000000000000000000000000000000A11CE"))).is_ok());
+        assert_eq!(forbidden(vault.allows(&req("get-address",None,Some("treasury")))),"key `treasury`");
+        assert_eq!(forbidden(vault.allows(&req("get-address",None,Some("VAULT")))),"key `VAULT`");
+        assert_eq!(forbidden(vault.allows(&req("get-address",None,None))),"the default key");
+    }
+
+
+    #[test]
+    fn invalid_configs() {
+        let config_error = |rslt: Result<Auth,Error>| match rslt {
+            Err(Error::Config(msg)) => msg,
+            other => panic!("expected config error, got {:?}",other),
+        };
+        // function restrictions cannot be enforced on raw transactions
+        let msg = config_error(config(r#"functions = ["deposit"]"#));
+        assert!(msg.contains("sign-raw-tx"),"unexpected error: {}",msg);
+        let duplicate = format!("[[client]]\nname = \"vault\"\nscheme = \"api-key\"\nsecret = \"{}\"\nmethods = []",SECRET);
+        assert_eq!(config_error(config(&duplicate)),"duplicate client `vault`");
+        let unkeyed = "[[client]]\nname = \"other\"\nscheme = \"api-key\"\nmethods = []";
+        assert_eq!(config_error(config(unkeyed)),"client `other` requires exactly one of `secret` or `secret-var`");
+    }
+}
@@ -1,11 +1,16 @@
 use hyper::client::HttpConnector;
 use hyper_tls::{self,HttpsConnector};
+use native_tls::TlsConnector;
 use tokio_util::service::{self,Service};
 use tokio::prelude::future::Either;
 use tokio::prelude::*;
+use tokio::net::TcpStream;
+use util::MaybeTls;
+use auth::{Auth,Restricted};
 use serde::de::DeserializeOwned;
 use serde::ser::Serialize;
 use log::Level;
+use std::net::SocketAddr;
 use std::{fmt,error};
 use serde_json::{self,Value};
 use hyper;
@@ -34,6 +39,17 @@ pub fn client() -> Result<Client,Error> {
 }
 
 
+/// Configure a client which uses the supplied tls connector for https uris
+///
+pub fn client_with_tls(tls: TlsConnector) -> Client {
+    let mut http = HttpConnector::new(CONNECTOR_THREADS);
+    http.enforce_http(false);
+    let connector = HttpsConnector::from((http,tls));
+    hyper::Client::builder()
+        .build(connector)
+}
+
+
 /// Marker trait indicating a task-safe error value
 pub trait SafeErr: error::Error + Send + 'static { }
 
@@ -58,32 +74,7 @@ pub fn serve_json<Srv,Req,Rsp>(incoming: impl Stream<Item=impl SafeIo,Error=impl
             let capture_handle = handle.clone();
             let service_fn = hyper::service::service_fn(move |req: Request<Body>| {
                 let sub_capture_handle = capture_handle.clone();
-                from_json_body(req.into_body()).then(move |rslt| {
-                    let rslt = rslt.map_err(|e| e.to_string());
-                    match rslt {
-                        Ok(req) => {
-                            Either::A(sub_capture_handle.call(req).map_err(|e|e.to_string()).and_then(|rsp| {
-                                into_json_body(&rsp).map_err(|e|e.to_string()).and_then(|body| {
-                                    //Response::new(body)
-                                    Response::builder()
-                                        .header("Content-Type","application/json")
-                                        .status(200)
-                                        .body(body)
-                                        .map_err(|e|e.to_string())
-                                })
-                            }))
-                        },
-                        Err(_) => {
-                            Either::B(Response::builder()
-                                .header("Content-Type","application/json")
-                                .status(400)
-                                .body(json!(rslt.map(drop)).to_string().into())
-                                .map_err(|e|e.to_string())
-                                .into_future()
-                            )
-                        }
-                    }
-                })
+                respond_json(req.into_body(),move |req| sub_capture_handle.call(req))
             });
             Ok(service_fn)
         };
@@ -94,6 +85,152 @@ pub fn serve_json<Srv,Req,Rsp>(incoming: impl Stream<Item=impl SafeIo,Error=impl
 }
 
 
+/// Context in which a request was received.
+///
+#[derive(Debug,Clone)]
+pub struct Context {
+    /// Address of the connected peer (if known)
+    pub peer: Option<SocketAddr>,
+    /// Name of the authenticated client (if authentication is enabled)
+    pub client: Option<String>,
+}
+
+
+/// Connection types capable of reporting their peer's address.
+///
+pub trait PeerAddr {
+
+    fn peer_addr(&self) -> Option<SocketAddr>;
+}
+
+
+impl PeerAddr for MaybeTls {
+
+    fn peer_addr(&self) -> Option<SocketAddr> { self.get_ref().peer_addr().ok() }
+}
+
+impl PeerAddr for TcpStream {
+
+    fn peer_addr(&self) -> Option<SocketAddr> { TcpStream::peer_addr(self).ok() }
+}
+
+
+/// Variant of `serve_json` which authenticates each request with `auth`, and
+/// passes its `Context` to the service alongside the request itself.
+///
+/// Requests which fail authentication are answered with status 401, and
+/// requests the client is not permitted to make with status 403.
+///
+pub fn serve_json_with_context<Inc,Io,Srv,Req,Rsp>(incoming: Inc, auth: Auth, service: Srv) -> impl Future<Item=(),Error=hyper::Error>
+    where
+        Inc: Stream<Item=Io>,
+        Inc::Error: SafeErr + Sync,
+        Io: SafeIo + PeerAddr,
+        Srv: Service<(Context,Req),Rsp,Error = ()> + Send + 'static,
+        Srv::Future: Send + 'static,
+        Req: DeserializeOwned + Restricted + fmt::Debug + Send + 'static,
+        Rsp: Serialize + fmt::Debug + Send + 'static {
+
+    service::spawn(service).map_err(|e| e.into()).and_then(|handle| {
+
+        let make_service = hyper::service::make_service_fn(move |conn: &Io| -> Result<_,String> {
+            let peer = conn.peer_addr();
+            let capture_handle = handle.clone();
+            let auth = auth.clone();
+            let service_fn = hyper::service::service_fn(move |req: Request<Body>| {
+                let sub_capture_handle = capture_handle.clone();
+                respond_authenticated(auth.clone(),peer,req,move |context,req| sub_capture_handle.call((context,req)))
+            });
+            Ok(service_fn)
+        });
+
+        hyper::Server::builder(incoming).serve(make_service)
+
+    })
+}
+
+
+/// Parse a json request body, pass it to `call`, and build a json response from the result.
+///
+/// Requests which fail to parse are answered with status 400.
+///
+fn respond_json<Req,Rsp,F,W>(body: Body, call: F) -> impl Future<Item=Response<Body>,Error=String>
+    where
+        Req: DeserializeOwned,
+        Rsp: Serialize,
+        F: FnOnce(Req) -> W,
+        W: Future<Item=Rsp>,
+        W::Error: fmt::Display {
+    body.concat2().map_err(|e| e.to_string()).and_then(move |body| {
+        let checked = serde_json::from_slice(&body).map_err(|err| (400u16,err.to_string()));
+        respond(None,checked.map(call))
+    })
+}
+
+
+/// Authenticate & authorize a json request, pass it to `call` along with its context,
+/// and build a json response from the result.
+///
+fn respond_authenticated<Req,Rsp,F,W>(auth: Auth, peer: Option<SocketAddr>, request: Request<Body>, call: F) -> impl Future<Item=Response<Body>,Error=String>
+    where
+        Req: DeserializeOwned + Restricted,
+        Rsp: Serialize,
+        F: FnOnce(Context,Req) -> W,
+        W: Future<Item=Rsp>,
+        W::Error: fmt::Display {
+    let (parts,body) = request.into_parts();
+    body.concat2().map_err(|e| e.to_string()).and_then(move |body| {
+        let checked = auth.authenticate(&parts.headers,&body)
+            .map_err(|err| (401u16,err.to_string()))
+            .and_then(|client| {
+                let req: Req = serde_json::from_slice(&body).map_err(|err| (400u16,err.to_string()))?;
+                if let Some(client) = client {
+                    client.allows(&req).map_err(|err| (403u16,err.to_string()))?;
+                }
+                Ok((client.map(|client| client.name().to_owned()),req))
+            });
+        respond(peer,checked.map(|(client,req)| call(Context { peer, client },req)))
+    })
+}
+
+
+/// Build a json response from the result of a request, or from the status & message
+/// with which it was rejected.
+///
+fn respond<Rsp,W>(peer: Option<SocketAddr>, checked: Result<W,(u16,String)>) -> impl Future<Item=Response<Body>,Error=String>
+    where
+        Rsp: Serialize,
+        W: Future<Item=Rsp>,
+        W::Error: fmt::Display {
+    match checked {
+        Ok(work) => {
+            Either::A(work.map_err(|e|e.to_string()).and_then(|rsp| {
+                into_json_body(&rsp).map_err(|e|e.to_string()).and_then(|body| {
+                    Response::builder()
+                        .header("Content-Type","application/json")
+                        .status(200)
+                        .body(body)
+                        .map_err(|e|e.to_string())
+                })
+            }))
+        },
+        Err((status,msg)) => {
+            if status != 400 {
+                warn!("Rejecting request from {:?}: {}",peer,msg);
+            }
+            let rslt: Result<(),String> = Err(msg);
+            Either::B(Response::builder()
+                .header("Content-Type","application/json")
+                .status(status)
+                .body(json!(rslt).to_string().into())
+                .map_err(|e|e.to_string())
+                .into_future()
+            )
+        },
+    }
+}
+
+
 pub type ParseJsonBody = ParseBody<Value>;
 
 #[derive(Debug)]
@@ -145,7 +282,14 @@ pub fn from_json_body<T>(body: Body) -> impl Future<Item=T,Error=Error> where T:
 
 
 pub fn into_json_body<T>(body: &T) -> impl Future<Item=Body,Error=Error> where T: Serialize {
-    serde_json::to_vec(body).map_err(From::from).map(From::from).into_future()
+    into_json_bytes(body).map(From::from).into_future()
+}
+
+
+/// Encode `body` as json, for use where the encoded bytes are needed (e.g. to sign them).
+///
+pub fn into_json_bytes<T>(body: &T) -> Result<Vec<u8>,Error> where T: Serialize {
+    serde_json::to_vec(body).map_err(From::from)
 }
 
 
@@ -16,12 +16,16 @@ extern crate http as _http;
 #[macro_use]
 extern crate log;
 extern crate url;
+extern crate toml;
+extern crate hmac;
+extern crate sha2;
 
 
 #[macro_use]
 mod macros;
 pub mod options;
 pub mod policy;
+pub mod auth;
 pub mod error;
 pub mod http;
 pub mod util;
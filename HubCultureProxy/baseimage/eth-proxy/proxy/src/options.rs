@@ -1,8 +1,10 @@
 use structopt::StructOpt;
 use std::net::IpAddr;
 use std::path::PathBuf;
-use native_tls::Identity;
+use native_tls::{Identity,Certificate,TlsConnector};
 use error::Error;
+use auth::{Auth,AuthConfig};
+use toml;
 use std::fs;
 
 #[derive(Debug,Clone,StructOpt)]
@@ -19,6 +21,9 @@ pub struct ServerOptions {
     /// Path to whitelist file
     #[structopt(long = "ip-whitelist",name = "ip-whitelist",parse(from_os_str))]
     whitelist: Option<PathBuf>,
+    /// Path to file declaring authenticated clients & their permissions
+    #[structopt(long = "auth-config",name = "auth-config",parse(from_os_str))]
+    auth_config: Option<PathBuf>,
 }
 
 
@@ -40,6 +45,24 @@ impl ServerOptions {
         }
     }
 
+    /// Load the client authenticator (open, if no auth config is specified).
+    ///
+    pub fn load_auth(&self) -> Result<Auth,Error> {
+        if let Some(ref path) = self.auth_config {
+            let raw = fs::read_to_string(path)?;
+            let config: AuthConfig = toml::from_str(&raw).map_err(|err| {
+                Error::message(format!("Failed to parse auth config ({})",err))
+            })?;
+            let auth = Auth::from_config(config).map_err(|err| Error::message(err.to_string()))?;
+            if !self.use_tls {
+                warn!("Client authentication configured without tls; credentials are sent in the clear");
+            }
+            Ok(auth)
+        } else {
+            Ok(Auth::open())
+        }
+    }
+
     pub fn load_whitelist(&self) -> Result<Option<Vec<IpAddr>>,Error> {
         if let Some(ref path) = self.whitelist {
             if path.is_file() {
@@ -63,3 +86,56 @@ impl ServerOptions {
         }
     }
 }
+
+
+#[derive(Debug,Clone,StructOpt)]
+pub struct ClientOptions {
+    /// Password for client PKCS-12 archive (required by `client-cert-file`)
+    #[structopt(long = "client-cert-pass",name = "client-cert-pass")]
+    certpass: Option<String>,
+    /// Path to PKCS-12 archive presented as client identity
+    #[structopt(long = "client-cert-file",name = "client-cert-file",parse(from_os_str))]
+    certfile: Option<PathBuf>,
+    /// Path to additional trusted root certificate (PEM or DER)
+    #[structopt(long = "ca-cert-file",name = "ca-cert-file",parse(from_os_str))]
+    ca_cert: Option<PathBuf>,
+}
+
+
+impl ClientOptions {
+
+    /// Build a tls connector using the configured identity & root certificate (if any).
+    ///
+    pub fn load_connector(&self) -> Result<TlsConnector,Error> {
+        let mut builder = TlsConnector::builder();
+        if let Some(ref path) = self.certfile {
+            let certpass = self.certpass.as_ref().ok_or_else(|| {
+                Error::message("`client-cert-file` requires `client-cert-pass`")
+            })?;
+            let archive = read_file(path,"archive")?;
+            let identity = Identity::from_pkcs12(&archive,certpass).map_err(|err| {
+                error!("Failed to decrypt PKCS-12 archive: {:?}",err);
+                Error::message("Failed to decrypt PKCS-12 archive (see logs for details)")
+            })?;
+            builder.identity(identity);
+        }
+        if let Some(ref path) = self.ca_cert {
+            let raw = read_file(path,"certificate")?;
+            let cert = Certificate::from_pem(&raw).or_else(|_| Certificate::from_der(&raw))?;
+            builder.add_root_certificate(cert);
+        }
+        let connector = builder.build()?;
+        Ok(connector)
+    }
+}
+
+
+fn read_file(path: &PathBuf, kind: &str) -> Result<Vec<u8>,Error> {
+    if path.is_file() {
+        let raw = fs::read(path)?;
+        Ok(raw)
+    } else {
+        let msg = format!("Unable to locate {} file (`{}`)",kind,path.display());
+        Err(Error::message(msg))
+    }
+}
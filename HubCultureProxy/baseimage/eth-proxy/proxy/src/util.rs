@@ -1,6 +1,8 @@
 use tokio::net::TcpStream;
 use tokio_tls::TlsStream;
 use tokio::prelude::*;
+use std::time::{SystemTime,UNIX_EPOCH};
+use std::sync::{Mutex,MutexGuard};
 use std::io;
 
 
@@ -64,6 +66,55 @@ impl AsyncWrite for MaybeTls {
 
 
 
+/// Get current unix timestamp
+///
+/// ## Panics
+///
+/// This function panics if called prior to the unix epoch
+///
+pub fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .expect("Must be called after the unix epoch")
+        .as_secs()
+}
+
+
+/// Lock `mutex`, recovering the guard if a previous holder panicked.
+///
+/// Only for state which is left consistent at every point a panic could occur
+/// (e.g. maps updated by single insertions & removals), such that a poisoned
+/// lock is safe to reuse.
+///
+pub fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+
+/// Compare two byte strings in time which depends only on their lengths.
+///
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0u8,|acc,(x,y)| acc | (x ^ y)) == 0
+}
+
+
+/// Lowercase hex encoding (without `0x` prefix).
+///
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}",b)).collect()
+}
+
+
+/// Decode hex (without `0x` prefix), failing on odd lengths or invalid digits.
+///
+pub fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2],16).ok()).collect()
+}
+
+
+
 /// Serialize/Deserialize a type using its `Display` and `FromStr` implementations respectively.
 pub mod serde_str {
     use serde::de::{self,Deserializer};
@@ -0,0 +1,19 @@
+extern crate ethrpc;
+
+use ethrpc::contract::codegen;
+use std::path::Path;
+use std::{env,fs};
+
+
+fn main() {
+    let abi_path = "contracts/HubCulture.abi";
+    println!("cargo:rerun-if-changed={}",abi_path);
+    let abi = fs::read_to_string(abi_path)
+        .unwrap_or_else(|e| panic!("unable to read {}: {}",abi_path,e));
+    let source = codegen::generate("HubCulture",&abi)
+        .unwrap_or_else(|e| panic!("unable to generate bindings for {}: {}",abi_path,e));
+    let out_dir = env::var("OUT_DIR").expect("cargo must set OUT_DIR");
+    let out_path = Path::new(&out_dir).join("hub_culture.rs");
+    fs::write(&out_path,source)
+        .unwrap_or_else(|e| panic!("unable to write {}: {}",out_path.display(),e));
+}
@@ -1,18 +1,87 @@
 use tokio::prelude::*;
+use tokio::prelude::future::Either;
 use signer::options::SignerOptions;
 use signer::{self,rpc};
+use signer::contracts::Contracts;
 use ethrpc::crypto::Address;
+use signer::rpc::{KeyedRequest,KeyId};
+use proxy::http::{self,Uri,ParseBody,ParseJsonBody};
+use proxy::auth::Credentials;
 use rpc::{
     BaseRequest,
     BaseResponse,
 };
+use std::time::Duration;
 use std::{fmt,error};
 
 /// Configure local signer instance.
 ///
+/// All requests are directed at the signer's default key, and are subject to
+/// its pre-sign hook (if configured).
+///
 pub fn configure_local(opt: &SignerOptions) -> Result<impl BaseSigner<Error=rpc::Error> + Clone,signer::Error> {
     let local_signer = signer::Signer::from_options(opt)?;
-    Ok(move |req| local_signer.serve(req))
+    let key = KeyId::from(local_signer.address());
+    Ok(move |req| local_signer.serve_async(None,KeyedRequest::new(key.clone(),req)))
+}
+
+
+/// Configure remote signer instance (as served by `eth-proxy run-signer`).
+///
+/// Requests are posted as json to `uri`, directed at `key` if specified, and
+/// authenticated with `credentials` if specified.  Transport & encoding failures,
+/// non-success statuses (e.g. failed authentication), and requests which are not
+/// answered within `timeout`, are surfaced as signer errors.
+///
+pub fn configure_remote(uri: Uri, key: Option<KeyId>, credentials: Option<Credentials>, client: http::Client, timeout: Duration) -> impl BaseSigner<Error=rpc::Error> + Clone {
+    move |req: BaseRequest| {
+        let request = KeyedRequest::new(key.clone(),req);
+        let client = client.clone();
+        let uri = uri.clone();
+        let credentials = credentials.clone();
+        let build_request = http::into_json_bytes(&request).and_then(move |body| {
+            let mut builder = http::Request::post(uri);
+            builder.header("Content-Type","application/json");
+            if let Some(credentials) = credentials.as_ref() {
+                for (name,value) in credentials.headers(&body) {
+                    builder.header(name,value.as_str());
+                }
+            }
+            builder.body(http::Body::from(body)).map_err(From::from)
+        });
+        build_request.into_future().and_then(move |request| client.request(request).from_err())
+            .map_err(|err| rpc::Error::message(format!("request to remote signer failed: {}",err)))
+            .and_then(|rsp| {
+                let status = rsp.status();
+                if status.is_success() {
+                    let parse = http::from_json_body(rsp.into_body()).map_err(move |err| {
+                        let msg = format!("invalid response from remote signer (status `{}`): {}",status,err);
+                        rpc::Error::message(msg)
+                    });
+                    Either::A(parse)
+                } else {
+                    let reject = ParseJsonBody::parse(rsp.into_body()).then(move |body| {
+                        // rejections by `run-signer` carry their reason as `{"Err":"..."}`
+                        let reason = match body {
+                            Ok(ParseBody::Json(value)) => value.get("Err").and_then(|err| err.as_str())
+                                .map(String::from).unwrap_or_else(|| value.to_string()),
+                            Ok(ParseBody::Str(text)) => text,
+                            Ok(ParseBody::Raw(bytes)) => format!("<{} bytes>",bytes.len()),
+                            Err(err) => err.to_string(),
+                        };
+                        let msg = format!("remote signer rejected request (status `{}`): {}",status,reason);
+                        Err(rpc::Error::message(msg))
+                    });
+                    Either::B(reject)
+                }
+            })
+            .and_then(|rslt: Result<BaseResponse,String>| rslt.map_err(rpc::Error::message))
+            .timeout(timeout)
+            .map_err(move |err| err.into_inner().unwrap_or_else(|| {
+                let msg = format!("remote signer did not respond within {}s",timeout.as_secs());
+                rpc::Error::message(msg)
+            }))
+    }
 }
 
 
@@ -69,6 +138,15 @@ impl<'a,S> Api<'a,S> where S: BaseSigner {
             rsp.to_addr().map_err(Error::expecting_addr)
         })
     }
+
+    /// Get contract whitelist & default contract.
+    ///
+    pub fn get_contracts(&self) -> impl Future<Item=Contracts,Error=Error<S::Error>> {
+        let req = BaseRequest::GetContracts { };
+        self.signer.call(req).from_err().and_then(|rsp| {
+            rsp.to_contracts().map_err(Error::expecting_contracts)
+        })
+    }
 }
 
 
@@ -76,6 +154,7 @@ impl<'a,S> Api<'a,S> where S: BaseSigner {
 ///
 #[derive(Debug,Copy,Clone,PartialEq,Eq)]
 pub enum Token {
+    Contracts,
     Address,
     Hash,
     Signature,
@@ -87,6 +166,7 @@ impl Token {
 
     fn as_str(&self) -> &'static str {
         match self {
+            Token::Contracts => "contracts",
             Token::Address => "address",
             Token::Hash => "bytes32",
             Token::Signature => "signature",
@@ -118,6 +198,13 @@ pub enum Error<E> {
 
 impl<E> Error<E> {
 
+    pub fn expecting_contracts(other: BaseResponse) -> Self {
+        Error::Unexpected {
+            expecting: Token::Contracts,
+            got: other,
+        }
+    }
+
     pub fn expecting_addr(other: BaseResponse) -> Self {
         Error::Unexpected {
             expecting: Token::Address,
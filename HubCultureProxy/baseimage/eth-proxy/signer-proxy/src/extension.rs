@@ -1,7 +1,9 @@
 use rpc::{TxStatus,ExtRequest};
+use ethrpc::api::Confirmation;
 use ethrpc::types::H256;
 use ethrpc::{self,api,Url};
 use tokio::prelude::*;
+use std::time::Duration;
 
 
 /// Spawn a handler for extension-requests.
@@ -48,10 +50,32 @@ impl Handler {
 
     pub fn handle_ext(&self, req: ExtRequest) -> impl Future<Item=Option<TxStatus>,Error=Error> {
         match req {
-            ExtRequest::GetTxStatus { hash } => self.get_tx_status(hash),
+            ExtRequest::GetTxStatus { hash } => {
+                future::Either::A(self.get_tx_status(hash))
+            },
+            ExtRequest::AwaitTxStatus { hash, confirmations, timeout } => {
+                let timeout = Duration::from_secs(timeout);
+                future::Either::B(self.await_tx_status(hash,confirmations,timeout))
+            },
         }
     }
 
+    pub fn await_tx_status(&self, tx_hash: H256, confirmations: u64, timeout: Duration) -> impl Future<Item=Option<TxStatus>,Error=Error> {
+        let work = ethrpc::connect(self.node.clone()).and_then(move |api| {
+            api.util().await_receipt(tx_hash,confirmations,timeout).map(|confirmation| {
+                match confirmation {
+                    Confirmation::Confirmed(receipt) => Some(TxStatus::Mined {
+                        block_number: receipt.block_number,
+                        block_hash: receipt.block_hash,
+                        execution: receipt.status.map(From::from),
+                    }),
+                    Confirmation::Dropped { .. } => Some(TxStatus::Dropped { }),
+                }
+            })
+        });
+        work
+    }
+
     pub fn get_tx_status(&self, tx_hash: H256) -> impl Future<Item=Option<TxStatus>,Error=Error> {
         let work = ethrpc::connect(self.node.clone()).and_then(move |api| {
             api.eth().get_tx_by_hash(tx_hash).and_then(move |rslt| {
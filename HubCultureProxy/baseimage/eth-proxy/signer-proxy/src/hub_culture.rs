@@ -0,0 +1,7 @@
+//! Typed bindings for the `HubCulture` contract.
+//!
+//! Generated from `contracts/HubCulture.abi` by the build script (see
+//! `ethrpc::contract::codegen`).  Calls built from these bindings may be
+//! submitted via `rpc::TxCall::typed`.
+//!
+include!(concat!(env!("OUT_DIR"),"/hub_culture.rs"));
@@ -24,34 +24,61 @@ pub mod base;
 pub mod options;
 pub mod util;
 pub mod rpc;
+pub mod hub_culture;
 
 use tokio::prelude::*;
 use signer::rpc::Error as SignerError;
 use signer::Error as SetupError;
 use rpc::{Request,Response};
 use extension::ExtHandler;
-use transact::TxHandler;
+use transact::{TxHandler,GasPolicy};
 use options::SignerProxyOptions;
 use ethrpc::Url;
+use proxy::http;
 use base::BaseSigner;
+use std::time::Duration;
 use std::{fmt,error};
 
 
 pub type BoxHandler<E> = Box<RequestHandler<Future=Box<Future<Item=Response,Error=Error<E>> + Send>,Error=Error<E>> + Send>;
 
+/// Spawn a `RequestHandler` backed by a local signer instance, or by a remote
+/// signer if `--remote-signer` was specified.
+///
 pub fn spawn_local(opt: &SignerProxyOptions) -> Result<Box<Future<Item=BoxHandler<SignerError>,Error=Error<SignerError>> + Send>,SetupError> {
-    let local_signer = base::configure_local(&opt.signer)?;
     let node = opt.node_addr.clone();
-    let work = Box::new(spawn(local_signer,node));
-    Ok(work)
+    if let Some(uri) = opt.remote_signer.as_ref() {
+        if opt.signer.has_secret() {
+            warn!("Ignoring local secret (delegating signing to remote)");
+        }
+        if uri.scheme_part().map(|scheme| scheme.as_str()) != Some("https") {
+            warn!("Remote signer not configured for TLS; requests will be unencrypted");
+        }
+        let tls = opt.client.load_connector().map_err(|err| {
+            SetupError::message(format!("unable to configure tls client: {}",err))
+        })?;
+        let client = http::client_with_tls(tls);
+        info!("Delegating signing to remote signer at {}",uri);
+        let credentials = opt.remote_credentials().map_err(SetupError::message)?;
+        if credentials.is_none() {
+            warn!("No remote signer credentials configured; requests will not be authenticated");
+        }
+        let remote_signer = base::configure_remote(uri.clone(),opt.remote_key.clone(),credentials,client,opt.remote_timeout());
+        let work = Box::new(spawn(remote_signer,node,opt.gas_policy(),opt.tx_queue_capacity.get(),opt.max_head_age()));
+        Ok(work)
+    } else {
+        let local_signer = base::configure_local(&opt.signer)?;
+        let work = Box::new(spawn(local_signer,node,opt.gas_policy(),opt.tx_queue_capacity.get(),opt.max_head_age()));
+        Ok(work)
+    }
 }
 
 
 /// Spawn a `RequestHandler` instance.
 ///
-pub fn spawn<S>(signer: S, node: Url) -> impl Future<Item=BoxHandler<S::Error>,Error=Error<S::Error>>
+pub fn spawn<S>(signer: S, node: Url, gas: GasPolicy, capacity: usize, max_head_age: Duration) -> impl Future<Item=BoxHandler<S::Error>,Error=Error<S::Error>>
         where S: BaseSigner + Clone + Send + 'static {
-    let work = transact::spawn(signer.clone(),node.clone()).map_err(Error::tx)
+    let work = transact::spawn(signer.clone(),node.clone(),gas,capacity,max_head_age).map_err(Error::tx)
         .map(move |tx_handler| -> BoxHandler<S::Error> {
             let ext_handler = extension::spawn(node);
             Box::new(ProxySigner {
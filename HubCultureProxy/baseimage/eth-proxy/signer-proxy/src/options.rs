@@ -1,6 +1,13 @@
 use signer::options::SignerOptions;
+use signer::rpc::KeyId;
+use proxy::options::ClientOptions;
+use proxy::auth::{Credentials,Scheme};
 use proxy::http::Uri;
+use transact::GasPolicy;
 use ethrpc::Url;
+use std::time::Duration;
+use std::num::NonZeroUsize;
+use std::env;
 
 #[derive(Debug,Clone,StructOpt)]
 pub struct SignerProxyOptions {
@@ -10,7 +17,76 @@ pub struct SignerProxyOptions {
     /// Delegate signing to remote
     #[structopt(name = "signer-url",long="remote-signer")]
     pub remote_signer: Option<Uri>,
+    /// Name or address of key to use on remote signer (defaults to remote's default key)
+    #[structopt(name = "remote-key",long="remote-signer-key")]
+    pub remote_key: Option<KeyId>,
+    /// Fail requests to the remote signer which are not answered within this (seconds)
+    #[structopt(long="remote-signer-timeout",default_value="30")]
+    pub remote_timeout: u64,
+    /// Client name under which requests to the remote signer are authenticated
+    /// (requires `remote-signer-secret-var`)
+    #[structopt(name = "remote-signer-client",long="remote-signer-client")]
+    pub remote_client: Option<String>,
+    /// Scheme by which requests to the remote signer are authenticated (`hmac` or `api-key`)
+    #[structopt(name = "remote-signer-scheme",long="remote-signer-scheme",default_value="hmac")]
+    pub remote_scheme: Scheme,
+    /// Environment variable holding the secret presented to the remote signer
+    #[structopt(name = "remote-signer-secret-var",long="remote-signer-secret-var")]
+    pub remote_secret_var: Option<String>,
+    #[structopt(flatten)]
+    pub client: ClientOptions,
+    /// Multiplier applied to gas estimates
+    #[structopt(long="gas-multiplier",default_value="1.25")]
+    pub gas_multiplier: f64,
+    /// Upper bound for estimated gas limits
+    #[structopt(long="gas-ceiling",default_value="8000000")]
+    pub gas_ceiling: u64,
+    /// Maximum number of pending tx requests
+    #[structopt(long="tx-queue-capacity",default_value="64")]
+    pub tx_queue_capacity: NonZeroUsize,
+    /// Refuse to sign while the node's latest block is older than this (seconds)
+    #[structopt(long="max-head-age",default_value="120")]
+    pub max_head_age: u64,
     #[structopt(flatten)]
     pub signer: SignerOptions,
 }
 
+
+impl SignerProxyOptions {
+
+    /// Gas policy applied to transactions without an explicit gas limit.
+    ///
+    pub fn gas_policy(&self) -> GasPolicy {
+        GasPolicy { multiplier: self.gas_multiplier, ceiling: self.gas_ceiling }
+    }
+
+    /// Maximum age of the node's latest block before signing is refused.
+    ///
+    pub fn max_head_age(&self) -> Duration {
+        Duration::from_secs(self.max_head_age)
+    }
+
+    /// Credentials presented to the remote signer (if client authentication is configured).
+    ///
+    pub fn remote_credentials(&self) -> Result<Option<Credentials>,String> {
+        match (self.remote_client.as_ref(),self.remote_secret_var.as_ref()) {
+            (Some(client),Some(var)) => {
+                let secret = env::var(var).map_err(|_| {
+                    format!("unable to read remote signer secret from `{}`",var)
+                })?;
+                if secret.is_empty() {
+                    return Err(format!("remote signer secret in `{}` is empty",var));
+                }
+                Ok(Some(Credentials::new(client.as_str(),self.remote_scheme,secret)))
+            },
+            (None,None) => Ok(None),
+            _ => Err("`remote-signer-client` & `remote-signer-secret-var` must be given together".into()),
+        }
+    }
+
+    /// Maximum time to wait for a response from the remote signer.
+    ///
+    pub fn remote_timeout(&self) -> Duration {
+        Duration::from_secs(self.remote_timeout)
+    }
+}
@@ -7,6 +7,7 @@ mod error;
 pub use self::transaction::{
     TxCall,
     Transaction,
+    GasLimit,
 };
 pub use self::error::Error;
 
@@ -19,6 +20,8 @@ use ethrpc::types::{H256,U256,Status};
 use signer::functions::Call as FunctionCall;
 use signer::ethtokens::Request as TokenRequest;
 use signer::rpc;
+use proxy::auth::Restricted;
+use transact::Submitted;
 
 
 /// Transaction status report.
@@ -34,6 +37,7 @@ pub enum TxStatus {
         #[serde(default)]
         execution: Option<CallStatus>,
     },
+    Dropped { },
 }
 
 /// Evm call status.
@@ -67,6 +71,32 @@ pub enum Request {
 }
 
 
+impl Restricted for Request {
+
+    fn method(&self) -> &str {
+        match self {
+            Request::Transact(TxRequest::SignRawTx(_)) => "sign-raw-tx",
+            Request::Transact(TxRequest::SignTxCall(_)) => "sign-tx-call",
+            Request::Call(CallRequest::SignToken(_)) => "sign-token",
+            Request::Call(CallRequest::EncodeToken(_)) => "encode-token",
+            Request::Call(CallRequest::EncodeCall(_)) => "encode-call",
+            Request::Call(CallRequest::GetContracts { }) => "get-contracts",
+            Request::Call(CallRequest::GetAddress { }) => "get-address",
+            Request::Ext(ExtRequest::GetTxStatus { .. }) => "get-tx-status",
+            Request::Ext(ExtRequest::AwaitTxStatus { .. }) => "await-tx-status",
+        }
+    }
+
+    fn function(&self) -> Option<&str> {
+        match self {
+            Request::Transact(TxRequest::SignTxCall(tx)) => Some(&tx.call.name),
+            Request::Call(CallRequest::EncodeCall(call)) => Some(&call.name),
+            _other => None,
+        }
+    }
+}
+
+
 /// An outbound response.
 ///
 #[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
@@ -92,6 +122,16 @@ impl From<BaseResponse> for Response {
     fn from(rsp: BaseResponse) -> Self { Response::Base(rsp) }
 }
 
+impl From<Submitted> for Response {
+
+    fn from(submitted: Submitted) -> Self {
+        match submitted {
+            Submitted::Sent(hash) => hash.into(),
+            Submitted::Pending(pending) => Response::Base(pending.into()),
+        }
+    }
+}
+
 /// Request subset which generates transactions.
 ///
 /// These requests must be handled in a semi-synchronous fashion in
@@ -107,17 +147,26 @@ pub enum TxRequest {
 
 impl TxRequest {
 
+    /// Gas limit requested by the caller.
+    ///
+    pub fn gas_limit(&self) -> GasLimit {
+        match self {
+            TxRequest::SignRawTx(tx) => tx.gas_limit,
+            TxRequest::SignTxCall(tx) => tx.gas_limit,
+        }
+    }
+
     /// Convert to base request, inserting required values.
     ///
-    pub fn seed(self, nonce: U256, gas_price: U256) -> BaseRequest {
+    pub fn seed(self, nonce: U256, gas_price: U256, gas_limit: U256) -> BaseRequest {
         match self {
             TxRequest::SignRawTx(tx) => {
-                let Transaction { gas_limit, to, value, data } = tx;
+                let Transaction { to, value, data, .. } = tx;
                 let tx = rpc::Transaction { nonce, gas_price, gas_limit, to, value, data };
                 BaseRequest::SignRawTx(tx)
             },
             TxRequest::SignTxCall(tx) => {
-                let TxCall { gas_limit, to, value, call } = tx;
+                let TxCall { to, value, call, .. } = tx;
                 let tx = rpc::TxCall { nonce, gas_price, gas_limit, to, value, call };
                 BaseRequest::SignTxCall(tx)
             },
@@ -165,6 +214,21 @@ impl CallRequest {
 pub enum ExtRequest {
     GetTxStatus {
         hash: H256,
-    }
+    },
+    #[serde(rename_all = "kebab-case")]
+    AwaitTxStatus {
+        hash: H256,
+        /// Number of blocks the tx must be buried under (default: 1)
+        #[serde(default = "default_confirmations")]
+        confirmations: u64,
+        /// Maximum wait in seconds (default: 120)
+        #[serde(default = "default_await_timeout")]
+        timeout: u64,
+    },
 }
 
+
+fn default_confirmations() -> u64 { 1 }
+
+fn default_await_timeout() -> u64 { 120 }
+
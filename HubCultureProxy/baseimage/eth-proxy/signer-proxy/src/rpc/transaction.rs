@@ -1,22 +1,29 @@
 use signer::functions::Call;
 use ethrpc::types::{U256,Bytes};
+use ethrpc::contract::FunctionCall;
 use ethrpc::crypto::Address;
+use ethrpc::util::units::{self,serde_uint,ParseError};
+use serde::de::{Deserialize,Deserializer};
+use serde::ser::{Serialize,Serializer};
+use proxy::util::serde_str;
+use std::str::FromStr;
+use std::fmt;
 
 
 /// A contract-calling transaction (minus nonce and gas-price).
 ///
 #[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
 pub struct TxCall {
-    /// Gas limit (default: 90000)
-    #[serde(rename = "gas",default = "default_gas_limit")]
-    pub gas_limit: U256,
+    /// Gas limit (default: auto)
+    #[serde(rename = "gas",default)]
+    pub gas_limit: GasLimit,
 
     /// Destination address
     #[serde(default,skip_serializing_if = "Option::is_none")]
     pub to: Option<Address>,
 
     /// Transaction value in wei (default: 0)
-    #[serde(default)]
+    #[serde(default,with = "serde_uint")]
     pub value: U256,
 
     /// Description of a contract call
@@ -24,21 +31,32 @@ pub struct TxCall {
 }
 
 
+impl TxCall {
+
+    /// Build a call to the contract at `to` from typed bindings (see `hub_culture`).
+    ///
+    pub fn typed<F: FunctionCall>(to: Address, call: &F) -> Self {
+        let value = U256::from(0u64);
+        Self { gas_limit: GasLimit::default(), to: Some(to), value, call: Call::from_typed(call) }
+    }
+}
+
+
 /// A transaction body (minus nonce and gas-price).
 ///
 #[derive(Hash,Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
 pub struct Transaction {
-    /// Gas limit (default: 90000)
-    #[serde(rename = "gas",default = "default_gas_limit")]
-    pub gas_limit: U256,
+    /// Gas limit (default: auto)
+    #[serde(rename = "gas",default)]
+    pub gas_limit: GasLimit,
 
     /// Destination address (required unless contract-creation)
     #[serde(default,skip_serializing_if = "Option::is_none")]
     pub to: Option<Address>,
 
     /// Transaction value in wei (default: 0)
-    #[serde(default)]
-    pub value: U256, 
+    #[serde(default,with = "serde_uint")]
+    pub value: U256,
 
     /// Transaction data (default: empty)
     #[serde(default)]
@@ -46,7 +64,78 @@ pub struct Transaction {
 }
 
 
-/// Official default value for gas limit (per ethereum rpc spec).
+/// Gas limit of a transaction.
+///
+/// Serialized as either the string `"auto"` or a hex-encoded integer (decimal
+/// integers are also accepted).
+///
+/// ```
+/// extern crate signer_proxy;
+/// use signer_proxy::rpc::GasLimit;
 ///
-fn default_gas_limit() -> U256 { U256::from(90000u32) }
+/// # fn main() {
+/// assert_eq!("auto".parse::<GasLimit>().unwrap(),GasLimit::Auto);
+/// assert_eq!("0x15f90".parse::<GasLimit>().unwrap(),GasLimit::Fixed(90000u32.into()));
+/// assert_eq!("90000".parse::<GasLimit>().unwrap(),GasLimit::Fixed(90000u32.into()));
+/// assert_eq!(GasLimit::default(),GasLimit::Auto);
+/// # }
+/// ```
+///
+#[derive(Hash,Debug,Copy,Clone,PartialEq,Eq)]
+pub enum GasLimit {
+    /// Estimate gas prior to signing
+    Auto,
+    /// Use the supplied gas limit
+    Fixed(U256),
+}
+
+
+impl Default for GasLimit {
+
+    fn default() -> Self { GasLimit::Auto }
+}
+
+
+impl From<U256> for GasLimit {
 
+    fn from(limit: U256) -> Self { GasLimit::Fixed(limit) }
+}
+
+
+impl fmt::Display for GasLimit {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GasLimit::Auto => f.write_str("auto"),
+            GasLimit::Fixed(limit) => limit.fmt(f),
+        }
+    }
+}
+
+
+impl FromStr for GasLimit {
+
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        match s.trim() {
+            "auto" => Ok(GasLimit::Auto),
+            other => Ok(GasLimit::Fixed(units::parse_uint(other)?)),
+        }
+    }
+}
+
+
+impl Serialize for GasLimit {
+
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok,S::Error> {
+        serde_str::serialize(self,serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for GasLimit {
+
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self,D::Error> {
+        serde_str::deserialize(deserializer)
+    }
+}
@@ -0,0 +1,132 @@
+//! Gas-limit estimation.
+//!
+use ethrpc::types::{U256,Transaction};
+use ethrpc::util::trim;
+use ethrpc::{self,api,Url};
+use tokio::prelude::*;
+use std::{fmt,error};
+use util;
+
+
+/// Indicates failure to estimate gas.
+///
+pub type Error = api::Error;
+
+
+/// Initialize a gas estimator.
+///
+pub fn estimator(node: Url) -> impl GasEstimator<Error=Error> {
+    debug!("Initializing gas estimator with node {}",node);
+    let estimator = move |tx: Transaction| {
+        let node = node.clone();
+        util::retry(3,move || {
+            let tx = tx.clone();
+            ethrpc::connect(node.clone()).and_then(move |api| {
+                api.eth().estimate_gas(tx,Default::default())
+            })
+        })
+    };
+    estimator
+}
+
+
+/// Service responsible for estimating the gas required by a transaction.
+///
+pub trait GasEstimator {
+
+    /// Indicates failure to estimate gas.
+    ///
+    type Error;
+
+    /// Future yielding the estimated gas.
+    ///
+    type Future: Future<Item=U256,Error=Self::Error> + Send + 'static;
+
+    /// Estimate the gas required by `tx`.
+    ///
+    fn estimate_gas(&self, tx: Transaction) -> Self::Future;
+}
+
+
+impl<T,F> GasEstimator for T where T: Fn(Transaction) -> F, F: IntoFuture<Item=U256>, F::Future: Send + 'static {
+
+    type Error = <F as IntoFuture>::Error;
+
+    type Future = <F as IntoFuture>::Future;
+
+    fn estimate_gas(&self, tx: Transaction) -> Self::Future {
+        (self)(tx).into_future()
+    }
+}
+
+
+/// Rules for converting a gas estimate into a gas limit.
+///
+#[derive(Debug,Copy,Clone,PartialEq)]
+pub struct GasPolicy {
+    /// Multiplier applied to raw estimates
+    pub multiplier: f64,
+    /// Maximum allowable gas limit
+    pub ceiling: u64,
+}
+
+
+impl Default for GasPolicy {
+
+    fn default() -> Self {
+        Self { multiplier: 1.25, ceiling: 8_000_000 }
+    }
+}
+
+
+impl GasPolicy {
+
+    /// Apply multiplier to `estimate`, capping the result at the ceiling.
+    ///
+    /// Fails if the raw estimate already exceeds the ceiling, since the
+    /// transaction could not succeed with any permissible gas limit.
+    ///
+    pub fn apply(&self, estimate: U256) -> Result<U256,CeilingExceeded> {
+        let raw = trimmed_u64(&estimate).filter(|raw| *raw <= self.ceiling)
+            .ok_or(CeilingExceeded { estimate, ceiling: self.ceiling })?;
+        let scaled = (raw as f64 * self.multiplier).ceil();
+        let limit = if scaled < self.ceiling as f64 { scaled as u64 } else { self.ceiling };
+        debug!("Applied gas policy to estimate {} (limit {})",estimate,limit);
+        Ok(U256::from(limit.max(raw)))
+    }
+}
+
+
+/// Interpret a 256-bit integer as a `u64` (if small enough).
+///
+fn trimmed_u64(value: &U256) -> Option<u64> {
+    let trimmed = trim(value);
+    if trimmed.len() <= 8 {
+        Some(trimmed.iter().fold(0u64,|acc,byte| (acc << 8) | *byte as u64))
+    } else {
+        None
+    }
+}
+
+
+/// Indicates that a gas estimate exceeded the configured ceiling.
+///
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub struct CeilingExceeded {
+    pub estimate: U256,
+    pub ceiling: u64,
+}
+
+
+impl fmt::Display for CeilingExceeded {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"gas estimate {} exceeds ceiling {:#x}",self.estimate,self.ceiling)
+    }
+}
+
+
+impl error::Error for CeilingExceeded {
+
+    fn description(&self) -> &str { "gas estimate exceeds ceiling" }
+}
@@ -2,24 +2,36 @@
 //!
 use transact::nonce::{self,NonceStore};
 use transact::price::{self,PriceStore};
+use transact::ready::{self,ReadyStore};
+use transact::gas::{self,GasEstimator,GasPolicy,CeilingExceeded};
 use base::Error as SignerError;
 use base::BaseSigner;
-use tokio_channel::{mpsc,oneshot};
-use rpc::TxRequest;
+use signer::approvals::PendingApproval;
+use tokio_channel::oneshot;
+use rpc::{TxRequest,TxCall,GasLimit,BaseRequest};
 use tokio::prelude::*;
 use tokio;
-use ethrpc::types::{Never,Bytes,H256,U256};
-use ethrpc::{self,api,Url};
+use ethrpc::types::{self,Never,Bytes,H256,U256};
+use ethrpc::crypto::{self,Address};
+use ethrpc::util::queue;
+use ethrpc::{self,api,Url,Classify,ErrorKind};
+use ethrpc::api::Readiness;
+use std::time::Duration;
 use std::{fmt,error};
 use util;
 
 
 /// Spawn handler to event-loop.
 ///
-pub fn spawn<S>(signer: S, node: Url) -> impl Future<Item=impl TxHandler<Error=Error<S::Error>> + Clone,Error=Error<S::Error>>
+/// At most `capacity` requests may be queued awaiting processing; requests
+/// made while the queue is full fail with `Error::Overloaded`.  Requests
+/// fail with `Error::NotReady` while the node is syncing, or while its
+/// latest block is older than `max_head_age`.
+///
+pub fn spawn<S>(signer: S, node: Url, gas_policy: GasPolicy, capacity: usize, max_head_age: Duration) -> impl Future<Item=impl TxHandler<Error=Error<S::Error>> + Clone,Error=Error<S::Error>>
         where S: BaseSigner + Send + 'static {
-    init_base_handler(signer,node).map(|base| {
-        let handle = spawn_as_remote(base);
+    init_base_handler(signer,node,gas_policy,max_head_age).map(move |base| {
+        let handle = spawn_as_remote(base,capacity);
         let tx_handler = move |req| { handle.call(req) };
         tx_handler
     })
@@ -44,12 +56,21 @@ fn tx_sender(node: Url) -> impl TxSender<Error=api::Error> {
 
 /// Sets up basic handler instance.
 ///
-fn init_base_handler<S: BaseSigner>(signer: S, node: Url) -> impl Future<Item=impl BaseHandler<Error=Error<S::Error>>,Error=Error<S::Error>> {
-    let work = signer.api().get_address().from_err().map(move |addr| {
+fn init_base_handler<S: BaseSigner>(signer: S, node: Url, gas_policy: GasPolicy, max_head_age: Duration) -> impl Future<Item=impl BaseHandler<Error=Error<S::Error>>,Error=Error<S::Error>> {
+    let get_addr = signer.api().get_address();
+    let get_contracts = signer.api().get_contracts();
+    let work = get_addr.join(get_contracts).from_err().map(move |(addr,contracts)| {
         let tx_sender = tx_sender(node.clone());
         let nonce_store = nonce::store(node.clone(),addr);
-        let price_store = price::store(node);
-        Handler::new(signer,tx_sender,nonce_store,price_store)
+        let price_store = price::store(node.clone());
+        let ready_store = ready::store(node.clone(),max_head_age);
+        let gas = GasConfig {
+            estimator: gas::estimator(node),
+            policy: gas_policy,
+            from: addr,
+            default_to: contracts.get_default(),
+        };
+        Handler::new(signer,tx_sender,nonce_store,price_store,ready_store,gas)
     });
     work
 }
@@ -57,22 +78,33 @@ fn init_base_handler<S: BaseSigner>(signer: S, node: Url) -> impl Future<Item=im
 
 /// Sets up remote handler instance.
 ///
-fn spawn_as_remote<T>(handler: T) -> RemoteHandle<T::Error>
+fn spawn_as_remote<T>(handler: T, capacity: usize) -> RemoteHandle<T::Error>
         where T: BaseHandler + Send + 'static, T::Error: Send + 'static {
     let remote = RemoteHandler::new(handler);
-    let (tx,rx) = mpsc::unbounded();
+    let (tx,rx) = queue::bounded(capacity);
     let work = rx.forward(remote.sink_map_err(|e| e.into::<()>()));
     tokio::spawn(work.map(drop));
     RemoteHandle::new(tx)
 }
 
 
+/// Result of a transaction-generating request.
+///
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Submitted {
+    /// Transaction was signed & sent (yielding its hash)
+    Sent(H256),
+    /// Transaction awaits approval, and was neither signed nor sent
+    Pending(PendingApproval),
+}
+
+
 /// Wraps `TxRequest` for handling by dedicated task.
 ///
 #[derive(Debug)]
 struct RemoteRequest<E> {
     req: TxRequest,
-    rsp: oneshot::Sender<Result<H256,E>>,
+    rsp: oneshot::Sender<Result<Submitted,E>>,
 }
 
 
@@ -80,7 +112,7 @@ struct RemoteRequest<E> {
 ///
 #[derive(Debug)]
 struct RemoteHandle<E> {
-    inner: mpsc::UnboundedSender<RemoteRequest<E>>,
+    inner: queue::Sender<RemoteRequest<E>>,
 }
 
 
@@ -94,17 +126,25 @@ impl<E> Clone for RemoteHandle<E> {
 
 impl<E> RemoteHandle<E> {
 
-    fn new(inner: mpsc::UnboundedSender<RemoteRequest<E>>) -> Self {
+    fn new(inner: queue::Sender<RemoteRequest<E>>) -> Self {
         Self { inner }
     }
 }
 
-impl<E> RemoteHandle<E> where E: From<oneshot::Canceled> + From<mpsc::SendError<RemoteRequest<E>>> {
+impl<E> RemoteHandle<E> where E: From<oneshot::Canceled> + From<queue::SendError<RemoteRequest<E>>> {
 
-    fn call(&self, request: TxRequest) -> impl Future<Item=H256,Error=E> {
+    fn call(&self, request: TxRequest) -> impl Future<Item=Submitted,Error=E> {
         let (tx,rx) = oneshot::channel();
         let remote_req = RemoteRequest { req: request, rsp: tx };
-        self.inner.unbounded_send(remote_req).into_future().from_err::<E>()
+        let pushed = self.inner.try_send(remote_req);
+        match pushed {
+            Ok(()) => debug!("Queued tx request (depth {})",self.inner.depth()),
+            Err(ref err) if err.is_overloaded() => {
+                warn!("Rejecting tx request; queue full (capacity {})",self.inner.capacity());
+            },
+            Err(_) => { },
+        }
+        pushed.into_future().from_err::<E>()
             .and_then(move |()| rx.from_err())
             .flatten()
     }
@@ -121,7 +161,7 @@ struct RemoteHandler<T,E> {
     /// Inner handler instance
     inner: T,
     /// Response channel for pending result
-    rsp: Option<oneshot::Sender<Result<H256,E>>>,
+    rsp: Option<oneshot::Sender<Result<Submitted,E>>>,
 }
 
 
@@ -154,7 +194,7 @@ impl<T> RemoteHandler<T,T::Error> where T: BaseHandler {
     fn poll_work(&mut self) -> Async<()> {
         if self.inner.is_working() {
             let result = match self.inner.poll_work() {
-                Ok(Async::Ready(tx_hash)) => Ok(tx_hash),
+                Ok(Async::Ready(submitted)) => Ok(submitted),
                 Ok(Async::NotReady) => { return Async::NotReady; },
                 Err(err) => Err(err),
             };
@@ -198,7 +238,7 @@ pub trait TxHandler {
     type Error: Send + 'static;
 
     /// Type of future yielded by this handler.
-    type Future: Future<Item=H256,Error=Self::Error> + Send + 'static;
+    type Future: Future<Item=Submitted,Error=Self::Error> + Send + 'static;
 
     /// Handle a transaction-generating request.
     fn handle_tx(&self, req: TxRequest) -> Self::Future;
@@ -206,7 +246,7 @@ pub trait TxHandler {
 
 
 impl<T,F> TxHandler for T
-        where T: Fn(TxRequest) -> F, F: IntoFuture<Item=H256>,
+        where T: Fn(TxRequest) -> F, F: IntoFuture<Item=Submitted>,
               F::Future: Send + 'static, F::Error: Send + 'static {
 
     type Error = <F as IntoFuture>::Error;
@@ -228,7 +268,7 @@ impl<T,F> TxHandler for T
 ///
 trait TxSender {
 
-    type Error;
+    type Error: Classify;
 
     type Future: Future<Item=H256,Error=Self::Error> + Send + 'static;
 
@@ -236,7 +276,7 @@ trait TxSender {
 }
 
 
-impl<T,F> TxSender for T where T: Fn(Bytes) -> F, F: IntoFuture<Item=H256>, F::Future: Send + 'static {
+impl<T,F> TxSender for T where T: Fn(Bytes) -> F, F: IntoFuture<Item=H256>, F::Future: Send + 'static, F::Error: Classify {
 
     type Error = <F as IntoFuture>::Error;
 
@@ -260,20 +300,20 @@ trait BaseHandler {
 
     fn push_work(&mut self, req: TxRequest);
 
-    fn poll_work(&mut self) -> Poll<H256,Self::Error>;
+    fn poll_work(&mut self) -> Poll<Submitted,Self::Error>;
 
     fn is_working(&self) -> bool;
 }
 
 
-impl<S,T,N,P> BaseHandler for Handler<S,T,N,P,S::Future,T::Future>
-        where S: BaseSigner, T: TxSender, N: NonceStore, P: PriceStore {
+impl<S,T,N,P,R,E> BaseHandler for Handler<S,T,N,P,R,E,S::Future,T::Future,E::Future>
+        where S: BaseSigner, T: TxSender, N: NonceStore, P: PriceStore, R: ReadyStore, E: GasEstimator {
 
-    type Error = Error<S::Error,T::Error,N::Error,P::Error>;
+    type Error = Error<S::Error,T::Error,N::Error,P::Error,E::Error,R::Error>;
 
     fn push_work(&mut self, req: TxRequest) { self.push_work(req) }
 
-    fn poll_work(&mut self) -> Poll<H256,Self::Error> { self.poll_work() }
+    fn poll_work(&mut self) -> Poll<Submitted,Self::Error> { self.poll_work() }
 
     fn is_working(&self) -> bool { self.work.is_some() }
 }
@@ -285,49 +325,65 @@ impl<S,T,N,P> BaseHandler for Handler<S,T,N,P,S::Future,T::Future>
 /// workaround to allow convenient usage of methods which return
 /// `impl Future`.
 ///
-struct Handler<S,T,N,P,A,B> {
+struct Handler<S,T,N,P,R,E,A,B,C> {
     signer: S,
     tx_sender: T,
     nonce_store: N,
     price_store: P,
-    work: Option<Work<A,B>>,
+    ready_store: R,
+    gas: GasConfig<E>,
+    work: Option<Work<A,B,C>>,
 }
 
 
-impl<S,T,N,P,A,B> Handler<S,T,N,P,A,B> {
+impl<S,T,N,P,R,E,A,B,C> Handler<S,T,N,P,R,E,A,B,C> {
 
-    pub fn new(signer: S, tx_sender: T, nonce_store: N, price_store: P) -> Self {
+    pub fn new(signer: S, tx_sender: T, nonce_store: N, price_store: P, ready_store: R, gas: GasConfig<E>) -> Self {
         let work = Default::default();
-        Self { signer, tx_sender, nonce_store, price_store, work }
+        Self { signer, tx_sender, nonce_store, price_store, ready_store, gas, work }
     }
 }
 
 
-impl<S,T,N,P> Handler<S,T,N,P,S::Future,T::Future>
-        where S: BaseSigner, T: TxSender, N: NonceStore, P: PriceStore {
+/// Gas estimation settings for transactions which do not specify
+/// a gas limit.
+///
+struct GasConfig<E> {
+    estimator: E,
+    policy: GasPolicy,
+    /// Signer address (origin of estimated txs)
+    from: Address,
+    /// Default contract of the signer (if any)
+    default_to: Option<Address>,
+}
+
+
+impl<S,T,N,P,R,E> Handler<S,T,N,P,R,E,S::Future,T::Future,E::Future>
+        where S: BaseSigner, T: TxSender, N: NonceStore, P: PriceStore, R: ReadyStore, E: GasEstimator {
 
 
     pub fn cancel(&mut self) {
         let _ = self.work.take();
         self.nonce_store.cancel();
         self.price_store.cancel();
+        self.ready_store.cancel();
     }
 
     /// Push a new request for processing.
     ///
     pub fn push_work(&mut self, req: TxRequest) {
         debug_assert!(self.work.is_none(),"must not push new work while job is pending");
-        let job = Work::SeedTx { tx: Some(req), nonce: None, price: None };
+        let job = Work::SeedTx { tx: Some(req), ready: false, nonce: None, price: None };
         self.work = Some(job);
     }
 
     /// Drive current request-process work to completion.
     ///
-    pub fn poll_work(&mut self) -> Poll<H256,Error<S::Error,T::Error,N::Error,P::Error>> {
+    pub fn poll_work(&mut self) -> Poll<Submitted,Error<S::Error,T::Error,N::Error,P::Error,E::Error,R::Error>> {
         match self.poll_work_inner() {
-            Ok(Async::Ready(tx_hash)) => {
+            Ok(Async::Ready(submitted)) => {
                 self.cancel();
-                Ok(Async::Ready(tx_hash))
+                Ok(Async::Ready(submitted))
             },
             Ok(Async::NotReady) => Ok(Async::NotReady),
             Err(err) => {
@@ -338,12 +394,20 @@ impl<S,T,N,P> Handler<S,T,N,P,S::Future,T::Future>
     }
 
     /// Core state-machine logic for driving request-processing.
-    fn poll_work_inner(&mut self) -> Poll<H256,Error<S::Error,T::Error,N::Error,P::Error>> {
+    fn poll_work_inner(&mut self) -> Poll<Submitted,Error<S::Error,T::Error,N::Error,P::Error,E::Error,R::Error>> {
         loop {
             let next_step = match self.work.as_mut().expect("cannot poll empty work cache") {
-                // Drive nonce & gas-price loading ops to completions and begin
-                // sigining process on success.
-                Work::SeedTx { ref mut tx, ref mut nonce, ref mut price } => {
+                // Confirm node readiness, then drive nonce & gas-price loading
+                // ops to completions and begin sigining process on success (or
+                // gas estimation if the gas limit was not specified).
+                Work::SeedTx { ref mut tx, ref mut ready, ref mut nonce, ref mut price } => {
+                    if !*ready {
+                        let readiness = try_ready!(self.ready_store.poll_ready().map_err(Error::ready));
+                        if !readiness.is_ready() {
+                            return Err(Error::NotReady(readiness));
+                        }
+                        *ready = true;
+                    }
                     if nonce.is_none() {
                         match self.nonce_store.poll_nonce().map_err(Error::nonce)? {
                             Async::Ready(n) => { *nonce = Some(n); },
@@ -359,28 +423,114 @@ impl<S,T,N,P> Handler<S,T,N,P,S::Future,T::Future>
                     match (nonce,price) {
                         (Some(nonce),Some(price)) => {
                             let tx_request = tx.take().expect("tx request must exist");
-                            let seeded_request = tx_request.seed(*nonce,*price);
-                            let work = self.signer.call(seeded_request);
-                            Work::SignTx { work }
+                            match (tx_request.gas_limit(),tx_request) {
+                                (GasLimit::Fixed(gas_limit),tx_request) => {
+                                    let seeded_request = tx_request.seed(*nonce,*price,gas_limit);
+                                    let work = self.signer.call(seeded_request);
+                                    Work::SignTx { work }
+                                },
+                                (GasLimit::Auto,TxRequest::SignTxCall(call)) => {
+                                    let work = self.signer.call(BaseRequest::EncodeCall(call.call.clone()));
+                                    Work::EncodeCall { call: Some(call), nonce: *nonce, price: *price, work }
+                                },
+                                (GasLimit::Auto,TxRequest::SignRawTx(raw)) => {
+                                    let estimate_tx = types::Transaction {
+                                        nonce: Some(*nonce),
+                                        gas_price: Some(*price),
+                                        gas_limit: None,
+                                        from: Some(self.gas.from),
+                                        to: raw.to,
+                                        value: Some(raw.value),
+                                        data: Some(raw.data.clone()),
+                                    };
+                                    let work = self.gas.estimator.estimate_gas(estimate_tx);
+                                    let tx = Some(TxRequest::SignRawTx(raw));
+                                    Work::EstimateGas { tx, nonce: *nonce, price: *price, work }
+                                },
+                            }
                         },
                         _ => { return Ok(Async::NotReady); }
                     }
                 },
-                // Drive tx signing to completion and begin tx-submission
+                // Drive calldata encoding to completion and begin gas
+                // estimation on success.
+                Work::EncodeCall { ref mut call, ref nonce, ref price, ref mut work } => {
+                    let response = try_ready!(work.poll().map_err(Error::signer));
+                    let calldata = response.to_bytes()
+                        .map_err(SignerError::expecting_bytes)?;
+                    let call = call.take().expect("tx call must exist");
+                    let to = call.to.or(self.gas.default_to).ok_or(Error::NoTarget)?;
+                    let estimate_tx = types::Transaction {
+                        nonce: Some(*nonce),
+                        gas_price: Some(*price),
+                        gas_limit: None,
+                        from: Some(self.gas.from),
+                        to: Some(to),
+                        value: Some(call.value),
+                        data: Some(calldata),
+                    };
+                    let work = self.gas.estimator.estimate_gas(estimate_tx);
+                    let tx = Some(TxRequest::SignTxCall(call));
+                    Work::EstimateGas { tx, nonce: *nonce, price: *price, work }
+                },
+                // Drive gas estimation to completion and begin signing
                 // process on success.
+                Work::EstimateGas { ref mut tx, ref nonce, ref price, ref mut work } => {
+                    let estimate = try_ready!(work.poll().map_err(Error::estimate));
+                    let gas_limit = self.gas.policy.apply(estimate).map_err(Error::GasCeiling)?;
+                    let tx_request = tx.take().expect("tx request must exist");
+                    let seeded_request = tx_request.seed(*nonce,*price,gas_limit);
+                    let work = self.signer.call(seeded_request);
+                    Work::SignTx { work }
+                },
+                // Drive tx signing to completion and begin tx-submission
+                // process on success.  Requests held for approval are passed
+                // back to the caller (the nonce is not consumed).
                 Work::SignTx { ref mut work } => {
                     let response = try_ready!(work.poll().map_err(Error::signer));
-                    let encoded = response.to_bytes().map_err(|e| e.into())
-                        .map_err(SignerError::expecting_bytes)?;
+                    let encoded = match response.to_pending() {
+                        Ok(pending) => {
+                            info!("Tx request held for approval as {}",pending.pending);
+                            return Ok(Async::Ready(Submitted::Pending(pending)));
+                        },
+                        Err(other) => other.to_bytes().map_err(SignerError::expecting_bytes)?,
+                    };
+                    let tx_hash = crypto::keccak(&encoded[..]);
                     let work = self.tx_sender.send_raw(encoded);
-                    Work::SendTx { work }
+                    Work::SendTx { tx_hash, work }
                 },
                 // Drive tx-submission to completion and increment nonce
-                // cache on success.
-                Work::SendTx { ref mut work } => {
-                    let tx_hash = try_ready!(work.poll().map_err(Error::tx_sender));
-                    self.nonce_store.increment();
-                    return Ok(Async::Ready(tx_hash));
+                // cache on success.  Rejections which indicate stale cache
+                // state cause the relevant cache to be cleared.
+                Work::SendTx { ref tx_hash, ref mut work } => {
+                    let error = match work.poll() {
+                        Ok(Async::Ready(tx_hash)) => {
+                            self.nonce_store.increment();
+                            return Ok(Async::Ready(Submitted::Sent(tx_hash)));
+                        },
+                        Ok(Async::NotReady) => { return Ok(Async::NotReady); },
+                        Err(error) => error,
+                    };
+                    let kind = error.kind();
+                    match kind {
+                        // A previous attempt reached the node (e.g. the response was
+                        // lost and the send was retried); the tx is already pending.
+                        ErrorKind::AlreadyKnown => {
+                            warn!("Node already knows tx {:?}, treating as sent",tx_hash);
+                            self.nonce_store.increment();
+                            return Ok(Async::Ready(Submitted::Sent(*tx_hash)));
+                        },
+                        ErrorKind::NonceTooLow => {
+                            warn!("Nonce rejected as too low, clearing nonce cache");
+                            self.nonce_store.reset();
+                        },
+                        ErrorKind::Underpriced => {
+                            warn!("Gas price rejected as too low, clearing price cache");
+                            self.price_store.reset();
+                        },
+                        _ => { },
+                    }
+                    return Err(Error::TxSender { kind, error });
                 },
             };
             self.work = Some(next_step);
@@ -391,29 +541,61 @@ impl<S,T,N,P> Handler<S,T,N,P,S::Future,T::Future>
 
 /// Stores the current state of request-processing.
 /// 
-enum Work<S,T> {
+enum Work<S,T,E> {
     SeedTx {
         tx: Option<TxRequest>,
+        ready: bool,
         nonce: Option<U256>,
         price: Option<U256>,
     },
+    EncodeCall {
+        call: Option<TxCall>,
+        nonce: U256,
+        price: U256,
+        work: S,
+    },
+    EstimateGas {
+        tx: Option<TxRequest>,
+        nonce: U256,
+        price: U256,
+        work: E,
+    },
     SignTx { work: S },
-    SendTx { work: T },
+    SendTx {
+        tx_hash: H256,
+        work: T,
+    },
 }
 
 
 /// Indicates failure to serve a transaction-generating request.
 ///
 #[derive(Debug)]
-pub enum Error<S,T=api::Error,N=nonce::Error,P=price::Error> {
+pub enum Error<S,T=api::Error,N=nonce::Error,P=price::Error,G=gas::Error,R=ready::Error> {
     /// Singning failed
     Signer(SignerError<S>),
     /// Transaction submission failed
-    TxSender(T),
+    TxSender {
+        /// Classification of the node's rejection
+        kind: ErrorKind,
+        error: T,
+    },
     /// Nonce lookup failed
     Nonce(N),
     /// Gas-price lookup failed
     Price(P),
+    /// Gas estimation failed
+    Estimate(G),
+    /// Node readiness check failed
+    Ready(R),
+    /// Node is syncing or behind
+    NotReady(Readiness),
+    /// Gas estimate exceeded ceiling
+    GasCeiling(CeilingExceeded),
+    /// Request queue is full
+    Overloaded,
+    /// No destination available for gas estimation
+    NoTarget,
     /// Response channel canceled
     Canceled,
     /// Tx-handling task dropped
@@ -421,44 +603,54 @@ pub enum Error<S,T=api::Error,N=nonce::Error,P=price::Error> {
 }
 
 
-impl<S,T,N,P> From<SignerError<S>> for Error<S,T,N,P> {
+impl<S,T,N,P,G,R> From<SignerError<S>> for Error<S,T,N,P,G,R> {
 
     fn from(err: SignerError<S>) -> Self { Error::Signer(err) }
 }
 
-impl<S,T,N,P> Error<S,T,N,P> {
+impl<S,T,N,P,G,R> Error<S,T,N,P,G,R> {
 
     fn signer(err: S) -> Self { Error::Signer(err.into()) }
 
-    fn tx_sender(err: T) -> Self { Error::TxSender(err) }
-
     fn nonce(err: N) -> Self { Error::Nonce(err) }
 
     fn price(err: P) -> Self { Error::Price(err) }
+
+    fn estimate(err: G) -> Self { Error::Estimate(err) }
+
+    fn ready(err: R) -> Self { Error::Ready(err) }
 }
 
 
-impl<S,T,N,P> From<oneshot::Canceled> for Error<S,T,N,P> {
+impl<S,T,N,P,G,R> From<oneshot::Canceled> for Error<S,T,N,P,G,R> {
 
     fn from(_: oneshot::Canceled) -> Self { Error::Canceled }
 }
 
 
-impl<S,T,N,P,I> From<mpsc::SendError<I>> for Error<S,T,N,P> {
+impl<S,T,N,P,G,R,I> From<queue::SendError<I>> for Error<S,T,N,P,G,R> {
 
-    fn from(_: mpsc::SendError<I>) -> Self { Error::Dropped }
+    fn from(err: queue::SendError<I>) -> Self {
+        if err.is_overloaded() { Error::Overloaded } else { Error::Dropped }
+    }
 }
 
 
-impl<S,T,N,P> fmt::Display for Error<S,T,N,P>
-        where S: fmt::Display, T: fmt::Display, N: fmt::Display, P: fmt::Display {
+impl<S,T,N,P,G,R> fmt::Display for Error<S,T,N,P,G,R>
+        where S: fmt::Display, T: fmt::Display, N: fmt::Display, P: fmt::Display, G: fmt::Display, R: fmt::Display {
 
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Error::Signer(err) => err.fmt(f),
-            Error::TxSender(err) => err.fmt(f),
+            Error::TxSender { error, .. } => error.fmt(f),
             Error::Nonce(err) => err.fmt(f),
             Error::Price(err) => err.fmt(f),
+            Error::Estimate(err) => err.fmt(f),
+            Error::Ready(err) => err.fmt(f),
+            Error::NotReady(readiness) => write!(f,"refusing to sign; {}",readiness),
+            Error::GasCeiling(err) => err.fmt(f),
+            Error::NoTarget => f.write_str("no destination for gas estimation"),
+            Error::Overloaded => f.write_str("overloaded; too many pending tx requests"),
             Error::Canceled => f.write_str("response channel canceled"),
             Error::Dropped => f.write_str("tx-handler task dropped"),
         }
@@ -466,15 +658,21 @@ impl<S,T,N,P> fmt::Display for Error<S,T,N,P>
 }
 
 
-impl<S,T,N,P> error::Error for Error<S,T,N,P>
-        where S: error::Error, T: error::Error, N: error::Error, P: error::Error {
+impl<S,T,N,P,G,R> error::Error for Error<S,T,N,P,G,R>
+        where S: error::Error, T: error::Error, N: error::Error, P: error::Error, G: error::Error, R: error::Error {
 
     fn description(&self) -> &str {
         match self {
             Error::Signer(err) => err.description(),
-            Error::TxSender(err) => err.description(),
+            Error::TxSender { error, .. } => error.description(),
             Error::Nonce(err) => err.description(),
             Error::Price(err) => err.description(),
+            Error::Estimate(err) => err.description(),
+            Error::Ready(err) => err.description(),
+            Error::NotReady(_) => "node not ready",
+            Error::GasCeiling(err) => err.description(),
+            Error::NoTarget => "no destination for gas estimation",
+            Error::Overloaded => "overloaded",
             Error::Canceled => "response channel canceled",
             Error::Dropped => "tx-handler task dropped",
         }
@@ -483,12 +681,17 @@ impl<S,T,N,P> error::Error for Error<S,T,N,P>
     fn cause(&self) -> Option<&dyn error::Error> {
         match self {
             Error::Signer(err) => Some(err),
-            Error::TxSender(err) => Some(err),
+            Error::TxSender { error, .. } => Some(error),
             Error::Nonce(err) => Some(err),
             Error::Price(err) => Some(err),
+            Error::Estimate(err) => Some(err),
+            Error::Ready(err) => Some(err),
+            Error::NotReady(_) => None,
+            Error::GasCeiling(err) => Some(err),
+            Error::NoTarget => None,
+            Error::Overloaded => None,
             Error::Canceled => None,
             Error::Dropped => None,
         }
     }
 }
-
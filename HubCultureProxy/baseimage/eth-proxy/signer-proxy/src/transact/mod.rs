@@ -1,14 +1,18 @@
 //! Transaction-specific signing.
 //!
 pub mod handler;
+pub mod gas;
 pub mod nonce;
 pub mod price;
+pub mod ready;
 
 
 pub use self::handler::{
     TxHandler,
+    Submitted,
     spawn
 };
+pub use self::gas::GasPolicy;
 
 /// Default error type for transaction signer
 ///
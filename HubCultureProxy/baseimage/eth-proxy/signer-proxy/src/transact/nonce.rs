@@ -58,6 +58,14 @@ pub trait NonceStore {
     /// will not get a stale result.
     ///
     fn cancel(&mut self);
+
+    /// Discard the cached nonce (if any).
+    ///
+    /// This function should be called if the node rejects a transaction
+    /// in a manner which indicates that the cached value is stale.  The next
+    /// call to `poll_nonce` will load a fresh value from the node.
+    ///
+    fn reset(&mut self);
 }
 
 
@@ -70,6 +78,8 @@ impl<L> NonceStore for NonceCache<L,L::Future> where L: NonceLoader {
     fn increment(&mut self) { self.increment() }
 
     fn cancel(&mut self) { self.cancel() }
+
+    fn reset(&mut self) { self.reset() }
 }
 
 
@@ -125,6 +135,10 @@ impl<L,F> NonceCache<L,F> {
         let _ = self.work.take();
     }
 
+    pub fn reset(&mut self) {
+        let _ = self.current.take();
+    }
+
     fn set_current(&mut self, nonce: U256) {
         if let Some(previous) = self.current.take() {
             if previous > nonce {
@@ -50,6 +50,14 @@ pub trait PriceStore {
     /// will not get a stale result.
     ///
     fn cancel(&mut self);
+
+    /// Discard the cached gas-price (if any).
+    ///
+    /// This function should be called if the node rejects a transaction
+    /// in a manner which indicates that the cached value is stale.  The next
+    /// call to `poll_price` will load a fresh value from the node.
+    ///
+    fn reset(&mut self);
 }
 
 
@@ -60,6 +68,8 @@ impl<L> PriceStore for PriceCache<L,L::Future> where L: PriceLoader {
     fn poll_price(&mut self) -> Poll<U256,Self::Error> { self.poll_price() }
 
     fn cancel(&mut self) { self.cancel() }
+
+    fn reset(&mut self) { self.reset() }
 }
 
 
@@ -108,6 +118,10 @@ impl<L,F> PriceCache<L,F> {
         let _ = self.work.take();
     }
 
+    pub fn reset(&mut self) {
+        let _ = self.current.take();
+    }
+
     fn set_current(&mut self, price: U256) {
         self.last_update = Instant::now();
         self.current = Some(price);
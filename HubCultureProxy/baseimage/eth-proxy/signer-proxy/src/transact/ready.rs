@@ -0,0 +1,146 @@
+//! Node readiness checks.
+//!
+use std::time::{Duration,Instant};
+use ethrpc::api::Readiness;
+use ethrpc::{self,api,Url};
+use tokio::prelude::*;
+use std::fmt;
+use util;
+
+
+/// Indicates failure to check node readiness.
+///
+pub type Error = api::Error;
+
+
+/// Initialize a readiness store.
+///
+/// The node is considered ready if it is not syncing, and its latest
+/// block is no older than `max_head_age`.
+///
+pub fn store(node: Url, max_head_age: Duration) -> impl ReadyStore<Error=Error> {
+    debug!("Initializing readiness store with node {}",node);
+    let loader = move || {
+        let node = node.clone();
+        util::retry(3,move || {
+            ethrpc::connect(node.clone()).and_then(move |api| {
+                api.util().check_ready(max_head_age)
+            })
+        })
+    };
+    ReadyCache::new(loader)
+}
+
+
+/// Caching node readiness service.
+///
+pub trait ReadyStore {
+
+    /// Indicates failure to check readiness.
+    ///
+    type Error;
+
+    /// Poll for current node readiness.
+    ///
+    fn poll_ready(&mut self) -> Poll<Readiness,Self::Error>;
+
+    /// Cancel pending work (if any).
+    ///
+    /// This function must be called if `poll_ready` was called since the
+    /// last time an item or error was returned, but the caller no longer
+    /// cares about the result.
+    ///
+    fn cancel(&mut self);
+}
+
+
+impl<L> ReadyStore for ReadyCache<L,L::Future> where L: ReadyLoader {
+
+    type Error = <L as ReadyLoader>::Error;
+
+    fn poll_ready(&mut self) -> Poll<Readiness,Self::Error> { self.poll_ready() }
+
+    fn cancel(&mut self) { self.cancel() }
+}
+
+
+trait ReadyLoader {
+
+    type Error: fmt::Display;
+
+    type Future: Future<Item=Readiness,Error=Self::Error>;
+
+    fn load_ready(&self) -> Self::Future;
+}
+
+
+impl<T,F> ReadyLoader for T where T: Fn() -> F, F: IntoFuture<Item=Readiness>, F::Error: fmt::Display {
+
+    type Error = <F as IntoFuture>::Error;
+
+    type Future = <F as IntoFuture>::Future;
+
+    fn load_ready(&self) -> Self::Future {
+        (self)().into_future()
+    }
+}
+
+
+/// Only positive results are cached; a node which is not ready
+/// is re-checked on every poll.
+///
+struct ReadyCache<L,F> {
+    // Last time node was seen to be ready
+    last_ready: Option<Instant>,
+    max_age: Duration,
+    loader: L,
+    work: Option<F>,
+}
+
+
+impl<L,F> ReadyCache<L,F> {
+
+    pub fn new(loader: L) -> Self {
+        let max_age = Duration::from_secs(13);
+        let (last_ready,work) = Default::default();
+        Self { last_ready, max_age, loader, work }
+    }
+
+    pub fn cancel(&mut self) {
+        let _ = self.work.take();
+    }
+
+    fn is_fresh(&self) -> bool {
+        match self.last_ready {
+            Some(instant) => instant + self.max_age > Instant::now(),
+            None => false,
+        }
+    }
+}
+
+
+impl<L> ReadyCache<L,L::Future> where L: ReadyLoader {
+
+    pub fn poll_ready(&mut self) -> Poll<Readiness,L::Error> {
+        if self.is_fresh() {
+            return Ok(Async::Ready(Readiness::Ready));
+        }
+        let Self { work, loader, .. } = self;
+        let poll = work.get_or_insert_with(|| {
+            loader.load_ready()
+        }).poll();
+        let readiness = try_ready!(poll.map_err(|err| {
+            let _ = work.take();
+            warn!("Failed to check node readiness: {}",err);
+            err
+        }));
+        let _ = work.take();
+        if readiness.is_ready() {
+            self.last_ready = Some(Instant::now());
+        } else {
+            warn!("Node is not ready: {}",readiness);
+            self.last_ready = None;
+        }
+        Ok(Async::Ready(readiness))
+    }
+}
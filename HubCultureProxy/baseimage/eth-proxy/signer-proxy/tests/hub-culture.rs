@@ -0,0 +1,74 @@
+#[macro_use]
+extern crate serde_json;
+extern crate signer_proxy;
+extern crate signer;
+extern crate ethrpc;
+
+
+use signer_proxy::hub_culture::{functions,events};
+use signer_proxy::rpc::TxCall;
+use signer::functions::{Functions,Call};
+use ethrpc::contract::FunctionCall;
+use ethrpc::abi::Function;
+use ethrpc::types::{Log,Uint8};
+
+
+/// Functions as the signer would load them from the contract's abi.
+fn abi_functions() -> Functions {
+    let abi: Vec<serde_json::Value> = serde_json::from_str(include_str!("../contracts/HubCulture.abi")).unwrap();
+    abi.into_iter()
+        .filter(|item| item["type"] == "function")
+        .map(|item| serde_json::from_value::<Function>(item).unwrap())
+        .collect()
+}
+
+
+#[test]
+fn typed_calls_match_signer_encoding() {
+    let functions = abi_functions();
+    let account = "0x00000000000000000000000000000000deadbeef".parse().unwrap();
+    let deposit = functions::Deposit { account, value: "0x3e8".parse().unwrap() };
+    let call = Call::from_typed(&deposit);
+    assert_eq!(call.name,"deposit");
+    assert_eq!(call.inputs.len(),2);
+    assert_eq!(functions.try_encode(call).unwrap(),deposit.encode());
+    let selector = functions::Deposit::function().selector();
+    assert_eq!(&deposit.encode()[..4],&selector[..]);
+
+    let register = functions::Register {
+        uuid: "0x00000000000000000000000000000000000000000000000000000000000000aa".parse().unwrap(),
+        v: Uint8::from(27u8),
+        r: "0x00000000000000000000000000000000000000000000000000000000000000bb".parse().unwrap(),
+        s: "0x00000000000000000000000000000000000000000000000000000000000000cc".parse().unwrap(),
+    };
+    assert_eq!(functions.try_encode(Call::from_typed(&register)).unwrap(),register.encode());
+
+    let contract = "0x00a329c0648769a73afac7f9381e08fb43dbea72".parse().unwrap();
+    let tx = TxCall::typed(contract,&functions::Unpause { });
+    assert_eq!(tx.to,Some(contract));
+    assert_eq!(functions.try_encode(tx.call).unwrap(),functions::Unpause { }.encode());
+}
+
+
+#[test]
+fn typed_events_decode_logs() {
+    let mut log: Log = serde_json::from_value(json!({
+        "address": "0x00a329c0648769a73afac7f9381e08fb43dbea72",
+        "topics": [
+            "0x0000000000000000000000000000000000000000000000000000000000000000",
+            "0x00000000000000000000000000000000000000000000000000000000deadbeef",
+            "0x00000000000000000000000000000000000000000000000000000000000003e8",
+            "0x0000000000000000000000000000000000000000000000000000000000000007",
+        ],
+        "data": "0x",
+    })).unwrap();
+    assert_eq!(events::Deposit::from_log(&log),None);
+    log.topics[0] = events::Deposit::event().signature();
+    let expected = events::Deposit {
+        account: "0x00000000000000000000000000000000deadbeef".parse().unwrap(),
+        value: "0x3e8".parse().unwrap(),
+        nonce: "0x7".parse().unwrap(),
+    };
+    assert_eq!(events::Deposit::from_log(&log),Some(expected));
+    assert_eq!(events::Withdraw::from_log(&log),None);
+}
@@ -0,0 +1,93 @@
+extern crate signer_proxy;
+extern crate signer;
+extern crate proxy;
+extern crate hyper;
+extern crate tokio;
+
+use signer_proxy::base::{self,BaseSigner};
+use signer::rpc::KeyId;
+use signer::crypto::Address;
+use proxy::auth::{Credentials,Scheme};
+use proxy::http;
+use hyper::{Body,Request,Response,Server};
+use hyper::service::service_fn;
+use tokio::prelude::*;
+use tokio::runtime::Runtime;
+use tokio::timer::Delay;
+use std::time::{Duration,Instant};
+
+
+const ADDRESS: &str = "0x00000000000000000000000000000000000a11ce";
+
+const OK: &str = r#"{"Ok":"0x00000000000000000000000000000000000a11ce"}"#;
+
+const API_KEY: &str = "open-sesame";
+
+
+/// Request the signer's address from a local remote signer which answers with
+/// `status` & `body` after `delay_ms` (or with status 401, unless the request
+/// carries the expected api key), allowing it `timeout_ms` to respond.
+fn get_address(credentials: Option<Credentials>, status: u16, body: &'static str, delay_ms: u64, timeout_ms: u64) -> Result<Address,String> {
+    let mut runtime = Runtime::new().unwrap();
+    let server = Server::bind(&([127,0,0,1],0).into()).serve(move || {
+        service_fn(move |req: Request<Body>| {
+            let authorized = req.headers().get("X-Auth-Key").map(|key| key == API_KEY).unwrap_or(false);
+            let (status,body) = if authorized { (status,body) } else { (401,r#"{"Err":"missing credentials"}"#) };
+            let delay = Delay::new(Instant::now() + Duration::from_millis(delay_ms));
+            delay.map(move |()| Response::builder().status(status).body(Body::from(body)).unwrap())
+        })
+    });
+    let uri = format!("http://{}/",server.local_addr()).parse().unwrap();
+    runtime.spawn(server.map_err(|err| eprintln!("remote signer failed: {}",err)));
+    let key = Some(KeyId::Name("vault".into()));
+    let signer = base::configure_remote(uri,key,credentials,http::client().unwrap(),Duration::from_millis(timeout_ms));
+    runtime.block_on(signer.api().get_address()).map_err(|err| err.to_string())
+}
+
+
+fn credentials() -> Option<Credentials> {
+    Some(Credentials::new("proxy",Scheme::ApiKey,API_KEY))
+}
+
+
+#[test]
+fn success() {
+    let address = get_address(credentials(),200,OK,0,1000).unwrap();
+    assert_eq!(address,ADDRESS.parse().unwrap());
+}
+
+
+#[test]
+fn refused_by_signer() {
+    let err = get_address(credentials(),200,r#"{"Err":"unknown signer key `vault`"}"#,0,1000).unwrap_err();
+    assert_eq!(err,"unknown signer key `vault`");
+}
+
+
+#[test]
+fn error_status() {
+    // failed authentication is reported as such, rather than as an undecodable response
+    let err = get_address(None,200,OK,0,1000).unwrap_err();
+    assert_eq!(err,"remote signer rejected request (status `401 Unauthorized`): missing credentials");
+    let err = get_address(credentials(),403,r#"{"Err":"client `proxy` may not call `get-address`"}"#,0,1000).unwrap_err();
+    assert_eq!(err,"remote signer rejected request (status `403 Forbidden`): client `proxy` may not call `get-address`");
+    // error statuses are never treated as success, even if the body would parse
+    let err = get_address(credentials(),500,OK,0,1000).unwrap_err();
+    assert!(err.starts_with("remote signer rejected request (status `500 Internal Server Error`)"),"{}",err);
+    let err = get_address(credentials(),502,"bad gateway",0,1000).unwrap_err();
+    assert_eq!(err,"remote signer rejected request (status `502 Bad Gateway`): bad gateway");
+}
+
+
+#[test]
+fn malformed_response() {
+    let err = get_address(credentials(),200,"not json",0,1000).unwrap_err();
+    assert!(err.starts_with("invalid response from remote signer (status `200 OK`)"),"{}",err);
+}
+
+
+#[test]
+fn timeout() {
+    let err = get_address(credentials(),200,OK,2000,100).unwrap_err();
+    assert!(err.starts_with("remote signer did not respond within"),"{}",err);
+}
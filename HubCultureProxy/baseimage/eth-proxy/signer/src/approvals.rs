@@ -0,0 +1,659 @@
+//! M-of-N approval of high-value signing requests.
+//!
+//! Transactions which exceed a configured threshold are not signed immediately.
+//! Instead, the signer holds the request as pending (identified by its canonical
+//! hash), and signs it only when it is re-submitted after `quorum` distinct
+//! approvers have submitted an EIP-191 (`personal_sign`) signature over that hash:
+//!
+//! ```toml
+//! [approval]
+//! approvers = [
+//!     "0x00000000deadbeefdeadbeefdeadbeefdeadbeef",
+//!     "0x00000000feedfacefeedfacefeedfacefeedface",
+//!     "0x00000000cafebabecafebabecafebabecafebabe",
+//! ]
+//! quorum = 2
+//! value-above = "1000000000000000000"
+//! max-fee = "10000000000000000"
+//!
+//! [approval.inputs-above]
+//! transfer = { value = "1000000" }
+//! ```
+//!
+//! The hash covers everything but the nonce of the transaction, which `signer-proxy`
+//! assigns afresh to each submission.  If `max-fee` is set, the gas price & gas
+//! limit are not covered either (so that `signer-proxy` may re-price re-submitted
+//! requests), and requests whose fee (gas price times gas limit) exceeds it are
+//! refused instead.
+//!
+//! Pending requests & approvals are held in memory only, and are discarded
+//! once signed, once expired (after `ttl` seconds), or on restart.
+//!
+use ethrpc::abi::{Value,Token};
+use ethrpc::util::bufmath;
+use functions::Functions;
+use crypto::{self,Address,Signature};
+use types::{H256,U256};
+use util::{self,unix_time};
+use rpc::Request;
+use serde_json;
+use std::collections::{HashMap,HashSet};
+use std::sync::{Arc,Mutex};
+use std::{fmt,error};
+
+
+/// Maximum number of requests awaiting approval for any one key.
+///
+pub const MAX_PENDING: usize = 64;
+
+
+fn default_ttl() -> u64 { 60 * 60 }
+
+
+/// Approvers, quorum & thresholds above which requests require approval.
+///
+/// If no thresholds are specified, all transactions require approval.  Only the
+/// tx value of raw transactions is inspected (their calldata is not decoded).
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApprovalConfig {
+    pub approvers: HashSet<Address>,
+    pub quorum: usize,
+    /// Seconds for which a pending request may collect approvals
+    #[serde(default = "default_ttl")]
+    pub ttl: u64,
+    /// Transaction value (in wei) above which approval is required
+    #[serde(default,rename = "value-above",skip_serializing_if = "Option::is_none")]
+    pub value_above: Option<Value>,
+    /// Uint inputs (by function, then by input name) above which approval is required
+    #[serde(default,rename = "inputs-above",skip_serializing_if = "HashMap::is_empty")]
+    pub inputs_above: HashMap<String,HashMap<String,Value>>,
+    /// Maximum fee (gas price times gas limit, in wei) of transactions requiring approval
+    #[serde(default,rename = "max-fee",skip_serializing_if = "Option::is_none")]
+    pub max_fee: Option<Value>,
+}
+
+
+impl ApprovalConfig {
+
+    /// Check that the quorum is attainable, and that all thresholds are uints
+    /// referring to uint inputs of configured functions.
+    ///
+    pub fn validate(&self, functions: &Functions) -> Result<(),Error> {
+        if self.quorum == 0 || self.quorum > self.approvers.len() {
+            let msg = format!("quorum of {} not attainable with {} approvers",self.quorum,self.approvers.len());
+            return Err(Error::InvalidConfig(msg));
+        }
+        if let Some(value) = self.value_above.as_ref() {
+            if value.try_cast(Token::Uint).is_err() {
+                return Err(Error::InvalidConfig("`value-above` must be a uint".into()));
+            }
+        }
+        if let Some(value) = self.max_fee.as_ref() {
+            if value.try_cast(Token::Uint).is_err() {
+                return Err(Error::InvalidConfig("`max-fee` must be a uint".into()));
+            }
+        }
+        for (name,inputs) in self.inputs_above.iter() {
+            let function = functions.get(name).ok_or_else(|| {
+                Error::InvalidConfig(format!("`inputs-above` refers to unknown function `{}`",name))
+            })?;
+            for (input,value) in inputs.iter() {
+                let kind = function.iter_inputs().find(|(other,_)| *other == input.as_str())
+                    .map(|(_,kind)| kind);
+                let reason = match kind {
+                    Some(Token::Uint) | Some(Token::Uint8) if value.try_cast(Token::Uint).is_ok() => continue,
+                    Some(Token::Uint) | Some(Token::Uint8) => "threshold must be a uint",
+                    Some(_) => "threshold requires a uint input",
+                    None => "no such input",
+                };
+                let msg = format!("invalid threshold for `{}` of `{}`: {}",input,name,reason);
+                return Err(Error::InvalidConfig(msg));
+            }
+        }
+        Ok(())
+    }
+
+    /// Canonical hash of `request` under this config (see `request_hash`).
+    ///
+    pub fn request_hash(&self, key: Address, request: &Request) -> H256 {
+        request_hash(key,request,self.max_fee.is_some())
+    }
+
+    /// Check that the fee of `request` does not exceed `max-fee` (if set).
+    ///
+    pub fn check_fee(&self, request: &Request) -> Result<(),Error> {
+        let (gas_price,gas_limit) = match request {
+            Request::SignTxCall(tx_call) => (tx_call.gas_price,tx_call.gas_limit),
+            Request::SignRawTx(tx) => (tx.gas_price,tx.gas_limit),
+            _other => return Ok(()),
+        };
+        if let Some(max_fee) = self.max_fee.as_ref().and_then(as_uint) {
+            let mut fee = gas_price;
+            let overflow = bufmath::mul(&mut fee,&gas_limit);
+            if overflow || fee > max_fee {
+                return Err(Error::FeeTooHigh(max_fee));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check if `request` exceeds any threshold.
+    ///
+    pub fn requires_approval(&self, request: &Request) -> bool {
+        let unconditional = self.value_above.is_none() && self.inputs_above.is_empty();
+        match request {
+            Request::SignTxCall(tx_call) => {
+                let inputs_above = self.inputs_above.get(&tx_call.call.name).map(|thresholds| {
+                    thresholds.iter().any(|(input,threshold)| {
+                        let value = tx_call.call.inputs.get(input).and_then(as_uint);
+                        exceeds(value,threshold)
+                    })
+                });
+                unconditional
+                    || exceeds(Some(tx_call.value),self.value_above.as_ref())
+                    || inputs_above.unwrap_or(false)
+            },
+            Request::SignRawTx(tx) => {
+                unconditional || exceeds(Some(tx.value),self.value_above.as_ref())
+            },
+            _other => false,
+        }
+    }
+}
+
+
+fn as_uint(value: &Value) -> Option<U256> {
+    value.try_cast(Token::Uint).ok().and_then(|value| value.as_uint())
+}
+
+
+fn exceeds<'a>(value: Option<U256>, threshold: impl Into<Option<&'a Value>>) -> bool {
+    match (value,threshold.into().and_then(as_uint)) {
+        (Some(value),Some(threshold)) => value > threshold,
+        _ => false,
+    }
+}
+
+
+/// Canonical hash of a request to be signed by `key`.
+///
+/// This is the keccak-256 of the json encoding of `{"key":...,"request":...}`
+/// (with object keys sorted), omitting the nonce of transactions, and also their
+/// gas price & gas limit if `fee_capped` is set.
+///
+pub fn request_hash(key: Address, request: &Request, fee_capped: bool) -> H256 {
+    #[derive(Serialize)]
+    struct Canonical<'a> {
+        key: &'a Address,
+        request: serde_json::Value,
+    }
+    let tag = match request {
+        Request::SignRawTx(_) => Some("sign-raw-tx"),
+        Request::SignTxCall(_) => Some("sign-tx-call"),
+        _other => None,
+    };
+    let mut request = serde_json::to_value(request).expect("request must be serializable");
+    let tx = tag.and_then(|tag| request.get_mut(tag)).and_then(|tx| tx.as_object_mut());
+    if let Some(fields) = tx {
+        fields.remove("nonce");
+        if fee_capped {
+            fields.remove("gasPrice");
+            fields.remove("gas");
+        }
+    }
+    let encoded = serde_json::to_vec(&Canonical { key: &key, request }).expect("request must be serializable");
+    H256::from(crypto::keccak(encoded.as_slice()))
+}
+
+
+/// Message signed by approvers (the EIP-191 `personal_sign` hash of `hash`).
+///
+pub fn approval_message(hash: &H256) -> [u8;32] {
+    let mut buf = Vec::with_capacity(60);
+    buf.extend_from_slice(b"\x19Ethereum Signed Message:\n32");
+    buf.extend_from_slice(hash);
+    crypto::keccak(buf.as_slice())
+}
+
+
+/// An approver's signature over the hash of a pending request.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct Approval {
+    pub hash: H256,
+    pub sig: Signature,
+}
+
+
+/// A request awaiting approval.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct PendingApproval {
+    /// Canonical hash of the request
+    pub pending: H256,
+    /// Address of the signing key
+    pub key: Address,
+    /// The request as received
+    pub request: serde_json::Value,
+    /// Approvers who have signed off so far
+    pub approvals: Vec<Address>,
+    /// Number of approvals required
+    pub quorum: usize,
+    /// Unix timestamp after which the request is discarded
+    pub expires: u64,
+}
+
+
+impl PendingApproval {
+
+    pub fn is_approved(&self) -> bool { self.approvals.len() >= self.quorum }
+}
+
+
+/// Approval status of a signing request.
+///
+#[derive(Debug,Clone)]
+pub enum Status {
+    /// Request does not exceed any threshold
+    NotRequired,
+    /// Request has reached quorum (and has been discarded, so may be signed once)
+    Approved(Approved),
+    /// Request is awaiting approval
+    Pending(PendingApproval),
+}
+
+
+/// A request which has reached quorum, and has been removed from the pending set.
+///
+/// If the request is not signed after all, `Approvals::restore` returns it (and
+/// its approvals) to the pending set.
+///
+#[derive(Debug,Clone)]
+pub struct Approved {
+    entry: Entry,
+}
+
+
+impl Approved {
+
+    /// Canonical hash of the request
+    pub fn hash(&self) -> H256 { self.entry.status.pending }
+}
+
+
+/// Requests awaiting approval, shared by all clones.
+///
+#[derive(Default,Debug,Clone)]
+pub struct Approvals {
+    inner: Arc<Mutex<HashMap<H256,Entry>>>,
+}
+
+
+impl Approvals {
+
+    pub fn new() -> Self { Default::default() }
+
+    /// Check the approval status of `request`, holding it as pending if it
+    /// requires approval which it has not yet received.
+    ///
+    /// Approved requests are discarded by the same check which reports them,
+    /// so each approval permits exactly one signature (unless restored).
+    ///
+    pub fn check(&self, key: Address, config: &ApprovalConfig, request: &Request) -> Result<Status,Error> {
+        if !config.requires_approval(request) {
+            return Ok(Status::NotRequired);
+        }
+        config.check_fee(request)?;
+        let hash = config.request_hash(key,request);
+        let now = unix_time();
+        let mut inner = self.lock();
+        inner.retain(|_,entry| entry.status.expires > now);
+        if !inner.contains_key(&hash) {
+            let held = inner.values().filter(|entry| entry.status.key == key).count();
+            if held >= MAX_PENDING {
+                return Err(Error::TooManyPending);
+            }
+            let request = serde_json::to_value(request).expect("request must be serializable");
+            let status = PendingApproval {
+                pending: hash,
+                key,
+                request,
+                approvals: Vec::new(),
+                quorum: config.quorum,
+                expires: now + config.ttl,
+            };
+            info!("{} holding request {} for approval",key,hash);
+            inner.insert(hash,Entry { approvers: config.approvers.clone(), status });
+        }
+        if inner[&hash].status.is_approved() {
+            let entry = inner.remove(&hash).expect("entry must exist");
+            Ok(Status::Approved(Approved { entry }))
+        } else {
+            Ok(Status::Pending(inner[&hash].status.clone()))
+        }
+    }
+
+    /// Return an approved request to the pending set, e.g. because some later
+    /// check prevented it from being signed.
+    ///
+    pub fn restore(&self, approved: Approved) {
+        let Approved { entry } = approved;
+        info!("{} restoring unsigned request {}",entry.status.key,entry.status.pending);
+        self.lock().entry(entry.status.pending).or_insert(entry);
+    }
+
+    /// Record an approver's signature over a pending request, returning the
+    /// recovered approver & the updated request.
+    ///
+    pub fn approve(&self, approval: Approval) -> Result<(Address,PendingApproval),Error> {
+        let Approval { hash, sig } = approval;
+        let now = unix_time();
+        let mut inner = self.lock();
+        inner.retain(|_,entry| entry.status.expires > now);
+        let entry = inner.get_mut(&hash).ok_or(Error::UnknownRequest(hash))?;
+        let approver = crypto::ecrecover(&approval_message(&hash),&sig)?;
+        if !entry.approvers.contains(&approver) {
+            warn!("Rejecting approval of {} by non-approver {}",hash,approver);
+            return Err(Error::NotApprover(approver));
+        }
+        if !entry.status.approvals.contains(&approver) {
+            info!("{} approved request {}",approver,hash);
+            entry.status.approvals.push(approver);
+        }
+        Ok((approver,entry.status.clone()))
+    }
+
+    /// All unexpired pending requests (optionally only those for `key`).
+    ///
+    pub fn pending(&self, key: Option<Address>) -> Vec<PendingApproval> {
+        let now = unix_time();
+        let mut inner = self.lock();
+        inner.retain(|_,entry| entry.status.expires > now);
+        let mut pending: Vec<_> = inner.values()
+            .filter(|entry| key.map(|key| key == entry.status.key).unwrap_or(true))
+            .map(|entry| entry.status.clone())
+            .collect();
+        pending.sort_by_key(|status| status.expires);
+        pending
+    }
+
+    fn lock(&self) -> ::std::sync::MutexGuard<HashMap<H256,Entry>> {
+        util::lock(&self.inner)
+    }
+}
+
+
+#[derive(Debug,Clone)]
+struct Entry {
+    approvers: HashSet<Address>,
+    status: PendingApproval,
+}
+
+
+#[derive(Debug)]
+pub enum Error {
+    /// No pending request with the given hash
+    UnknownRequest(H256),
+    /// Signature recovered to an address which is not a configured approver
+    NotApprover(Address),
+    BadSignature(crypto::Error),
+    /// Transaction fee exceeds the configured maximum
+    FeeTooHigh(U256),
+    TooManyPending,
+    InvalidConfig(String),
+}
+
+
+impl From<crypto::Error> for Error {
+
+    fn from(err: crypto::Error) -> Self { Error::BadSignature(err) }
+}
+
+
+impl fmt::Display for Error {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownRequest(hash) => write!(f,"No pending request with hash {}",hash),
+            Error::NotApprover(addr) => write!(f,"{} is not a configured approver",addr),
+            Error::BadSignature(err) => write!(f,"Invalid approval signature: {}",err),
+            Error::FeeTooHigh(max) => write!(f,"Transaction fee exceeds `max-fee` of {} wei",max),
+            Error::TooManyPending => write!(f,"Too many requests awaiting approval for this key (max {})",MAX_PENDING),
+            Error::InvalidConfig(msg) => write!(f,"Invalid approval config: {}",msg),
+        }
+    }
+}
+
+
+impl error::Error for Error {
+
+    fn description(&self) -> &str {
+        match self {
+            Error::UnknownRequest(_) => "no such pending request",
+            Error::NotApprover(_) => "signer is not a configured approver",
+            Error::BadSignature(_) => "invalid approval signature",
+            Error::FeeTooHigh(_) => "transaction fee too high",
+            Error::TooManyPending => "too many requests awaiting approval",
+            Error::InvalidConfig(_) => "invalid approval config",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match self {
+            Error::BadSignature(err) => Some(err),
+            _other => None,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use approvals::{self,Approvals,ApprovalConfig,Approval,Status,Error,MAX_PENDING};
+    use ethrpc::abi::Value;
+    use crypto::{self,Signer};
+    use rpc::{self,Request};
+    use types::{H256,U256};
+
+    const KEY: &str = "0x00000000000000000000000000000000000a11ce";
+
+
+    fn raw_tx(nonce: u32, value: u32) -> Request {
+        priced_tx(nonce,value,1000,21000)
+    }
+
+
+    fn priced_tx(nonce: u32, value: u32, gas_price: u32, gas_limit: u32) -> Request {
+        Request::SignRawTx(rpc::Transaction {
+            nonce: nonce.into(),
+            gas_price: gas_price.into(),
+            gas_limit: gas_limit.into(),
+            to: Some(KEY.parse().unwrap()),
+            value: value.into(),
+            data: Default::default(),
+        })
+    }
+
+
+    fn config(approvers: &[&Signer], quorum: usize) -> ApprovalConfig {
+        ApprovalConfig {
+            approvers: approvers.iter().map(|signer| signer.address()).collect(),
+            quorum,
+            ttl: 60,
+            value_above: None,
+            inputs_above: Default::default(),
+            max_fee: None,
+        }
+    }
+
+
+    fn approve(signer: &Signer, hash: H256) -> Approval {
+        Approval { hash, sig: signer.sign(&approvals::approval_message(&hash)) }
+    }
+
+
+    fn approved_hash(status: Status) -> H256 {
+        match status {
+            Status::Approved(approved) => approved.hash(),
+            other => panic!("expected approved request, got {:?}",other),
+        }
+    }
+
+
+    fn pending_hash(status: Status) -> H256 {
+        match status {
+            Status::Pending(pending) => pending.pending,
+            other => panic!("expected pending request, got {:?}",other),
+        }
+    }
+
+
+    #[test]
+    fn recover_approvers() {
+        let approver = Signer::new(crypto::keygen()).unwrap();
+        let approvals = Approvals::new();
+        let config = config(&[&approver],1);
+        // collect requests until both signature recovery ids have been exercised
+        let mut seen = Vec::new();
+        let mut value = 0u32;
+        while seen.len() < 2 {
+            let hash = pending_hash(approvals.check(KEY.parse().unwrap(),&config,&raw_tx(0,value)).unwrap());
+            let approval = approve(&approver,hash);
+            let v = approval.sig.get_v();
+            assert!(v == 27 || v == 28,"unexpected recovery id {}",v);
+            let (recovered,pending) = approvals.approve(approval).unwrap();
+            assert_eq!(recovered,approver.address());
+            assert!(pending.is_approved());
+            if !seen.contains(&v) {
+                seen.push(v);
+            }
+            value += 1;
+        }
+    }
+
+
+    #[test]
+    fn reject_non_approvers() {
+        let approver = Signer::new(crypto::keygen()).unwrap();
+        let other = Signer::new(crypto::keygen()).unwrap();
+        let approvals = Approvals::new();
+        let config = config(&[&approver],1);
+        let hash = pending_hash(approvals.check(KEY.parse().unwrap(),&config,&raw_tx(0,1)).unwrap());
+        match approvals.approve(approve(&other,hash)) {
+            Err(Error::NotApprover(addr)) => assert_eq!(addr,other.address()),
+            rslt => panic!("expected non-approver rejection, got {:?}",rslt),
+        }
+        // a signature over some other hash recovers to some other address
+        let mut approval = approve(&approver,H256::from([1u8;32]));
+        approval.hash = hash;
+        assert!(approvals.approve(approval).is_err());
+        // approvals of unknown requests are rejected outright
+        match approvals.approve(approve(&approver,H256::from([1u8;32]))) {
+            Err(Error::UnknownRequest(unknown)) => assert_eq!(unknown,H256::from([1u8;32])),
+            rslt => panic!("expected unknown request, got {:?}",rslt),
+        }
+        let pending = approvals.pending(None);
+        assert_eq!(pending.len(),1);
+        assert!(pending[0].approvals.is_empty());
+    }
+
+
+    #[test]
+    fn approve_once() {
+        let signers: Vec<_> = (0..3).map(|_| Signer::new(crypto::keygen()).unwrap()).collect();
+        let approvals = Approvals::new();
+        let config = config(&[&signers[0],&signers[1],&signers[2]],2);
+        let key = KEY.parse().unwrap();
+        let hash = pending_hash(approvals.check(key,&config,&raw_tx(0,1)).unwrap());
+        // resubmission with a different nonce is the same request
+        let resubmitted = raw_tx(7,1);
+        assert_eq!(config.request_hash(key,&resubmitted),hash);
+        assert_ne!(config.request_hash(key,&raw_tx(0,2)),hash);
+        // duplicate approvals do not count towards quorum
+        approvals.approve(approve(&signers[0],hash)).unwrap();
+        let (_,pending) = approvals.approve(approve(&signers[0],hash)).unwrap();
+        assert!(!pending.is_approved());
+        assert_eq!(pending_hash(approvals.check(key,&config,&resubmitted).unwrap()),hash);
+        approvals.approve(approve(&signers[1],hash)).unwrap();
+        assert_eq!(approved_hash(approvals.check(key,&config,&resubmitted).unwrap()),hash);
+        // the approval is consumed by the check which reports it
+        assert!(approvals.pending(None).is_empty());
+        let status = approvals.check(key,&config,&resubmitted).unwrap();
+        match status {
+            Status::Pending(ref pending) => assert!(pending.approvals.is_empty()),
+            ref other => panic!("expected fresh pending request, got {:?}",other),
+        }
+    }
+
+
+    #[test]
+    fn restore_approval() {
+        let approver = Signer::new(crypto::keygen()).unwrap();
+        let approvals = Approvals::new();
+        let config = config(&[&approver],1);
+        let key = KEY.parse().unwrap();
+        let hash = pending_hash(approvals.check(key,&config,&raw_tx(0,1)).unwrap());
+        approvals.approve(approve(&approver,hash)).unwrap();
+        let approved = match approvals.check(key,&config,&raw_tx(0,1)).unwrap() {
+            Status::Approved(approved) => approved,
+            other => panic!("expected approved request, got {:?}",other),
+        };
+        assert!(approvals.pending(None).is_empty());
+        // a request which was not signed after all keeps its approvals
+        approvals.restore(approved);
+        assert_eq!(approvals.pending(None)[0].approvals,vec![approver.address()]);
+        assert_eq!(approved_hash(approvals.check(key,&config,&raw_tx(1,1)).unwrap()),hash);
+    }
+
+
+    #[test]
+    fn fee_cap() {
+        let approver = Signer::new(crypto::keygen()).unwrap();
+        let key = KEY.parse().unwrap();
+        // without a cap, gas price & limit are approved along with the request
+        let uncapped = config(&[&approver],1);
+        let hash = uncapped.request_hash(key,&priced_tx(0,1,1000,21000));
+        assert_eq!(uncapped.request_hash(key,&priced_tx(3,1,1000,21000)),hash);
+        assert_ne!(uncapped.request_hash(key,&priced_tx(0,1,2000,21000)),hash);
+        assert_ne!(uncapped.request_hash(key,&priced_tx(0,1,1000,42000)),hash);
+        // with a cap, they may vary up to the cap
+        let mut capped = config(&[&approver],1);
+        capped.max_fee = Some(Value::Uint(U256::from(42_000_000u64)));
+        let hash = capped.request_hash(key,&priced_tx(0,1,1000,21000));
+        assert_eq!(capped.request_hash(key,&priced_tx(0,1,2000,21000)),hash);
+        let approvals = Approvals::new();
+        assert_eq!(pending_hash(approvals.check(key,&capped,&priced_tx(0,1,1000,21000)).unwrap()),hash);
+        approvals.approve(approve(&approver,hash)).unwrap();
+        for &(gas_price,gas_limit) in [(2001,21000),(1000,42001),(u32::max_value(),u32::max_value())].iter() {
+            match approvals.check(key,&capped,&priced_tx(0,1,gas_price,gas_limit)) {
+                Err(Error::FeeTooHigh(_)) => { },
+                other => panic!("expected excessive fee, got {:?}",other),
+            }
+        }
+        // refused requests leave the approval in place
+        assert_eq!(approved_hash(approvals.check(key,&capped,&priced_tx(1,1,2000,21000)).unwrap()),hash);
+    }
+
+
+    #[test]
+    fn pending_per_key() {
+        let approver = Signer::new(crypto::keygen()).unwrap();
+        let approvals = Approvals::new();
+        let config = config(&[&approver],1);
+        let key = KEY.parse().unwrap();
+        let other = "0x00000000000000000000000000000000000b0b00".parse().unwrap();
+        for value in 0..MAX_PENDING {
+            approvals.check(key,&config,&raw_tx(0,value as u32)).unwrap();
+        }
+        match approvals.check(key,&config,&raw_tx(0,MAX_PENDING as u32)) {
+            Err(Error::TooManyPending) => { },
+            other => panic!("expected too many pending, got {:?}",other),
+        }
+        // requests already held, & requests for other keys, are unaffected
+        assert!(approvals.check(key,&config,&raw_tx(0,0)).is_ok());
+        assert!(approvals.check(other,&config,&raw_tx(0,0)).is_ok());
+        assert_eq!(approvals.pending(Some(key)).len(),MAX_PENDING);
+    }
+}
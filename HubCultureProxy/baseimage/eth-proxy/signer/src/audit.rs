@@ -0,0 +1,387 @@
+//! Tamper-evident audit log of signing operations.
+//!
+//! The log is a file of newline-delimited json records, one per signing
+//! operation (or accepted approval of a pending request).  Each record carries
+//! the hash of its predecessor, and its own hash (the keccak-256 of the record's
+//! json encoding, minus the `hash` field), so that any modification, removal or
+//! reordering of records breaks the chain.
+//!
+//! Removing records from the *end* of the log leaves a valid (shorter) chain, and
+//! so cannot be detected from the log alone.  Detecting truncation requires that
+//! the head (as printed by `eth-proxy verify-audit-log`) be stored elsewhere, and
+//! compared against the count & hash of a later verification.
+//!
+use crypto::{self,Address,Signature};
+use types::{Bytes,H256};
+use util::unix_time;
+use rpc::Request;
+use serde_json::{self,Value};
+use std::io::{BufRead,BufReader,Write};
+use std::fs::{File,OpenOptions};
+use std::net::SocketAddr;
+use std::path::{Path,PathBuf};
+use std::sync::{Arc,Mutex};
+use std::{fmt,error,io};
+
+
+/// Outcome of a signing (or approval) operation.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Outcome {
+    /// Hash of a signed transaction
+    TxHash(H256),
+    /// Signature over an ethtoken
+    Sig(Signature),
+    /// Address of the approver who signed off on a pending request
+    Approval(Address),
+}
+
+
+impl Outcome {
+
+    /// Outcome of signing the rlp-encoded transaction `signed`.
+    ///
+    pub fn tx(signed: &Bytes) -> Self {
+        Outcome::TxHash(H256::from(crypto::keccak(&signed[..])))
+    }
+}
+
+
+/// A single entry in the audit log.
+///
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct Record {
+    /// Position in the log (starting from zero)
+    pub seq: u64,
+    /// Unix timestamp of the operation
+    pub time: u64,
+    /// Address of the signing key
+    pub key: Address,
+    /// Address of the requesting client (if known)
+    pub peer: Option<SocketAddr>,
+    /// The request as received
+    pub request: Value,
+    /// Resulting tx hash, signature or approver
+    pub outcome: Outcome,
+    /// Hash of the previous record (zero for the first record)
+    pub prev: H256,
+    /// Hash of this record
+    pub hash: H256,
+}
+
+
+/// The hashed portion of a record.
+///
+#[derive(Serialize)]
+struct Unhashed<'a> {
+    seq: u64,
+    time: u64,
+    key: &'a Address,
+    peer: &'a Option<SocketAddr>,
+    request: &'a Value,
+    outcome: &'a Outcome,
+    prev: &'a H256,
+}
+
+
+impl Record {
+
+    /// Compute the expected hash of this record.
+    ///
+    pub fn compute_hash(&self) -> H256 {
+        let unhashed = Unhashed {
+            seq: self.seq,
+            time: self.time,
+            key: &self.key,
+            peer: &self.peer,
+            request: &self.request,
+            outcome: &self.outcome,
+            prev: &self.prev,
+        };
+        let encoded = serde_json::to_vec(&unhashed).expect("record must be serializable");
+        H256::from(crypto::keccak(encoded.as_slice()))
+    }
+}
+
+
+/// Position of the most recent record in a log.
+///
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub struct Head {
+    /// Number of records in the log
+    pub count: u64,
+    /// Hash of the last record (zero if the log is empty)
+    pub hash: H256,
+}
+
+
+impl Default for Head {
+
+    fn default() -> Self { Head { count: 0, hash: H256::from([0u8;32]) } }
+}
+
+
+/// Verify the integrity of the audit log at `path`.
+///
+pub fn verify(path: impl AsRef<Path>) -> Result<Head,Error> {
+    let file = File::open(path)?;
+    let mut head = Head::default();
+    for (index,line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        let record: Record = serde_json::from_str(&line).map_err(|err| {
+            Error::Broken { line: index + 1, reason: format!("malformed record ({})",err) }
+        })?;
+        let reason = if record.seq != head.count {
+            Some(format!("expected seq {}, got {}",head.count,record.seq))
+        } else if record.prev != head.hash {
+            Some("previous hash does not match".to_owned())
+        } else if record.compute_hash() != record.hash {
+            Some("record hash does not match contents".to_owned())
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Err(Error::Broken { line: index + 1, reason });
+        }
+        head = Head { count: head.count + 1, hash: record.hash };
+    }
+    Ok(head)
+}
+
+
+/// Handle to an (optional) audit log, shared by all clones.
+///
+#[derive(Debug,Clone)]
+pub struct AuditLog {
+    inner: Option<Arc<Mutex<Writer>>>,
+}
+
+
+impl AuditLog {
+
+    /// An audit log which records nothing.
+    ///
+    pub fn disabled() -> Self { Self { inner: None } }
+
+    /// Open the audit log at `path` for appending, verifying any existing records.
+    ///
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self,Error> {
+        let path = path.into();
+        let head = if path.is_file() { verify(&path)? } else { Head::default() };
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        info!("Appending to audit log {} ({} existing records)",path.display(),head.count);
+        let writer = Writer { file, head, failed: false };
+        Ok(Self { inner: Some(Arc::new(Mutex::new(writer))) })
+    }
+
+    pub fn is_enabled(&self) -> bool { self.inner.is_some() }
+
+    /// Append a record of a signing operation.
+    ///
+    /// The operation must not be considered complete (i.e. its result must not
+    /// be released) unless this succeeds.
+    ///
+    pub fn record(&self, key: Address, peer: Option<SocketAddr>, request: &Request, outcome: Outcome) -> Result<(),Error> {
+        if let Some(inner) = self.inner.as_ref() {
+            let request = serde_json::to_value(request)?;
+            let mut writer = inner.lock().map_err(|_| Error::Unavailable)?;
+            if writer.failed {
+                return Err(Error::Unavailable);
+            }
+            let mut record = Record {
+                seq: writer.head.count,
+                time: unix_time(),
+                key,
+                peer,
+                request,
+                outcome,
+                prev: writer.head.hash,
+                hash: H256::from([0u8;32]),
+            };
+            record.hash = record.compute_hash();
+            let mut line = serde_json::to_vec(&record)?;
+            line.push(b'\n');
+            let written = writer.file.write_all(&line).and_then(|()| writer.file.sync_data());
+            if let Err(err) = written {
+                // a partial record may have been written; refuse to extend the chain further
+                error!("Failed to append to audit log; refusing further signing: {}",err);
+                writer.failed = true;
+                return Err(err.into());
+            }
+            writer.head = Head { count: record.seq + 1, hash: record.hash };
+        }
+        Ok(())
+    }
+}
+
+
+#[derive(Debug)]
+struct Writer {
+    file: File,
+    head: Head,
+    failed: bool,
+}
+
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// Chain integrity check failed at the given line
+    Broken {
+        line: usize,
+        reason: String,
+    },
+    /// A previous write failed; the log may be incomplete
+    Unavailable,
+}
+
+
+impl From<io::Error> for Error {
+
+    fn from(err: io::Error) -> Self { Error::Io(err) }
+}
+
+impl From<serde_json::Error> for Error {
+
+    fn from(err: serde_json::Error) -> Self { Error::Json(err) }
+}
+
+
+impl fmt::Display for Error {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f,"audit log i/o failed: {}",err),
+            Error::Json(err) => write!(f,"audit log encoding failed: {}",err),
+            Error::Broken { line, reason } => write!(f,"audit log broken at line {}: {}",line,reason),
+            Error::Unavailable => f.write_str("audit log unavailable (previous write failed)"),
+        }
+    }
+}
+
+
+impl error::Error for Error {
+
+    fn description(&self) -> &str {
+        match self {
+            Error::Io(err) => err.description(),
+            Error::Json(err) => err.description(),
+            Error::Broken { .. } => "audit log integrity check failed",
+            Error::Unavailable => "audit log unavailable",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Json(err) => Some(err),
+            _other => None,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use audit::{self,AuditLog,Outcome,Record,Error,Head};
+    use functions::Call;
+    use rpc::Request;
+    use types::H256;
+    use std::path::PathBuf;
+    use std::{env,fs,process};
+
+    const KEY: &str = "0x00000000000000000000000000000000000a11ce";
+
+
+    /// Write a log of `count` records to a fresh file, returning its path & lines.
+    fn write_log(name: &str, count: u8) -> (PathBuf,Vec<String>) {
+        let path = env::temp_dir().join(format!("audit-test-{}-{}.log",name,process::id()));
+        let _ = fs::remove_file(&path);
+        let audit = AuditLog::open(&path).unwrap();
+        for index in 0..count {
+            let call = Call { name: format!("call{}",index), inputs: Default::default() };
+            let outcome = Outcome::TxHash(H256::from([index;32]));
+            audit.record(KEY.parse().unwrap(),None,&Request::EncodeCall(call),outcome).unwrap();
+        }
+        let lines = fs::read_to_string(&path).unwrap().lines().map(String::from).collect();
+        (path,lines)
+    }
+
+
+    fn verify_lines(path: &PathBuf, lines: &[String]) -> Result<Head,Error> {
+        fs::write(path,lines.join("\n") + "\n").unwrap();
+        audit::verify(path)
+    }
+
+
+    fn broken_at(rslt: Result<Head,Error>) -> usize {
+        match rslt {
+            Err(Error::Broken { line, .. }) => line,
+            other => panic!("expected broken chain, got {:?}",other),
+        }
+    }
+
+
+    #[test]
+    fn hash_chain() {
+        let (path,lines) = write_log("chain",3);
+        let records: Vec<Record> = lines.iter().map(|line| ::serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(records[0].prev,H256::from([0u8;32]));
+        for (index,record) in records.iter().enumerate() {
+            assert_eq!(record.seq,index as u64);
+            assert_eq!(record.hash,record.compute_hash());
+            if index > 0 {
+                assert_eq!(record.prev,records[index - 1].hash);
+            }
+        }
+        assert_eq!(audit::verify(&path).unwrap(),Head { count: 3, hash: records[2].hash });
+        // reopening continues the existing chain
+        let audit = AuditLog::open(&path).unwrap();
+        let call = Call { name: "more".into(), inputs: Default::default() };
+        audit.record(KEY.parse().unwrap(),None,&Request::EncodeCall(call),Outcome::TxHash(H256::from([9u8;32]))).unwrap();
+        let head = audit::verify(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(head.count,4);
+    }
+
+
+    #[test]
+    fn tampered_records() {
+        let (path,lines) = write_log("tamper",3);
+        // modified contents
+        let mut tampered = lines.clone();
+        tampered[1] = tampered[1].replace("call1","call7");
+        assert_eq!(broken_at(verify_lines(&path,&tampered)),2);
+        // modified contents, with the record's own hash recomputed
+        let mut record: Record = ::serde_json::from_str(&lines[1]).unwrap();
+        record.outcome = Outcome::TxHash(H256::from([7u8;32]));
+        record.hash = record.compute_hash();
+        let mut tampered = lines.clone();
+        tampered[1] = ::serde_json::to_string(&record).unwrap();
+        assert_eq!(broken_at(verify_lines(&path,&tampered)),3);
+        // removed record
+        let mut tampered = lines.clone();
+        tampered.remove(1);
+        assert_eq!(broken_at(verify_lines(&path,&tampered)),2);
+        // malformed record
+        let mut tampered = lines.clone();
+        tampered[0].pop();
+        assert_eq!(broken_at(verify_lines(&path,&tampered)),1);
+        fs::remove_file(&path).unwrap();
+    }
+
+
+    #[test]
+    fn reordered_records() {
+        let (path,lines) = write_log("reorder",3);
+        let mut reordered = lines.clone();
+        reordered.swap(1,2);
+        assert_eq!(broken_at(verify_lines(&path,&reordered)),2);
+        // truncation is not detectable from the log alone (see module docs)
+        let head = verify_lines(&path,&lines[..2]).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(head.count,2);
+    }
+}
@@ -1,5 +1,7 @@
-use ethrpc::abi::Function;
+use functions::FunctionConfig;
 use ethtokens::EthToken;
+use approvals::ApprovalConfig;
+use hooks::HookConfig;
 use crypto::Address;
 use std::collections::HashSet;
 
@@ -10,8 +12,45 @@ pub struct ConfigFile {
     #[serde(default,rename = "contract-whitelist")]
     pub contracts: HashSet<Address>,
     #[serde(default,rename = "function-config")]
-    pub functions: Vec<Function>,
+    pub functions: Vec<FunctionConfig>,
     #[serde(default,rename = "ethtoken-config")]
     pub ethtokens: Vec<EthToken>,
+    #[serde(default)]
+    pub approval: Option<ApprovalConfig>,
+    #[serde(default,rename = "pre-sign-hook")]
+    pub hook: Option<HookConfig>,
+    #[serde(default,rename = "key")]
+    pub keys: Vec<KeyConfig>,
 }
 
+
+/// A named key, served alongside the key specified on the command-line.
+///
+/// Each key carries its own whitelist, functions, ethtokens, approval policy &
+/// pre-sign hook; the top-level config applies only to the command-line key.
+///
+#[derive(Debug,Clone,Serialize,Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyConfig {
+    pub name: String,
+    #[serde(default)]
+    pub keystore: Option<String>,
+    #[serde(default,rename = "secret-file")]
+    pub key_file: Option<String>,
+    #[serde(default,rename = "secret-var")]
+    pub key_var: Option<String>,
+    #[serde(default,rename = "passphrase-file")]
+    pub passphrase_file: Option<String>,
+    #[serde(default,rename = "passphrase-var")]
+    pub passphrase_var: Option<String>,
+    #[serde(default,rename = "contract-whitelist")]
+    pub contracts: HashSet<Address>,
+    #[serde(default,rename = "function-config")]
+    pub functions: Vec<FunctionConfig>,
+    #[serde(default,rename = "ethtoken-config")]
+    pub ethtokens: Vec<EthToken>,
+    #[serde(default)]
+    pub approval: Option<ApprovalConfig>,
+    #[serde(default,rename = "pre-sign-hook")]
+    pub hook: Option<HookConfig>,
+}
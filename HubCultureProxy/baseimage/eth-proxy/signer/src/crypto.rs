@@ -7,6 +7,7 @@ pub use mimir_crypto::secp256k1::{
     Secret,
     Signer,
     Error,
+    ecrecover,
 };
 use rand;
 
@@ -2,6 +2,10 @@ use mimir_common::util::hex::ParseHexError;
 use std::{fmt,io,error};
 use toml;
 use functions;
+use limits;
+use audit;
+use approvals;
+use hooks;
 use crypto;
 
 wrap_errs! {
@@ -10,6 +14,10 @@ wrap_errs! {
     Hex => ParseHexError,
     Io => io::Error,
     Function => functions::Error,
+    Limit => limits::Error,
+    Audit => audit::Error,
+    Approval => approvals::Error,
+    Hook => hooks::Error,
     Msg => ErrorMsg,
 }
 
@@ -1,6 +1,9 @@
 use ethrpc::abi::{Function,Value,Token,EncodeError};
+use ethrpc::contract::FunctionCall;
+use limits::Limit;
+use crypto::Address;
 use types::Bytes;
-use std::collections::HashMap;
+use std::collections::{HashMap,HashSet};
 use std::iter::FromIterator;
 use std::{fmt,error};
 
@@ -17,11 +20,167 @@ pub struct Call {
 }
 
 
+impl Call {
+
+    /// Describe a call built from typed contract bindings.
+    ///
+    pub fn from_typed<F: FunctionCall>(call: &F) -> Self {
+        let function = F::function();
+        let inputs = function.iter_inputs().map(|(name,_)| name.to_owned())
+            .zip(call.args())
+            .collect();
+        Self { name: function.name.clone(), inputs }
+    }
+}
+
+
+/// A function specification, with optional policy rules for its inputs.
+///
+/// Rules are keyed by input name:
+///
+/// ```toml
+/// [[function-config]]
+/// name = "deposit"
+/// inputs = [
+///     { name = "to", type = "address" },
+///     { name = "value", type = "uint256" },
+/// ]
+///
+/// [function-config.rules]
+/// to = { allow = ["0x00000000deadbeefdeadbeefdeadbeefdeadbeef"] }
+/// value = { min = "1", max = "1000000" }
+/// ```
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct FunctionConfig {
+    #[serde(flatten)]
+    pub function: Function,
+    #[serde(default,skip_serializing_if = "HashMap::is_empty")]
+    pub rules: HashMap<String,Rule>,
+    #[serde(default,skip_serializing_if = "Vec::is_empty")]
+    pub limits: Vec<Limit>,
+}
+
+
+impl FunctionConfig {
+
+    /// Check that all rules refer to existing inputs of a compatible type.
+    ///
+    pub fn validate(&self) -> Result<(),Error> {
+        for (input,rule) in self.rules.iter() {
+            let kind = self.function.iter_inputs().find(|(name,_)| *name == input.as_str())
+                .map(|(_,kind)| kind);
+            let reason = match kind {
+                Some(kind) => rule.check_kind(kind),
+                None => Some("no such input"),
+            };
+            if let Some(reason) = reason {
+                return Err(Error::InvalidRule {
+                    function: self.function.name.clone(),
+                    input: input.to_owned(),
+                    reason: reason,
+                });
+            }
+        }
+        for limit in self.limits.iter() {
+            if let Some(reason) = limit.check_kinds(&self.function) {
+                return Err(Error::InvalidLimit {
+                    function: self.function.name.clone(),
+                    reason: reason,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+
+impl From<Function> for FunctionConfig {
+
+    fn from(function: Function) -> Self {
+        Self { function, rules: Default::default(), limits: Default::default() }
+    }
+}
+
+
+/// Constraints on the value of a single function input.
+///
+/// `min` & `max` apply to uints, `allow` & `deny` to addresses, and `equals` to
+/// inputs of any type.  An empty `allow` list permits no addresses.
+///
+#[derive(Default,Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub min: Option<Value>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub max: Option<Value>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub allow: Option<HashSet<Address>>,
+    #[serde(default,skip_serializing_if = "HashSet::is_empty")]
+    pub deny: HashSet<Address>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub equals: Option<Value>,
+}
+
+
+impl Rule {
+
+    /// Check the (already cast) value of an input against this rule.
+    ///
+    pub fn check(&self, value: &Value) -> Result<(),String> {
+        let word = value.into_word();
+        if let Some(min) = self.min.as_ref() {
+            // big-endian words order numerically
+            if word < min.into_word() {
+                return Err(format!("less than minimum {}",min));
+            }
+        }
+        if let Some(max) = self.max.as_ref() {
+            if word > max.into_word() {
+                return Err(format!("greater than maximum {}",max));
+            }
+        }
+        if let Some(addr) = value.as_addr() {
+            if self.allow.as_ref().map(|allow| !allow.contains(&addr)).unwrap_or(false) {
+                return Err("not in allow list".into());
+            }
+            if self.deny.contains(&addr) {
+                return Err("in deny list".into());
+            }
+        }
+        if let Some(expected) = self.equals.as_ref() {
+            if word != expected.into_word() {
+                return Err(format!("not equal to {}",expected));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_kind(&self, kind: Token) -> Option<&'static str> {
+        let is_uint = kind == Token::Uint || kind == Token::Uint8;
+        if (self.min.is_some() || self.max.is_some()) && !is_uint {
+            return Some("`min` & `max` require a uint input");
+        }
+        if (self.allow.is_some() || !self.deny.is_empty()) && kind != Token::Addr {
+            return Some("`allow` & `deny` require an address input");
+        }
+        let bounds = self.min.iter().chain(self.max.iter()).chain(self.equals.iter());
+        for value in bounds {
+            if value.try_cast(kind).is_err() {
+                return Some("constant not representable as input type");
+            }
+        }
+        None
+    }
+}
+
+
 /// A collection of function specifications
 ///
 #[derive(Debug,Clone,PartialEq,Eq)]
 pub struct Functions {
-    inner: HashMap<String,Function>,
+    inner: HashMap<String,FunctionConfig>,
 }
 
 
@@ -31,10 +190,19 @@ impl Functions {
     ///
     pub fn try_encode(&self, call: Call) -> Result<Bytes,Error> {
         let Call { name, mut inputs } = call;
-        if let Some(function) = self.inner.get(&name) {
+        if let Some(config) = self.inner.get(&name) {
+            let function = &config.function;
             let mut args = Vec::with_capacity(inputs.len());
             for (name,kind) in function.iter_inputs() {
                 if let Some(value) = inputs.remove(name) {
+                    // uncastable values are left for `encode` to reject
+                    if let (Some(rule),Ok(cast)) = (config.rules.get(name),value.try_cast(kind)) {
+                        rule.check(&cast).map_err(|reason| Error::Policy {
+                            name: name.to_owned(),
+                            value: value,
+                            reason: reason,
+                        })?;
+                    }
                     args.push(value);
                 } else {
                     return Err(Error::MissingArg {
@@ -54,27 +222,44 @@ impl Functions {
         }
     }
 
-    pub fn get(&self, name: &str) -> Option<&Function> { self.inner.get(name) }
+    /// Check if any function has call limits.
+    ///
+    pub fn has_limits(&self) -> bool {
+        self.inner.values().any(|config| !config.limits.is_empty())
+    }
+
+    pub fn get_config(&self, name: &str) -> Option<&FunctionConfig> { self.inner.get(name) }
+
+    pub fn get(&self, name: &str) -> Option<&Function> {
+        self.inner.get(name).map(|config| &config.function)
+    }
 
     pub fn iter(&self) -> impl Iterator<Item=(&str,&Function)> {
-        self.inner.iter().map(|(name,function)| {
-            (name.as_ref(),function)
+        self.inner.iter().map(|(name,config)| {
+            (name.as_ref(),&config.function)
         })
     }
 }
 
 
 
-impl FromIterator<Function> for Functions {
+impl FromIterator<FunctionConfig> for Functions {
 
-    fn from_iter<T>(iter: T) -> Self where T: IntoIterator<Item=Function> {
-        let inner = iter.into_iter().map(|function| {
-            (function.name.clone(),function)
+    fn from_iter<T>(iter: T) -> Self where T: IntoIterator<Item=FunctionConfig> {
+        let inner = iter.into_iter().map(|config| {
+            (config.function.name.clone(),config)
         }).collect();
         Self { inner }
     }
 }
 
+impl FromIterator<Function> for Functions {
+
+    fn from_iter<T>(iter: T) -> Self where T: IntoIterator<Item=Function> {
+        iter.into_iter().map(FunctionConfig::from).collect()
+    }
+}
+
 impl<'a> FromIterator<&'a Function> for Functions {
 
     fn from_iter<T>(iter: T) -> Self where T: IntoIterator<Item=&'a Function> {
@@ -98,6 +283,20 @@ pub enum Error {
     NoSuchFunction {
         name: String,
     },
+    Policy {
+        name: String,
+        value: Value,
+        reason: String,
+    },
+    InvalidRule {
+        function: String,
+        input: String,
+        reason: &'static str,
+    },
+    InvalidLimit {
+        function: String,
+        reason: &'static str,
+    },
 }
 
 
@@ -120,7 +319,16 @@ impl fmt::Display for Error {
             },
             Error::NoSuchFunction { name } => {
                 write!(f,"Unable to locate function `{}`",name)
-            }
+            },
+            Error::Policy { name, value, reason } => {
+                write!(f,"Argument `{}` ({}) rejected by policy: {}",name,value,reason)
+            },
+            Error::InvalidRule { function, input, reason } => {
+                write!(f,"Invalid rule for `{}` in function `{}`: {}",input,function,reason)
+            },
+            Error::InvalidLimit { function, reason } => {
+                write!(f,"Invalid limit for function `{}`: {}",function,reason)
+            },
         }
     }
 }
@@ -134,6 +342,9 @@ impl error::Error for Error {
             Error::MissingArg { .. } => "missing required argument",
             Error::UnknownArg { .. } => "got unexpected argument",
             Error::NoSuchFunction { .. } => "function does not exist",
+            Error::Policy { .. } => "argument rejected by policy",
+            Error::InvalidRule { .. } => "invalid argument rule",
+            Error::InvalidLimit { .. } => "invalid function limit",
         }
     }
 
@@ -145,3 +356,97 @@ impl error::Error for Error {
     }
 }
 
+
+
+#[cfg(test)]
+mod test {
+    use functions::{FunctionConfig,Rule,Error};
+    use ethrpc::abi::Value;
+    use ethrpc::types::U256;
+    use crypto::Address;
+    use toml;
+
+    const ALICE: &str = "0x00000000000000000000000000000000000a11ce";
+    const BOB: &str = "0x0000000000000000000000000000000000000b0b";
+
+
+    fn uint(value: u64) -> Value { Value::Uint(U256::from(value)) }
+
+    fn addr(value: &str) -> Value { Value::Addr(value.parse().unwrap()) }
+
+
+    fn rule(spec: &str) -> Rule { toml::from_str(spec).unwrap() }
+
+
+    #[test]
+    fn uint_bounds() {
+        let bounded = rule(r#"min = 10
+max = "0x64""#);
+        assert!(bounded.check(&uint(9)).is_err());
+        assert!(bounded.check(&uint(10)).is_ok());
+        assert!(bounded.check(&uint(100)).is_ok());
+        assert!(bounded.check(&uint(101)).is_err());
+        // bounds compare numerically across uint widths
+        assert!(bounded.check(&Value::Uint8(64u8.into())).is_ok());
+        assert!(bounded.check(&Value::Uint8(5u8.into())).is_err());
+        let exact = rule("equals = 42");
+        assert!(exact.check(&uint(42)).is_ok());
+        assert!(exact.check(&uint(43)).is_err());
+        assert!(exact.check(&uint(41)).is_err());
+    }
+
+
+    #[test]
+    fn addr_lists() {
+        let alice: Address = ALICE.parse().unwrap();
+        let bob: Address = BOB.parse().unwrap();
+        let allow = Rule { allow: Some(vec![alice].into_iter().collect()), ..Default::default() };
+        assert!(allow.check(&addr(ALICE)).is_ok());
+        assert_eq!(allow.check(&addr(BOB)),Err("not in allow list".into()));
+        let deny = Rule { deny: vec![bob].into_iter().collect(), ..Default::default() };
+        assert!(deny.check(&addr(ALICE)).is_ok());
+        assert_eq!(deny.check(&addr(BOB)),Err("in deny list".into()));
+        // an empty allow list permits nothing
+        let empty = Rule { allow: Some(Default::default()), ..Default::default() };
+        assert!(empty.check(&addr(ALICE)).is_err());
+        let exact = Rule { equals: Some(addr(ALICE)), ..Default::default() };
+        assert!(exact.check(&addr(ALICE)).is_ok());
+        assert!(exact.check(&addr(BOB)).is_err());
+    }
+
+
+    #[test]
+    fn negative_bounds() {
+        assert!(toml::from_str::<Rule>("min = -1").is_err());
+        assert!(toml::from_str::<Rule>("max = 0").is_ok());
+    }
+
+
+    #[test]
+    fn bad_types() {
+        let config = |rules: &str| -> FunctionConfig {
+            let spec = format!(r#"name = "deposit"
+inputs = [
+    {{ name = "to", type = "address" }},
+    {{ name = "count", type = "uint8" }},
+]
+
+[rules]
+{}"#,rules);
+            toml::from_str(&spec).unwrap()
+        };
+        let invalid = |rules: &str| -> &'static str {
+            match config(rules).validate() {
+                Err(Error::InvalidRule { reason, .. }) => reason,
+                other => panic!("expected invalid rule, got {:?}",other),
+            }
+        };
+        assert!(config(r#"to = { allow = ["0x00000000000000000000000000000000000a11ce"] }
+count = { max = 10 }"#).validate().is_ok());
+        assert_eq!(invalid("to = { min = 1 }"),"`min` & `max` require a uint input");
+        assert_eq!(invalid(r#"count = { deny = ["0x00000000000000000000000000000000000a11ce"] }"#),"`allow` & `deny` require an address input");
+        assert_eq!(invalid("count = { max = 256 }"),"constant not representable as input type");
+        assert_eq!(invalid(r#"to = { equals = "0x0000000000000000000000000000000000000000000000000000000000000001" }"#),"constant not representable as input type");
+        assert_eq!(invalid("amount = { max = 1 }"),"no such input");
+    }
+}
@@ -0,0 +1,305 @@
+//! External policy service consulted before signing selected function calls.
+//!
+//! ```toml
+//! [pre-sign-hook]
+//! url = "https://policy.example.com/check"
+//! functions = ["transfer"]
+//! timeout-ms = 2000
+//! ```
+//!
+//! Each call to a listed function (or to any function, if `functions` is omitted)
+//! is posted to `url` as a json `PreSign` description.  Signing proceeds only if
+//! the service responds with a success status and a body of `{"allow":true}`.  Any
+//! other outcome (denial, error status, malformed body, transport failure or
+//! timeout) refuses the request.
+//!
+use ethrpc::abi::Value;
+use serde::de;
+use serde_json::{self,Value as Json};
+use proxy::http::{self,Uri};
+use tokio::prelude::*;
+use tokio::prelude::future::Either;
+use crypto::Address;
+use types::U256;
+use std::collections::{HashMap,HashSet};
+use std::net::SocketAddr;
+use std::time::Duration;
+use std::{fmt,error};
+
+
+fn default_timeout_ms() -> u64 { 5000 }
+
+
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HookConfig {
+    pub url: String,
+    /// Functions whose calls require the hook's approval (all, if empty)
+    #[serde(default,skip_serializing_if = "HashSet::is_empty")]
+    pub functions: HashSet<String>,
+    /// Milliseconds to wait for the hook's response
+    #[serde(default = "default_timeout_ms",rename = "timeout-ms")]
+    pub timeout_ms: u64,
+}
+
+
+/// Description of a function call, as posted to the hook.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct PreSign {
+    /// Address of the signing key
+    pub key: Address,
+    /// Contract being called
+    pub contract: Address,
+    /// Name of the function being called
+    pub function: String,
+    /// Arguments, as a mapping from input name to value
+    pub inputs: HashMap<String,Value>,
+    /// Value (in wei) sent with the call
+    pub value: U256,
+    /// Address of the requesting client (if known)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub peer: Option<SocketAddr>,
+}
+
+
+/// Response expected from the hook.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct Verdict {
+    pub allow: bool,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+
+/// A configured pre-sign hook.
+///
+#[derive(Debug,Clone)]
+pub struct Hook {
+    uri: Uri,
+    functions: HashSet<String>,
+    timeout: Duration,
+    client: http::Client,
+}
+
+
+impl Hook {
+
+    pub fn new(config: HookConfig) -> Result<Self,Error> {
+        let HookConfig { url, functions, timeout_ms } = config;
+        let uri: Uri = url.parse().map_err(|_| Error::InvalidUrl(url.clone()))?;
+        if uri.scheme_part().map(|scheme| scheme.as_str()) != Some("https") {
+            warn!("Pre-sign hook {} does not use https",uri);
+        }
+        let client = http::client()?;
+        Ok(Self { uri, functions, timeout: Duration::from_millis(timeout_ms), client })
+    }
+
+    /// Check if calls to `function` must be allowed by this hook.
+    ///
+    pub fn applies_to(&self, function: &str) -> bool {
+        self.functions.is_empty() || self.functions.contains(function)
+    }
+
+    /// Ask the hook to allow `call`, failing unless it explicitly does so in time.
+    ///
+    pub fn check(&self, call: &PreSign) -> impl Future<Item=(),Error=Error> + Send {
+        let client = self.client.clone();
+        let uri = self.uri.clone();
+        let function = call.function.clone();
+        let build_request = http::into_json_body(call).and_then(move |body| {
+            http::Request::post(uri)
+                .header("Content-Type","application/json")
+                .body(body)
+                .map_err(From::from)
+        });
+        let work = build_request.and_then(move |request| client.request(request).from_err())
+            .from_err()
+            .and_then(|rsp| {
+                let status = rsp.status();
+                if status.is_success() {
+                    Either::A(http::from_json_body(rsp.into_body()).from_err().and_then(parse_verdict))
+                } else {
+                    Either::B(future::err(Error::Status(status.as_u16())))
+                }
+            })
+            .and_then(move |verdict: Verdict| {
+                if verdict.allow {
+                    Ok(())
+                } else {
+                    warn!("Pre-sign hook denied call to `{}`",function);
+                    Err(Error::Denied { function, reason: verdict.reason })
+                }
+            });
+        work.timeout(self.timeout).map_err(|err| err.into_inner().unwrap_or(Error::Timeout))
+    }
+}
+
+
+/// Decode the hook's verdict, which must be a json object (serde would otherwise
+/// also accept a sequence, such that `[true]` would allow the call).
+///
+fn parse_verdict(body: Json) -> Result<Verdict,Error> {
+    if !body.is_object() {
+        let err: serde_json::Error = de::Error::custom("expected verdict object");
+        return Err(http::Error::from(err).into());
+    }
+    serde_json::from_value(body).map_err(|err| http::Error::from(err).into())
+}
+
+
+
+#[derive(Debug)]
+pub enum Error {
+    /// Hook explicitly refused the call
+    Denied {
+        function: String,
+        reason: Option<String>,
+    },
+    /// Hook responded with a non-success status
+    Status(u16),
+    /// Hook did not respond within its timeout
+    Timeout,
+    InvalidUrl(String),
+    Http(http::Error),
+}
+
+
+impl From<http::Error> for Error {
+
+    fn from(err: http::Error) -> Self { Error::Http(err) }
+}
+
+
+impl fmt::Display for Error {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Denied { function, reason } => {
+                write!(f,"Call to `{}` denied by pre-sign hook",function)?;
+                if let Some(reason) = reason {
+                    write!(f," ({})",reason)?;
+                }
+                Ok(())
+            },
+            Error::Status(status) => write!(f,"Pre-sign hook failed with status {}",status),
+            Error::Timeout => f.write_str("Pre-sign hook timed out"),
+            Error::InvalidUrl(url) => write!(f,"Invalid pre-sign hook url `{}`",url),
+            Error::Http(err) => write!(f,"Pre-sign hook failed: {}",err),
+        }
+    }
+}
+
+
+impl error::Error for Error {
+
+    fn description(&self) -> &str {
+        match self {
+            Error::Denied { .. } => "call denied by pre-sign hook",
+            Error::Status(_) => "pre-sign hook returned error status",
+            Error::Timeout => "pre-sign hook timed out",
+            Error::InvalidUrl(_) => "invalid pre-sign hook url",
+            Error::Http(err) => err.description(),
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match self {
+            Error::Http(err) => Some(err),
+            _other => None,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use hooks::{Hook,HookConfig,PreSign,Error};
+    use hyper::{Body,Response,Server};
+    use hyper::service::service_fn;
+    use tokio::prelude::*;
+    use tokio::runtime::Runtime;
+    use tokio::timer::Delay;
+    use std::time::{Duration,Instant};
+
+
+    /// Check a call against a local hook which answers with `status` & `body`
+    /// after `delay_ms`, allowing the hook `timeout_ms` to respond.
+    fn check(status: u16, body: &'static str, delay_ms: u64, timeout_ms: u64) -> Result<(),Error> {
+        let mut runtime = Runtime::new().unwrap();
+        let server = Server::bind(&([127,0,0,1],0).into()).serve(move || {
+            service_fn(move |_req| {
+                let delay = Delay::new(Instant::now() + Duration::from_millis(delay_ms));
+                delay.map(move |()| Response::builder().status(status).body(Body::from(body)).unwrap())
+            })
+        });
+        let url = format!("http://{}/check",server.local_addr());
+        runtime.spawn(server.map_err(|err| error!("Hook server failed: {}",err)));
+        let hook = Hook::new(HookConfig { url, functions: Default::default(), timeout_ms }).unwrap();
+        let call = PreSign {
+            key: "0x00000000000000000000000000000000000a11ce".parse().unwrap(),
+            contract: "0x0000000000000000000000000000000000000c0d".parse().unwrap(),
+            function: "transfer".into(),
+            inputs: Default::default(),
+            value: 0u64.into(),
+            peer: None,
+        };
+        runtime.block_on(hook.check(&call))
+    }
+
+
+    #[test]
+    fn allow() {
+        check(200,r#"{"allow":true}"#,0,1000).unwrap();
+        check(200,r#"{"allow":true,"reason":"within policy"}"#,0,1000).unwrap();
+    }
+
+
+    #[test]
+    fn deny() {
+        match check(200,r#"{"allow":false,"reason":"over daily limit"}"#,0,1000) {
+            Err(Error::Denied { function, reason }) => {
+                assert_eq!(function,"transfer");
+                assert_eq!(reason.as_ref().map(String::as_str),Some("over daily limit"));
+            },
+            other => panic!("expected denial, got {:?}",other),
+        }
+        match check(200,r#"{"allow":false}"#,0,1000) {
+            Err(Error::Denied { reason: None, .. }) => { },
+            other => panic!("expected denial, got {:?}",other),
+        }
+    }
+
+
+    #[test]
+    fn error_status() {
+        // an error status refuses the call, whatever the body says
+        for &status in [403u16,500,302].iter() {
+            match check(status,r#"{"allow":true}"#,0,1000) {
+                Err(Error::Status(got)) => assert_eq!(got,status),
+                other => panic!("expected status {} error, got {:?}",status,other),
+            }
+        }
+    }
+
+
+    #[test]
+    fn malformed_body() {
+        for &body in ["","not json",r#"{"allow":"yes"}"#,r#"{"reason":"missing verdict"}"#,"[true]"].iter() {
+            match check(200,body,0,1000) {
+                Err(Error::Http(_)) => { },
+                other => panic!("expected decoding error for {:?}, got {:?}",body,other),
+            }
+        }
+    }
+
+
+    #[test]
+    fn timeout() {
+        match check(200,r#"{"allow":true}"#,500,100) {
+            Err(Error::Timeout) => { },
+            other => panic!("expected timeout, got {:?}",other),
+        }
+    }
+}
@@ -0,0 +1,551 @@
+//! Encrypted secret storage (Web3 Secret Storage v3).
+//!
+//! Keystore files are compatible with geth, parity & most wallets.  The secret
+//! is encrypted with `aes-128-ctr` under a key derived from a passphrase via
+//! `scrypt` or `pbkdf2`.  Intermediate key material is zeroized on drop.
+//!
+//! ## Example
+//!
+//! ```
+//! extern crate signer;
+//!
+//! use signer::keystore::{Kdf,KeyFile};
+//!
+//! # fn main() {
+//! let secret = signer::crypto::keygen();
+//!
+//! // use cheap kdf parameters; real keystores should use `Kdf::default()`
+//! let kdf = Kdf::Pbkdf2 { rounds: 1024 };
+//! let keyfile = KeyFile::encrypt(&secret,b"hunter2",kdf).unwrap();
+//!
+//! let json = keyfile.to_json().unwrap();
+//! let parsed: KeyFile = json.parse().unwrap();
+//! assert_eq!(format!("{:?}",parsed.decrypt(b"hunter2").unwrap()),format!("{:?}",secret));
+//! assert!(parsed.decrypt(b"hunter3").is_err());
+//! # }
+//! ```
+//!
+use crypto::{self,Secret,Signer};
+use error::Error;
+use aes_ctr::Aes128Ctr;
+use aes_ctr::cipher::stream::{NewStreamCipher,SyncStreamCipher};
+use hmac::Hmac;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+use serde::de::{self,Deserialize,Deserializer};
+use serde::ser::{Serialize,Serializer};
+use serde_json;
+use util;
+use proxy::util::{constant_time_eq,from_hex,to_hex};
+use rand;
+use std::path::Path;
+use std::str::FromStr;
+use std::{fmt,fs,mem};
+
+
+/// Largest `scrypt` cost parameter accepted (as for geth).
+///
+const MAX_SCRYPT_N: u64 = 1 << 20;
+
+/// Largest `scrypt` working memory (`128 * r * n` bytes) accepted.
+///
+const MAX_SCRYPT_MEMORY: u128 = 128 * 8 * (1 << 20);
+
+
+/// Key derivation function used to encrypt a keystore.
+///
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum Kdf {
+    /// `scrypt` with cost parameter `2^log_n`
+    Scrypt { log_n: u8, r: u32, p: u32 },
+    /// `pbkdf2` with `hmac-sha256`
+    Pbkdf2 { rounds: u32 },
+}
+
+
+impl Kdf {
+
+    /// Standard `scrypt` parameters (as used by geth).
+    ///
+    pub fn scrypt() -> Self { Kdf::Scrypt { log_n: 18, r: 8, p: 1 } }
+
+    /// Standard `pbkdf2` parameters.
+    ///
+    pub fn pbkdf2() -> Self { Kdf::Pbkdf2 { rounds: 262144 } }
+}
+
+
+impl Default for Kdf {
+
+    fn default() -> Self { Kdf::scrypt() }
+}
+
+
+impl FromStr for Kdf {
+
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        match s {
+            "scrypt" => Ok(Kdf::scrypt()),
+            "pbkdf2" => Ok(Kdf::pbkdf2()),
+            other => Err(Error::message(format!("unknown kdf `{}` (expected `scrypt` or `pbkdf2`)",other))),
+        }
+    }
+}
+
+
+/// A v3 keystore file.
+///
+#[derive(Debug,Clone,Serialize,Deserialize)]
+pub struct KeyFile {
+    /// Address of the encrypted key (hex, without prefix)
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(alias = "Crypto")]
+    crypto: CryptoParams,
+    pub id: String,
+    pub version: u32,
+}
+
+
+impl KeyFile {
+
+    /// Encrypt `secret` under `passphrase`.
+    ///
+    pub fn encrypt(secret: &Secret, passphrase: &[u8], kdf: Kdf) -> Result<Self,Error> {
+        let address = Signer::new(*secret)?.address();
+        let salt: [u8;32] = rand::random();
+        let iv: [u8;16] = rand::random();
+        let kdfparams = match kdf {
+            Kdf::Scrypt { log_n, r, p } => {
+                let n = 1u64.checked_shl(log_n as u32).unwrap_or(0);
+                KdfParams::Scrypt { dklen: 32, n, r, p, salt: Hex(salt.to_vec()) }
+            },
+            Kdf::Pbkdf2 { rounds } => {
+                KdfParams::Pbkdf2 { c: rounds, dklen: 32, prf: "hmac-sha256".into(), salt: Hex(salt.to_vec()) }
+            },
+        };
+        let derived = kdfparams.derive(passphrase)?;
+        let mut ciphertext = secret_bytes(secret);
+        apply_cipher(&derived[..16],&iv,&mut ciphertext[..]);
+        let mac = mac(&derived,&ciphertext[..]);
+        let crypto = CryptoParams {
+            cipher: "aes-128-ctr".into(),
+            cipherparams: CipherParams { iv: Hex(iv.to_vec()) },
+            ciphertext: Hex(ciphertext.to_vec()),
+            kdf: kdfparams.name().into(),
+            kdfparams,
+            mac: Hex(mac.to_vec()),
+        };
+        let address = format!("{}",address).trim_start_matches("0x").to_lowercase();
+        Ok(KeyFile { address: Some(address), crypto, id: uuid_v4(), version: 3 })
+    }
+
+    /// Decrypt the secret under `passphrase`.
+    ///
+    /// Fails if the passphrase is incorrect (i.e. the mac does not match), or
+    /// if the keystore uses an unsupported version, cipher or kdf.
+    ///
+    pub fn decrypt(&self, passphrase: &[u8]) -> Result<Secret,Error> {
+        if self.version != 3 {
+            return Err(Error::message(format!("unsupported keystore version {}",self.version)));
+        }
+        let crypto = &self.crypto;
+        if crypto.cipher != "aes-128-ctr" {
+            return Err(Error::message(format!("unsupported keystore cipher `{}`",crypto.cipher)));
+        }
+        if crypto.kdf != crypto.kdfparams.name() {
+            return Err(Error::message(format!("unsupported keystore kdf `{}`",crypto.kdf)));
+        }
+        if crypto.cipherparams.iv.0.len() != 16 || crypto.ciphertext.0.len() != 32 {
+            return Err(Error::message("malformed keystore ciphertext"));
+        }
+        let derived = crypto.kdfparams.derive(passphrase)?;
+        let expected = mac(&derived,&crypto.ciphertext.0);
+        if !constant_time_eq(&expected,&crypto.mac.0) {
+            return Err(Error::message("keystore mac mismatch (incorrect passphrase?)"));
+        }
+        let mut plaintext = Zeroizing::new([0u8;32]);
+        plaintext.copy_from_slice(&crypto.ciphertext.0);
+        apply_cipher(&derived[..16],&crypto.cipherparams.iv.0,&mut plaintext[..]);
+        let secret = secret_from_bytes(&plaintext)?;
+        if let Some(address) = self.address.as_ref() {
+            let actual = format!("{}",Signer::new(secret)?.address());
+            if !actual.trim_start_matches("0x").eq_ignore_ascii_case(address.trim_start_matches("0x")) {
+                return Err(Error::message("keystore address does not match decrypted key"));
+            }
+        }
+        Ok(secret)
+    }
+
+    /// Load & decrypt the keystore at `path`.
+    ///
+    pub fn load(path: impl AsRef<Path>, passphrase: &[u8]) -> Result<Secret,Error> {
+        let buf = Zeroizing::new(fs::read_to_string(path)?);
+        let keyfile: KeyFile = buf.parse()?;
+        keyfile.decrypt(passphrase)
+    }
+
+    /// Write the keystore to `path`.
+    ///
+    /// The file is created readable by its owner only, and existing files are
+    /// never overwritten (see `util::write_private`).
+    ///
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(),Error> {
+        let json = self.to_json()?;
+        util::write_private(path,(json + "\n").as_bytes())?;
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Result<String,Error> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::message(e.to_string()))
+    }
+}
+
+
+impl FromStr for KeyFile {
+
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        serde_json::from_str(s).map_err(|e| Error::message(format!("invalid keystore: {}",e)))
+    }
+}
+
+
+/// Check whether `buf` looks like a keystore (as opposed to a raw hex secret).
+///
+pub fn is_keystore(buf: &str) -> bool { buf.trim_start().starts_with('{') }
+
+
+#[derive(Debug,Clone,Serialize,Deserialize)]
+struct CryptoParams {
+    cipher: String,
+    cipherparams: CipherParams,
+    ciphertext: Hex,
+    kdf: String,
+    kdfparams: KdfParams,
+    mac: Hex,
+}
+
+
+#[derive(Debug,Clone,Serialize,Deserialize)]
+struct CipherParams {
+    iv: Hex,
+}
+
+
+#[derive(Debug,Clone,Serialize,Deserialize)]
+#[serde(untagged)]
+enum KdfParams {
+    Scrypt {
+        dklen: usize,
+        n: u64,
+        r: u32,
+        p: u32,
+        salt: Hex,
+    },
+    Pbkdf2 {
+        c: u32,
+        dklen: usize,
+        prf: String,
+        salt: Hex,
+    },
+}
+
+
+impl KdfParams {
+
+    fn name(&self) -> &'static str {
+        match self {
+            KdfParams::Scrypt { .. } => "scrypt",
+            KdfParams::Pbkdf2 { .. } => "pbkdf2",
+        }
+    }
+
+    /// Derive the 32-byte key used for encryption (first half) & mac (second half).
+    ///
+    fn derive(&self, passphrase: &[u8]) -> Result<Zeroizing<[u8;32]>,Error> {
+        let mut derived = Zeroizing::new([0u8;32]);
+        match self {
+            KdfParams::Scrypt { dklen, n, r, p, salt } => {
+                let (r,p) = (*r as usize,*p as usize);
+                if *dklen != 32 || !n.is_power_of_two() || *n < 2 || r == 0 || p == 0 || (r as u64) * (p as u64) >= 1 << 30 {
+                    return Err(Error::message("unsupported scrypt parameters"));
+                }
+                if *n > MAX_SCRYPT_N || (*n as u128) * (r as u128) * 128 > MAX_SCRYPT_MEMORY {
+                    return Err(Error::message("scrypt parameters exceed memory limit"));
+                }
+                let n = *n as usize;
+                scrypt(passphrase,&salt.0,n,r,p,&mut derived[..]);
+            },
+            KdfParams::Pbkdf2 { c, dklen, prf, salt } => {
+                if *dklen != 32 || prf != "hmac-sha256" {
+                    return Err(Error::message("unsupported pbkdf2 parameters"));
+                }
+                pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase,&salt.0,*c,&mut derived[..]);
+            },
+        }
+        Ok(derived)
+    }
+}
+
+
+/// Hex-encoded bytes (no `0x` prefix, as is conventional for keystores).
+///
+#[derive(Clone)]
+struct Hex(Vec<u8>);
+
+
+impl fmt::Debug for Hex {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&to_hex(&self.0))
+    }
+}
+
+
+impl Serialize for Hex {
+
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok,S::Error> {
+        serializer.serialize_str(&to_hex(&self.0))
+    }
+}
+
+
+impl<'de> Deserialize<'de> for Hex {
+
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self,D::Error> {
+        let s = String::deserialize(deserializer)?;
+        from_hex(s.trim_start_matches("0x")).map(Hex)
+            .ok_or_else(|| de::Error::custom("invalid hex string"))
+    }
+}
+
+
+/// `scrypt` key derivation.
+///
+/// Unlike RFC 7914, `n` is not required to be less than `2^(16 * r)`; geth does
+/// not enforce that bound, and keystores which exceed it (including the reference
+/// test vectors) are in circulation.  Published `scrypt` crates reject such
+/// parameters outright, hence the in-tree implementation (tested against the
+/// RFC 7914 vectors below).
+///
+fn scrypt(passphrase: &[u8], salt: &[u8], n: usize, r: usize, p: usize, output: &mut [u8]) {
+    let mut b = Zeroizing::new(vec![0u8; 128 * r * p]);
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase,salt,1,&mut b[..]);
+    let mut v = Zeroizing::new(vec![0u32; 32 * r * n]);
+    for chunk in b.chunks_mut(128 * r) {
+        ro_mix(chunk,&mut v,n,r);
+    }
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase,&b,1,output);
+}
+
+
+/// `scrypt` ROMix, over a `128 * r` byte block (`v` must hold `32 * r * n` words).
+///
+fn ro_mix(block: &mut [u8], v: &mut [u32], n: usize, r: usize) {
+    let words = 32 * r;
+    let mut x = Zeroizing::new(vec![0u32; words]);
+    let mut y = Zeroizing::new(vec![0u32; words]);
+    for (word,bytes) in x.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_le_bytes([bytes[0],bytes[1],bytes[2],bytes[3]]);
+    }
+    for i in 0..n {
+        v[i * words..(i + 1) * words].copy_from_slice(&x);
+        block_mix(&x,&mut y,r);
+        mem::swap(&mut x,&mut y);
+    }
+    for _ in 0..n {
+        let j = (x[words - 16] as usize) & (n - 1);
+        for (a,b) in x.iter_mut().zip(v[j * words..(j + 1) * words].iter()) {
+            *a ^= *b;
+        }
+        block_mix(&x,&mut y,r);
+        mem::swap(&mut x,&mut y);
+    }
+    for (bytes,word) in block.chunks_mut(4).zip(x.iter()) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+}
+
+
+/// `scrypt` BlockMix, over `2 * r` blocks of 16 words.
+///
+fn block_mix(input: &[u32], output: &mut [u32], r: usize) {
+    let mut x = [0u32;16];
+    x.copy_from_slice(&input[input.len() - 16..]);
+    for (i,block) in input.chunks(16).enumerate() {
+        for (a,b) in x.iter_mut().zip(block.iter()) {
+            *a ^= *b;
+        }
+        salsa20_8(&mut x);
+        let pos = if i % 2 == 0 { i / 2 } else { r + i / 2 };
+        output[pos * 16..(pos + 1) * 16].copy_from_slice(&x);
+    }
+}
+
+
+/// The Salsa20/8 core function.
+///
+fn salsa20_8(block: &mut [u32;16]) {
+    fn quarter(x: &mut [u32;16], a: usize, b: usize, c: usize, d: usize) {
+        x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+        x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+        x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+        x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+    }
+    let mut x = *block;
+    for _ in 0..4 {
+        quarter(&mut x,0,4,8,12);
+        quarter(&mut x,5,9,13,1);
+        quarter(&mut x,10,14,2,6);
+        quarter(&mut x,15,3,7,11);
+        quarter(&mut x,0,1,2,3);
+        quarter(&mut x,5,6,7,4);
+        quarter(&mut x,10,11,8,9);
+        quarter(&mut x,15,12,13,14);
+    }
+    for (a,b) in block.iter_mut().zip(x.iter()) {
+        *a = a.wrapping_add(*b);
+    }
+}
+
+
+fn apply_cipher(key: &[u8], iv: &[u8], buf: &mut [u8]) {
+    let mut cipher = Aes128Ctr::new_var(key,iv).expect("key & iv must be 16 bytes");
+    cipher.apply_keystream(buf);
+}
+
+
+fn mac(derived: &[u8;32], ciphertext: &[u8]) -> [u8;32] {
+    let mut buf = Zeroizing::new(Vec::with_capacity(16 + ciphertext.len()));
+    buf.extend_from_slice(&derived[16..]);
+    buf.extend_from_slice(ciphertext);
+    crypto::keccak(buf.as_slice())
+}
+
+
+/// Raw bytes of a secret (copied into a buffer which is zeroized on drop).
+///
+fn secret_bytes(secret: &Secret) -> Zeroizing<[u8;32]> {
+    let mut bytes = Zeroizing::new([0u8;32]);
+    bytes.copy_from_slice(AsRef::<[u8]>::as_ref(secret));
+    bytes
+}
+
+
+fn secret_from_bytes(bytes: &[u8;32]) -> Result<Secret,Error> {
+    let encoded = Zeroizing::new(format!("0x{}",to_hex(bytes)));
+    let secret = encoded.parse()?;
+    Ok(secret)
+}
+
+
+/// Random (version 4) uuid, as used for keystore ids.
+///
+fn uuid_v4() -> String {
+    let mut bytes: [u8;16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = to_hex(&bytes);
+    format!("{}-{}-{}-{}-{}",&hex[0..8],&hex[8..12],&hex[12..16],&hex[16..20],&hex[20..32])
+}
+
+
+#[cfg(test)]
+mod test {
+    use keystore::{self,KdfParams,Hex};
+    use proxy::util::{from_hex,to_hex};
+
+    /// Parse hex into little-endian words (as laid out in RFC 7914).
+    fn words(hex: &str) -> Vec<u32> {
+        let bytes = from_hex(&hex.replace(' ',"")).unwrap();
+        bytes.chunks(4).map(|b| u32::from_le_bytes([b[0],b[1],b[2],b[3]])).collect()
+    }
+
+
+    const BLOCK_MIX_INPUT: &str = "\
+        f7ce0b653d2d72a4108cf5abe912ffdd777616dbbb27a70e8204f3ae2d0f6fad \
+        89f68f4811d1e87bcc3bd7400a9ffd29094f0184639574f39ae5a1315217bcd7 \
+        894991447213bb226c25b54da86370fbcd984380374666bb8ffcb5bf40c254b0 \
+        67d27c51ce4ad5fed829c90b505a571b7f4d1cad6a523cda770e67bceaaf7e89";
+
+
+    #[test]
+    fn salsa20_8() {
+        // RFC 7914 section 8
+        let mut block = [0u32;16];
+        block.copy_from_slice(&words("\
+            7e879a214f3ec9867ca940e641718f26baee555b8c61c1b50df846116dcd3b1d \
+            ee24f319df9b3d8514121e4b5ac5aa3276021d2909c74829edebc68db8b8c25e"));
+        keystore::salsa20_8(&mut block);
+        assert_eq!(&block[..],&words("\
+            a41f859c6608cc993b81cacb020cef05044b2181a2fd337dfd7b1c6396682f29 \
+            b4393168e3c9e6bcfe6bc5b7a06d96bae424cc102c91745c24ad673dc7618f81")[..]);
+    }
+
+
+    #[test]
+    fn block_mix() {
+        // RFC 7914 section 9 (r = 1)
+        let mut output = vec![0u32;32];
+        keystore::block_mix(&words(BLOCK_MIX_INPUT),&mut output,1);
+        assert_eq!(output,words("\
+            a41f859c6608cc993b81cacb020cef05044b2181a2fd337dfd7b1c6396682f29 \
+            b4393168e3c9e6bcfe6bc5b7a06d96bae424cc102c91745c24ad673dc7618f81 \
+            20edc975323881a80540f64c162dcd3c21077cfe5f8d5fe2b1a4168f953678b7 \
+            7d3b3d803b60e4ab920996e59b4d53b65d2a225877d5edf5842cb9f14eefe425"));
+    }
+
+
+    #[test]
+    fn ro_mix() {
+        // RFC 7914 section 10 (r = 1, n = 16)
+        let mut block = from_hex(&BLOCK_MIX_INPUT.replace(' ',"")).unwrap();
+        let mut v = vec![0u32;32 * 16];
+        keystore::ro_mix(&mut block,&mut v,16,1);
+        assert_eq!(to_hex(&block),"\
+            79ccc193629debca047f0b70604bf6b62ce3dd4a9626e355fafc6198e6ea2b46\
+            d58413673b99b029d665c357601fb426a0b2f4bba200ee9f0a43d19b571a9c71\
+            ef1142e65d5a266fddca832ce59faa7cac0b9cf1be2bffca300d01ee387619c4\
+            ae12fd4438f203a0e4e1c47ec314861f4e9087cb33396a6873e8f9d2539a4b8e");
+    }
+
+
+    #[test]
+    fn scrypt() {
+        // RFC 7914 section 12 (the 1 GiB `n = 2^20` vector is omitted)
+        let vectors: &[(&str,&str,usize,usize,usize,&str)] = &[
+            ("","",16,1,1,"\
+                77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442\
+                fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906"),
+            ("password","NaCl",1024,8,16,"\
+                fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162\
+                2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"),
+            ("pleaseletmein","SodiumChloride",16384,8,1,"\
+                7023bdcb3afd7348461c06cd81fd38ebfda8fbba904f8e3ea9b543f6545da1f2\
+                d5432955613f0fcf62d49705242a9af9e61e85dc0d651e40dfcf017b45575887"),
+        ];
+        for &(passphrase,salt,n,r,p,expect) in vectors {
+            let mut output = [0u8;64];
+            keystore::scrypt(passphrase.as_bytes(),salt.as_bytes(),n,r,p,&mut output);
+            assert_eq!(to_hex(&output),expect,"scrypt({:?},{:?},{},{},{})",passphrase,salt,n,r,p);
+        }
+    }
+
+
+    #[test]
+    fn scrypt_limits() {
+        let params = |n: u64, r: u32| KdfParams::Scrypt { dklen: 32, n, r, p: 1, salt: Hex(vec![0;32]) };
+        assert!(params(16,1).derive(b"").is_ok());
+        assert!(params(0,1).derive(b"").is_err());
+        assert!(params(24,1).derive(b"").is_err());
+        // beyond geth's maximum cost, or its maximum memory
+        assert!(params(1 << 21,1).derive(b"").is_err());
+        assert!(params(1 << 63,1).derive(b"").is_err());
+        assert!(params(1 << 20,16).derive(b"").is_err());
+        assert!(params(16,1 << 24).derive(b"").is_err());
+    }
+}
@@ -11,15 +11,29 @@ extern crate serde_json;
 extern crate serde;
 extern crate rand;
 extern crate toml;
+extern crate pbkdf2;
+extern crate hmac;
+extern crate sha2;
+extern crate aes_ctr;
+extern crate zeroize;
+extern crate rpassword;
+extern crate tokio;
 #[macro_use]
 extern crate log;
+#[cfg(test)]
+extern crate hyper;
 
 pub mod contracts;
+pub mod audit;
+pub mod approvals;
+pub mod hooks;
 pub mod functions;
+pub mod limits;
 pub mod ethtokens;
 pub mod options;
 pub mod config;
 pub mod crypto;
+pub mod keystore;
 pub mod types;
 pub mod util;
 pub mod rpc;
@@ -30,69 +44,260 @@ pub use error::Error;
 
 use ethrpc::transaction::Transaction;
 use contracts::Contracts;
-use functions::Functions;
-use ethtokens::EthTokens;
+use functions::{Functions,FunctionConfig};
+use limits::Limits;
+use audit::{AuditLog,Outcome};
+use approvals::{Approvals,ApprovalConfig,PendingApproval,Status};
+use hooks::{Hook,HookConfig,PreSign};
+use ethtokens::{EthTokens,EthToken};
 use options::SignerOptions;
-use crypto::Address;
+use crypto::{Address,Secret};
 use types::Bytes;
-use rpc::Request;
+use rpc::{Request,KeyedRequest,KeyId,KeyInfo};
+use tokio::prelude::*;
+use tokio::prelude::future::Either;
+use std::collections::HashSet;
+use std::net::SocketAddr;
+
+
+/// Name of the key specified on the command-line.
+///
+pub const DEFAULT_KEY: &str = "default";
 
 
 #[derive(Debug,Clone)]
 pub struct Signer {
+    /// Keys served by this signer; the first is the default.
+    keys: Vec<KeySigner>,
+    /// Requests awaiting approval (shared by all keys)
+    approvals: Approvals,
+}
+
+
+impl Signer {
+
+    /// Address of the default key.
+    ///
+    pub fn address(&self) -> Address {
+        self.keys[0].address()
+    }
+
+    /// Name & address of all keys, starting with the default.
+    ///
+    pub fn keys(&self) -> Vec<KeyInfo> {
+        self.keys.iter().map(|key| KeyInfo { name: key.name.clone(), address: key.address() }).collect()
+    }
+
+    pub fn from_options(opt: &SignerOptions) -> Result<Self,Error> {
+        let config = opt.load_config()?;
+        let limits = opt.load_limits()?;
+        let audit = opt.load_audit_log()?;
+        let approvals = Approvals::new();
+        let mut keys = Vec::with_capacity(config.keys.len() + 1);
+        if opt.has_secret() || config.keys.is_empty() {
+            let secret = opt.load_secret()?;
+            let policy = Policy { contracts: config.contracts, functions: config.functions, ethtokens: config.ethtokens, approval: config.approval, hook: config.hook };
+            let key = KeySigner::new(DEFAULT_KEY.to_owned(),secret,policy,&limits,&audit,&approvals,opt)?;
+            keys.push(key);
+        } else if !config.contracts.is_empty() || !config.functions.is_empty() || !config.ethtokens.is_empty() || config.approval.is_some() || config.hook.is_some() {
+            warn!("Ignoring top-level signer config (no command-line key specified)");
+        }
+        for key_config in config.keys {
+            let secret = opt.load_key_secret(&key_config)?;
+            let policy = Policy { contracts: key_config.contracts, functions: key_config.functions, ethtokens: key_config.ethtokens, approval: key_config.approval, hook: key_config.hook };
+            let key = KeySigner::new(key_config.name,secret,policy,&limits,&audit,&approvals,opt)?;
+            if keys.iter().any(|other| other.name == key.name || other.address == key.address) {
+                let msg = format!("duplicate signer key `{}` ({})",key.name,key.address);
+                return Err(Error::message(msg));
+            }
+            keys.push(key);
+        }
+        if !limits.is_persistent() && keys.iter().any(|key| key.functions.has_limits()) {
+            warn!("No limit state file specified; call limits will reset on restart");
+        }
+        if let Some(addr) = opt.default_contract {
+            keys[0].contracts.set_default(addr)?;
+            info!("Configured {} with default contract {}",keys[0].address,addr);
+        }
+        Ok(Self { keys, approvals })
+    }
+
+    pub fn serve(&self, request: KeyedRequest) -> rpc::Result {
+        self.serve_from(None,request)
+    }
+
+    /// Serve a request from `peer` (recorded in the audit log, if enabled).
+    ///
+    /// Calls which require the approval of a pre-sign hook are refused; use
+    /// `serve_async` to consult the hook before signing.
+    ///
+    pub fn serve_from(&self, peer: Option<SocketAddr>, request: KeyedRequest) -> rpc::Result {
+        self.dispatch(peer,request,false)
+    }
+
+    /// Serve a request from `peer`, first consulting the pre-sign hook of the
+    /// selected key (if the request requires it).
+    ///
+    pub fn serve_async(&self, peer: Option<SocketAddr>, request: KeyedRequest) -> impl Future<Item=rpc::Response,Error=rpc::Error> + Send {
+        let pre_sign = self.select_key(request.key.as_ref())
+            .and_then(|key| key.pre_sign(peer,&request.request));
+        let check = match pre_sign {
+            Ok(Some(work)) => Either::A(work.from_err()),
+            Ok(None) => Either::B(future::ok(())),
+            Err(err) => Either::B(future::err(err)),
+        };
+        let signer = self.clone();
+        check.and_then(move |()| signer.dispatch(peer,request,true))
+    }
+
+    fn dispatch(&self, peer: Option<SocketAddr>, request: KeyedRequest, hooked: bool) -> rpc::Result {
+        let KeyedRequest { key, request } = request;
+        match (key, request) {
+            (None, Request::GetAddress { }) if self.keys.len() > 1 => {
+                info!("serving get-address for all keys");
+                Ok(self.keys().into())
+            },
+            (None, Request::GetPending { }) if self.keys.len() > 1 => {
+                info!("serving get-pending for all keys");
+                Ok(self.approvals.pending(None).into())
+            },
+            (key, request) => self.select_key(key.as_ref())?.serve(peer,request,hooked),
+        }
+    }
+
+    /// Get the key identified by `id`, or the default key.
+    ///
+    fn select_key(&self, id: Option<&KeyId>) -> Result<&KeySigner,rpc::Error> {
+        match id {
+            Some(id) => self.get_key(id),
+            None => Ok(&self.keys[0]),
+        }
+    }
+
+    fn get_key(&self, id: &KeyId) -> Result<&KeySigner,rpc::Error> {
+        let found = match id {
+            KeyId::Addr(addr) => self.keys.iter().find(|key| key.address == *addr),
+            KeyId::Name(name) => self.keys.iter().find(|key| key.name == *name),
+        };
+        found.ok_or_else(|| rpc::Error::message(format!("unknown signer key `{}`",id)))
+    }
+}
+
+
+/// Policy sections of the signer config which apply to a single key.
+///
+struct Policy {
+    contracts: HashSet<Address>,
+    functions: Vec<FunctionConfig>,
+    ethtokens: Vec<EthToken>,
+    approval: Option<ApprovalConfig>,
+    hook: Option<HookConfig>,
+}
+
+
+/// Outcome of a signing request which passed policy checks.
+///
+enum Signed {
+    Tx(Bytes),
+    Pending(PendingApproval),
+}
+
+
+/// A single key & the policy under which it signs.
+///
+#[derive(Debug,Clone)]
+struct KeySigner {
+    name: String,
     address: Address,
     signer: crypto::Signer,
     contracts: Contracts,
     functions: Functions,
     ethtokens: EthTokens,
+    limits: Limits,
+    audit: AuditLog,
+    approval: Option<ApprovalConfig>,
+    approvals: Approvals,
+    hook: Option<Hook>,
     allow_creation: bool,
     allow_raw: bool,
 }
 
 
-impl Signer {
+impl KeySigner {
 
-    pub fn address(&self) -> Address {
+    fn address(&self) -> Address {
         debug_assert!(self.address == self.signer.address());
         self.address
     }
 
-    pub fn from_options(opt: &SignerOptions) -> Result<Self,Error> {
-        let secret = opt.load_secret()?;
-        let config = opt.load_config()?;
+    fn new(name: String, secret: Secret, policy: Policy, limits: &Limits, audit: &AuditLog, approvals: &Approvals, opt: &SignerOptions) -> Result<Self,Error> {
+        let Policy { contracts, functions, ethtokens, approval, hook } = policy;
         let signer = crypto::Signer::new(secret)?;
         let address = signer.address();
-        let mut contracts = Contracts::new(config.contracts);
-        if let Some(addr) = opt.default_contract {
-            contracts.set_default(addr)?;
+        let contracts = Contracts::new(contracts);
+        for function in functions.iter() {
+            function.validate()?;
         }
-        let functions = config.functions.into_iter().collect();
-        let ethtokens = config.ethtokens.into_iter().collect();
+        let functions: Functions = functions.into_iter().collect();
+        if let Some(approval) = approval.as_ref() {
+            approval.validate(&functions)?;
+        }
+        let ethtokens = ethtokens.into_iter().collect();
+        let limits = limits.clone();
+        let audit = audit.clone();
+        let approvals = approvals.clone();
+        let hook = match hook {
+            Some(config) => Some(Hook::new(config)?),
+            None => None,
+        };
         let allow_creation = opt.allow_contract_creation;
         let allow_raw = opt.allow_raw_txns;
-        info!("Initializing signer {}",address);
+        info!("Initializing signer key `{}` ({})",name,address);
         if contracts.is_empty() {
-            warn!("No contract whitelist specified; allowing all targets");
+            warn!("No contract whitelist specified for {}; allowing all targets",address);
         }
         if let Some(addr) = contracts.get_default() {
-            info!("Configured with default contract {}",addr);
+            info!("Configured {} with default contract {}",address,addr);
         }
         if allow_raw {
             warn!("Allowing raw transactions for signer {}",address);
         }
-        Ok(Self { address, signer, contracts, functions, ethtokens, allow_creation, allow_raw })
+        if let Some(approval) = approval.as_ref() {
+            info!("Requiring {} of {} approvals for high-value requests to {}",approval.quorum,approval.approvers.len(),address);
+        }
+        if hook.is_some() && allow_raw {
+            warn!("Raw transactions are not inspected by the pre-sign hook; refusing them for {}",address);
+        }
+        if functions.has_limits() && allow_raw {
+            warn!("Raw transactions are not subject to call limits; refusing them for {}",address);
+        }
+        Ok(Self { name, address, signer, contracts, functions, ethtokens, limits, audit, approval, approvals, hook, allow_creation, allow_raw })
     }
 
-    pub fn serve(&self, request: Request) -> rpc::Result {
+    fn serve(&self, peer: Option<SocketAddr>, request: Request, hooked: bool) -> rpc::Result {
         debug_assert!(self.address == self.signer.address());
         match request {
             Request::SignRawTx(body) => {
-                let bytes = self.sign_raw_tx(body)?;
-                Ok(bytes.into())
+                match self.sign_raw_tx(body.clone())? {
+                    Signed::Tx(bytes) => {
+                        self.audit.record(self.address,peer,&Request::SignRawTx(body),Outcome::tx(&bytes))?;
+                        Ok(bytes.into())
+                    },
+                    Signed::Pending(pending) => Ok(pending.into()),
+                }
             },
             Request::SignTxCall(tx_call) => {
-                let bytes = self.sign_tx_call(tx_call)?;
-                Ok(bytes.into())
+                if !hooked && self.requires_hook(&tx_call) {
+                    let msg = format!("Call to `{}` requires pre-sign hook (not consulted)",tx_call.call.name);
+                    return Err(rpc::Error::message(msg));
+                }
+                match self.sign_tx_call(tx_call.clone())? {
+                    Signed::Tx(bytes) => {
+                        self.audit.record(self.address,peer,&Request::SignTxCall(tx_call),Outcome::tx(&bytes))?;
+                        Ok(bytes.into())
+                    },
+                    Signed::Pending(pending) => Ok(pending.into()),
+                }
             },
             Request::EncodeCall(call) => {
                 info!("{} encoding {:?}",self.address,call);
@@ -101,9 +306,10 @@ impl Signer {
             },
             Request::SignToken(req) => {
                 info!("{} signing ethtoken {:?}",self.address,req);
-                let encoded = self.ethtokens.try_encode(req)?;
+                let encoded = self.ethtokens.try_encode(req.clone())?;
                 let hash = crypto::keccak(encoded.as_slice());
                 let sig = self.signer.sign(&hash);
+                self.audit.record(self.address,peer,&Request::SignToken(req),Outcome::Sig(sig))?;
                 Ok(sig.into())
             },
             Request::EncodeToken(req) => {
@@ -119,6 +325,58 @@ impl Signer {
                 info!("{} serving {:?}",self.address,request);
                 Ok(self.address.into())
             },
+            Request::Approve(approval) => {
+                // pending requests are shared by all keys, and identify their own key.  if the
+                // record cannot be written, the audit log refuses all further signing, so the
+                // (already counted) approval can never take effect unrecorded.
+                let (approver,pending) = self.approvals.approve(approval.clone())?;
+                self.audit.record(pending.key,peer,&Request::Approve(approval),Outcome::Approval(approver))?;
+                Ok(pending.into())
+            },
+            Request::GetPending { } => {
+                info!("{} serving {:?}",self.address,request);
+                Ok(self.approvals.pending(Some(self.address)).into())
+            },
+        }
+    }
+
+
+    fn requires_hook(&self, tx_call: &rpc::TxCall) -> bool {
+        self.hook.as_ref().map(|hook| hook.applies_to(&tx_call.call.name)).unwrap_or(false)
+    }
+
+
+    /// Check `request` against local policy, and build the pre-sign hook query
+    /// for it (if required).
+    ///
+    fn pre_sign(&self, peer: Option<SocketAddr>, request: &Request) -> Result<Option<impl Future<Item=(),Error=hooks::Error> + Send>,rpc::Error> {
+        match (self.hook.as_ref(), request) {
+            (Some(hook), Request::SignTxCall(tx_call)) if hook.applies_to(&tx_call.call.name) => {
+                let contract = self.resolve_target(tx_call.to)?;
+                // the hook is only consulted about calls which local policy permits
+                self.functions.try_encode(tx_call.call.clone())?;
+                let call = PreSign {
+                    key: self.address,
+                    contract,
+                    function: tx_call.call.name.clone(),
+                    inputs: tx_call.call.inputs.clone(),
+                    value: tx_call.value,
+                    peer,
+                };
+                info!("{} consulting pre-sign hook about call to `{}`",self.address,call.function);
+                Ok(Some(hook.check(&call)))
+            },
+            _other => Ok(None),
+        }
+    }
+
+
+    /// Check whether `request` has the approval it requires (if any).
+    ///
+    fn check_approval(&self, request: Request) -> Result<Status,rpc::Error> {
+        match self.approval.as_ref() {
+            Some(config) => Ok(self.approvals.check(self.address,config,&request)?),
+            None => Ok(Status::NotRequired),
         }
     }
 
@@ -133,22 +391,46 @@ impl Signer {
     }
 
 
-    fn sign_tx_call(&self, tx_call: rpc::TxCall) -> Result<Bytes,rpc::Error> {
-        info!("{} serving {:?}",self.address,tx_call);
-        let to_addr = if let Some(addr) = tx_call.to {
+    /// Get the (whitelisted) target of a call, falling back to the default contract.
+    ///
+    fn resolve_target(&self, to: Option<Address>) -> Result<Address,rpc::Error> {
+        if let Some(addr) = to {
             self.assert_whitelisted(addr)?;
-            addr
+            Ok(addr)
         } else if let Some(addr) = self.contracts.get_default() {
             debug_assert!(self.assert_whitelisted(addr).is_ok());
-            addr
+            Ok(addr)
         } else {
             let msg = "Missing required field `to` (no default configured)";
-            return Err(rpc::Error::message(msg));
-        };
+            Err(rpc::Error::message(msg))
+        }
+    }
+
+
+    fn sign_tx_call(&self, tx_call: rpc::TxCall) -> Result<Signed,rpc::Error> {
+        info!("{} serving {:?}",self.address,tx_call);
+        let to_addr = self.resolve_target(tx_call.to)?;
         let payable = self.functions.get(&tx_call.call.name)
             .map(|f| f.payable).unwrap_or(false);
         if payable || tx_call.value  == 0u32.into() {
-            let calldata = self.functions.try_encode(tx_call.call)?;
+            let calldata = self.functions.try_encode(tx_call.call.clone())?;
+            let approved = match self.check_approval(Request::SignTxCall(tx_call.clone()))? {
+                Status::Pending(pending) => return Ok(Signed::Pending(pending)),
+                Status::Approved(approved) => Some(approved),
+                Status::NotRequired => None,
+            };
+            if let Some(config) = self.functions.get_config(&tx_call.call.name) {
+                if let Err(err) = self.limits.consume(self.address,config,&tx_call.call) {
+                    // nothing was signed, so the approval still stands
+                    if let Some(approved) = approved {
+                        self.approvals.restore(approved);
+                    }
+                    return Err(err.into());
+                }
+            }
+            if let Some(approved) = approved.as_ref() {
+                info!("{} signing approved request {}",self.address,approved.hash());
+            }
             let tx = Transaction::builder()
                 .nonce(tx_call.nonce)
                 .gas_price(tx_call.gas_price)
@@ -158,20 +440,31 @@ impl Signer {
                 .data(calldata)
                 .sign(&self.signer);
             let encoded = tx.rlp();
-            Ok(encoded)
+            Ok(Signed::Tx(encoded))
         } else {
             let msg = format!("Nonzero value in call to non-payable function ({})",tx_call.value);
             Err(rpc::Error::message(msg))
         }
     } 
 
-    fn sign_raw_tx(&self, body: rpc::Transaction) -> Result<Bytes,rpc::Error> {
-        if self.allow_raw {
+    fn sign_raw_tx(&self, body: rpc::Transaction) -> Result<Signed,rpc::Error> {
+        if self.allow_raw && self.hook.is_some() {
+            warn!("{} denying raw {:?} (pre-sign hook configured)",self.address,body);
+            Err(rpc::Error::message("raw tx signing disabled by pre-sign hook"))
+        } else if self.allow_raw && self.functions.has_limits() {
+            warn!("{} denying raw {:?} (call limits configured)",self.address,body);
+            Err(rpc::Error::message("raw tx signing disabled by call limits"))
+        } else if self.allow_raw {
             if let Some(to_addr) = body.to {
                 self.assert_whitelisted(to_addr)?;
             } else if !self.allow_creation {
                 return Err(rpc::Error::message("contract-creation signing disabled"));
             }
+            match self.check_approval(Request::SignRawTx(body.clone()))? {
+                Status::Pending(pending) => return Ok(Signed::Pending(pending)),
+                Status::Approved(approved) => info!("{} signing approved request {}",self.address,approved.hash()),
+                Status::NotRequired => { },
+            }
             info!("{} signing raw {:?}",self.address,body);
             let tx = Transaction::builder()
                 .nonce(body.nonce)
@@ -182,7 +475,7 @@ impl Signer {
                 .data(body.data)
                 .sign(&self.signer);
             let encoded = tx.rlp();
-            Ok(encoded)
+            Ok(Signed::Tx(encoded))
         } else {
             warn!("{} denying raw {:?}",self.address,body);
             Err(rpc::Error::message("raw tx signing disabled"))
@@ -190,3 +483,174 @@ impl Signer {
     }
 }
 
+
+
+#[cfg(test)]
+mod test {
+    use {Signer,Error};
+    use options::SignerOptions;
+    use functions::Call;
+    use crypto::{self,Address};
+    use rpc::{self,Request,KeyedRequest,KeyId,KeyInfo};
+    use structopt::StructOpt;
+    use std::{env,fs,process};
+
+    const VAULT_TARGET: &str = "0x00000000000000000000000000000000000000aa";
+
+    const USER_TARGET: &str = "0x00000000000000000000000000000000000000bb";
+
+
+    /// Generate a secret (exported via env var `SIGNER_TEST_<name>`), returning its
+    /// encoding (as printed by `keygen`) & address.
+    fn secret(name: &str) -> (String,Address) {
+        let secret = crypto::keygen();
+        let encoded = format!("{:?}",secret);
+        let address = crypto::Signer::new(secret).unwrap().address();
+        env::set_var(format!("SIGNER_TEST_{}",name),&encoded);
+        (encoded,address)
+    }
+
+
+    /// A `[[key]]` table loading its secret from `SIGNER_TEST_<var>`.
+    fn key(name: &str, var: &str, target: &str, function: &str) -> String {
+        format!("[[key]]\nname = \"{}\"\nsecret-var = \"SIGNER_TEST_{}\"\ncontract-whitelist = [\"{}\"]\n\n\
+            [[key.function-config]]\nname = \"{}\"\ninputs = []\n\n",name,var,target,function)
+    }
+
+
+    fn signer(name: &str, config: &str, args: &[&str]) -> Result<Signer,Error> {
+        let path = env::temp_dir().join(format!("signer-test-{}-{}.toml",name,process::id()));
+        fs::write(&path,config).unwrap();
+        let mut argv = vec!["signer","--signer-config",path.to_str().unwrap()];
+        argv.extend_from_slice(args);
+        let signer = Signer::from_options(&SignerOptions::from_iter(argv));
+        fs::remove_file(&path).unwrap();
+        signer
+    }
+
+
+    fn keyed(key: impl Into<Option<KeyId>>, request: Request) -> KeyedRequest {
+        KeyedRequest::new(key,request)
+    }
+
+
+    fn name(name: &str) -> KeyId { KeyId::Name(name.into()) }
+
+
+    fn raw_tx(to: &str) -> Request {
+        Request::SignRawTx(rpc::Transaction {
+            nonce: 0u32.into(),
+            gas_price: 1000u32.into(),
+            gas_limit: 21000u32.into(),
+            to: Some(to.parse().unwrap()),
+            value: 0u32.into(),
+            data: Default::default(),
+        })
+    }
+
+
+    fn encode_call(function: &str) -> Request {
+        Request::EncodeCall(Call { name: function.into(), inputs: Default::default() })
+    }
+
+
+    fn error(rslt: rpc::Result) -> String {
+        match rslt {
+            Err(err) => err.to_string(),
+            Ok(rsp) => panic!("expected error, got {:?}",rsp),
+        }
+    }
+
+
+    #[test]
+    fn select_keys() {
+        let (_,vault) = secret("SELECT_VAULT");
+        let (_,user) = secret("SELECT_USER");
+        let config = key("vault","SELECT_VAULT",VAULT_TARGET,"hello") + &key("user","SELECT_USER",USER_TARGET,"world");
+        let signer = signer("select",&config,&[]).unwrap();
+        // the first declared key is the default
+        assert_eq!(signer.address(),vault);
+        let get_address = |key: Option<KeyId>| signer.serve(keyed(key,Request::GetAddress { }));
+        assert_eq!(get_address(Some(name("vault"))).unwrap().to_addr().unwrap(),vault);
+        assert_eq!(get_address(Some(name("user"))).unwrap().to_addr().unwrap(),user);
+        assert_eq!(get_address(Some(vault.into())).unwrap().to_addr().unwrap(),vault);
+        assert_eq!(get_address(Some(user.into())).unwrap().to_addr().unwrap(),user);
+        // with several keys, `get-address` without a key lists them all
+        let expect = vec![
+            KeyInfo { name: "vault".into(), address: vault },
+            KeyInfo { name: "user".into(), address: user },
+        ];
+        assert_eq!(get_address(None).unwrap().to_keys().unwrap(),expect);
+        assert_eq!(signer.keys(),expect);
+        // other requests without a key are served by the default key
+        let contracts = signer.serve(keyed(None,Request::GetContracts { })).unwrap().to_contracts().unwrap();
+        assert!(contracts.is_allowed(VAULT_TARGET.parse().unwrap()));
+        // unknown keys are refused, by name or address
+        assert_eq!(error(get_address(Some(name("treasury")))),"unknown signer key `treasury`");
+        let unknown: Address = "0x00000000000000000000000000000000000000cc".parse().unwrap();
+        assert!(error(get_address(Some(unknown.into()))).starts_with("unknown signer key"));
+        assert!(error(signer.serve(keyed(name("Vault"),encode_call("hello")))).starts_with("unknown signer key"));
+    }
+
+
+    #[test]
+    fn single_key() {
+        let (secret,address) = secret("SINGLE");
+        let signer = signer("single","",&["--secret-key",&secret]).unwrap();
+        assert_eq!(signer.keys(),vec![KeyInfo { name: ::DEFAULT_KEY.into(), address }]);
+        // a lone key answers `get-address` with its address, rather than a list
+        let rsp = signer.serve(keyed(None,Request::GetAddress { })).unwrap();
+        assert_eq!(rsp.to_addr().unwrap(),address);
+        let rsp = signer.serve(keyed(name(::DEFAULT_KEY),Request::GetAddress { })).unwrap();
+        assert_eq!(rsp.to_addr().unwrap(),address);
+    }
+
+
+    #[test]
+    fn duplicate_keys() {
+        let (cli_secret,_) = secret("DUP_A");
+        secret("DUP_B");
+        let setup_error = |rslt: Result<Signer,Error>| match rslt {
+            Err(err) => err.to_string(),
+            Ok(signer) => panic!("expected setup error, got {:?}",signer.keys()),
+        };
+        // duplicate names
+        let config = key("vault","DUP_A",VAULT_TARGET,"hello") + &key("vault","DUP_B",USER_TARGET,"world");
+        assert!(setup_error(signer("dup-name",&config,&[])).starts_with("duplicate signer key `vault`"));
+        // duplicate addresses (the same secret under two names)
+        let config = key("vault","DUP_A",VAULT_TARGET,"hello") + &key("user","DUP_A",USER_TARGET,"world");
+        assert!(setup_error(signer("dup-addr",&config,&[])).starts_with("duplicate signer key `user`"));
+        // the command-line key is named `default`, and may not be redeclared
+        let config = key(::DEFAULT_KEY,"DUP_B",VAULT_TARGET,"hello");
+        assert!(setup_error(signer("dup-default",&config,&["--secret-key",&cli_secret])).starts_with("duplicate signer key `default`"));
+        let config = key("vault","DUP_A",VAULT_TARGET,"hello");
+        assert!(setup_error(signer("dup-cli",&config,&["--secret-key",&cli_secret])).starts_with("duplicate signer key `vault`"));
+        // distinct keys are fine
+        let config = key("vault","DUP_B",VAULT_TARGET,"hello");
+        assert_eq!(signer("distinct",&config,&["--secret-key",&cli_secret]).unwrap().keys().len(),2);
+    }
+
+
+    #[test]
+    fn per_key_policy() {
+        secret("POLICY_VAULT");
+        secret("POLICY_USER");
+        let config = key("vault","POLICY_VAULT",VAULT_TARGET,"hello") + &key("user","POLICY_USER",USER_TARGET,"world");
+        let signer = signer("policy",&config,&["--allow-raw-txns"]).unwrap();
+        // contract whitelists
+        let contracts = |key| signer.serve(keyed(name(key),Request::GetContracts { })).unwrap().to_contracts().unwrap();
+        assert!(contracts("vault").is_allowed(VAULT_TARGET.parse().unwrap()));
+        assert!(!contracts("vault").is_allowed(USER_TARGET.parse().unwrap()));
+        assert!(contracts("user").is_allowed(USER_TARGET.parse().unwrap()));
+        assert!(!contracts("user").is_allowed(VAULT_TARGET.parse().unwrap()));
+        assert!(signer.serve(keyed(name("vault"),raw_tx(VAULT_TARGET))).unwrap().to_bytes().is_ok());
+        assert!(signer.serve(keyed(name("user"),raw_tx(USER_TARGET))).unwrap().to_bytes().is_ok());
+        assert!(error(signer.serve(keyed(name("vault"),raw_tx(USER_TARGET)))).contains("not in contract whitelist"));
+        assert!(error(signer.serve(keyed(name("user"),raw_tx(VAULT_TARGET)))).contains("not in contract whitelist"));
+        // function configs
+        assert!(signer.serve(keyed(name("vault"),encode_call("hello"))).is_ok());
+        assert!(signer.serve(keyed(name("user"),encode_call("world"))).is_ok());
+        assert!(signer.serve(keyed(name("vault"),encode_call("world"))).is_err());
+        assert!(signer.serve(keyed(name("user"),encode_call("hello"))).is_err());
+    }
+}
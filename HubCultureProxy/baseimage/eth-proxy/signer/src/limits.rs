@@ -0,0 +1,479 @@
+//! Rolling limits on signed function calls.
+//!
+//! Limits are declared per function, and cap either the number of calls or
+//! the total of a uint input within a rolling window:
+//!
+//! ```toml
+//! [[function-config.limits]]
+//! window = "minute"
+//! max = "10"
+//!
+//! [[function-config.limits]]
+//! window = "day"
+//! sum = "value"
+//! per = "to"
+//! max = "1000000000000000000"
+//! ```
+//!
+//! Usage is shared by all clones of a `Limits` instance, and is persisted to
+//! a local file (if configured) so that limits survive restarts.
+//!
+//! Raw transactions carry opaque calldata, and so cannot be checked against
+//! limits; keys with any limits refuse raw transactions even if
+//! `--allow-raw-txns` is set.
+//!
+use ethrpc::abi::{Function,Value,Token};
+use ethrpc::util::bufmath;
+use functions::{Call,FunctionConfig};
+use crypto::Address;
+use types::U256;
+use util::{self,unix_time};
+use serde_json;
+use std::collections::{HashMap,VecDeque};
+use std::path::{Path,PathBuf};
+use std::sync::{Arc,Mutex};
+use std::cmp::Ordering;
+use std::{fmt,error,fs,io};
+
+
+/// Length of a limit's rolling window.
+///
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Hash,Serialize,Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Window {
+    Minute,
+    Hour,
+    Day,
+}
+
+
+impl Window {
+
+    pub fn as_secs(&self) -> u64 {
+        match self {
+            Window::Minute => 60,
+            Window::Hour => 60 * 60,
+            Window::Day => 60 * 60 * 24,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Window::Minute => "minute",
+            Window::Hour => "hour",
+            Window::Day => "day",
+        }
+    }
+}
+
+
+impl fmt::Display for Window {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+
+/// A rolling limit on calls to a function.
+///
+/// Caps the number of calls per window, or the total of the uint input named
+/// by `sum`.  If `per` names an address input, usage is tracked separately for
+/// each address.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Limit {
+    pub window: Window,
+    pub max: Value,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub sum: Option<String>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub per: Option<String>,
+}
+
+
+impl Limit {
+
+    /// Check that this limit refers to inputs of a compatible type.
+    ///
+    pub(crate) fn check_kinds(&self, function: &Function) -> Option<&'static str> {
+        let kind_of = |input: &str| {
+            function.iter_inputs().find(|(name,_)| *name == input).map(|(_,kind)| kind)
+        };
+        if self.max.try_cast(Token::Uint).is_err() {
+            return Some("`max` must be a uint");
+        }
+        if let Some(input) = self.sum.as_ref() {
+            match kind_of(input) {
+                Some(Token::Uint) | Some(Token::Uint8) => { },
+                Some(_) => return Some("`sum` requires a uint input"),
+                None => return Some("`sum` refers to nonexistent input"),
+            }
+        }
+        if let Some(input) = self.per.as_ref() {
+            match kind_of(input) {
+                Some(Token::Addr) => { },
+                Some(_) => return Some("`per` requires an address input"),
+                None => return Some("`per` refers to nonexistent input"),
+            }
+        }
+        None
+    }
+
+    /// Identifies the usage tracked by this limit for a given signer, function & account.
+    ///
+    /// Limits are identified by what they measure rather than by position, so that usage
+    /// is preserved when the config is reordered or a `max` is changed.
+    ///
+    fn track_key(&self, signer: Address, function: &str, account: Option<Address>) -> String {
+        let measure = self.sum.as_ref().map(|input| input.as_str()).unwrap_or("calls");
+        match (self.per.as_ref(),account) {
+            (Some(per),Some(account)) => format!("{}/{}/{}/{}/{}={}",signer,function,measure,self.window,per,account),
+            _ => format!("{}/{}/{}/{}",signer,function,measure,self.window),
+        }
+    }
+
+    /// Amount consumed by `call` (one, if counting calls).
+    ///
+    fn amount(&self, call: &Call) -> U256 {
+        self.sum.as_ref()
+            .and_then(|input| call.inputs.get(input))
+            .and_then(|value| value.try_cast(Token::Uint).ok())
+            .and_then(|value| value.as_uint())
+            .unwrap_or_else(|| U256::from(if self.sum.is_some() { 0u64 } else { 1u64 }))
+    }
+
+    fn account(&self, call: &Call) -> Option<Address> {
+        self.per.as_ref()
+            .and_then(|input| call.inputs.get(input))
+            .and_then(|value| value.try_cast(Token::Addr).ok())
+            .and_then(|value| value.as_addr())
+    }
+
+    fn describe(&self) -> String {
+        let measure = match self.sum.as_ref() {
+            Some(input) => format!("total `{}`",input),
+            None => "calls".to_owned(),
+        };
+        match self.per.as_ref() {
+            Some(per) => format!("{} per {} per `{}`",measure,self.window,per),
+            None => format!("{} per {}",measure,self.window),
+        }
+    }
+}
+
+
+/// Shared, optionally persisted, usage of all limits.
+///
+#[derive(Debug,Clone)]
+pub struct Limits {
+    inner: Arc<Mutex<Tracker>>,
+}
+
+
+impl Limits {
+
+    /// Load usage from `path`, if specified (starting fresh if the file does not exist).
+    ///
+    pub fn load(path: Option<&str>) -> Result<Self,Error> {
+        let usage = match path {
+            Some(path) if Path::new(path).is_file() => {
+                let buf = fs::read_to_string(path)?;
+                serde_json::from_str(&buf).map_err(|e| Error::Persist(e.to_string()))?
+            },
+            _ => Default::default(),
+        };
+        let tracker = Tracker { path: path.map(PathBuf::from), usage };
+        Ok(Self { inner: Arc::new(Mutex::new(tracker)) })
+    }
+
+    /// Check if state is persisted across restarts.
+    ///
+    pub fn is_persistent(&self) -> bool {
+        self.lock().path.is_some()
+    }
+
+    /// Record a call against all limits of `function`, failing without recording
+    /// anything if any limit would be exceeded (or usage could not be persisted).
+    ///
+    pub fn consume(&self, signer: Address, function: &FunctionConfig, call: &Call) -> Result<(),Error> {
+        self.consume_at(unix_time(),signer,function,call)
+    }
+
+    fn consume_at(&self, now: u64, signer: Address, function: &FunctionConfig, call: &Call) -> Result<(),Error> {
+        if function.limits.is_empty() {
+            return Ok(());
+        }
+        let mut tracker = self.lock();
+        let mut pending = Vec::with_capacity(function.limits.len());
+        for limit in function.limits.iter() {
+            let account = limit.account(call);
+            let key = limit.track_key(signer,&function.function.name,account);
+            let amount = limit.amount(call);
+            let max = limit.max.as_uint().unwrap_or_else(|| U256::from(0u64));
+            let used = tracker.used(&key,now,limit.window);
+            let mut total = used;
+            let overflow = bufmath::add(&mut total,&amount);
+            if overflow || bufmath::cmp(&total,&max) == Ordering::Greater {
+                return Err(Error::Exceeded {
+                    function: function.function.name.clone(),
+                    limit: limit.describe(),
+                    account,
+                    max,
+                    used,
+                    amount,
+                });
+            }
+            pending.push((key,amount));
+        }
+        for (key,amount) in pending.iter() {
+            tracker.usage.entry(key.to_owned()).or_insert_with(Default::default)
+                .push_back(Usage { time: now, amount: *amount });
+        }
+        if let Err(err) = tracker.persist(now) {
+            for (key,_) in pending.iter() {
+                tracker.usage.get_mut(key).and_then(|entries| entries.pop_back());
+            }
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> ::std::sync::MutexGuard<Tracker> {
+        util::lock(&self.inner)
+    }
+}
+
+
+#[derive(Debug)]
+struct Tracker {
+    path: Option<PathBuf>,
+    usage: HashMap<String,VecDeque<Usage>>,
+}
+
+
+impl Tracker {
+
+    /// Total usage of `key` within the window ending at `now` (pruning older entries).
+    ///
+    fn used(&mut self, key: &str, now: u64, window: Window) -> U256 {
+        let mut total = U256::from(0u64);
+        if let Some(entries) = self.usage.get_mut(key) {
+            let start = now.saturating_sub(window.as_secs());
+            while entries.front().map(|usage| usage.time <= start).unwrap_or(false) {
+                entries.pop_front();
+            }
+            for usage in entries.iter() {
+                if bufmath::add(&mut total,&usage.amount) {
+                    // saturate; any further usage exceeds the limit
+                    total = U256::from([0xffu8;32]);
+                    break;
+                }
+            }
+        }
+        total
+    }
+
+    fn persist(&mut self, now: u64) -> Result<(),Error> {
+        // nothing outlives the longest window, so stale accounts can be dropped
+        let start = now.saturating_sub(Window::Day.as_secs());
+        for entries in self.usage.values_mut() {
+            entries.retain(|usage| usage.time > start);
+        }
+        self.usage.retain(|_,entries| !entries.is_empty());
+        if let Some(path) = self.path.as_ref() {
+            let buf = serde_json::to_vec(&self.usage).map_err(|e| Error::Persist(e.to_string()))?;
+            let tmp = path.with_extension("tmp");
+            fs::write(&tmp,&buf)?;
+            fs::rename(&tmp,path)?;
+        }
+        Ok(())
+    }
+}
+
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Serialize,Deserialize)]
+struct Usage {
+    time: u64,
+    amount: U256,
+}
+
+
+#[derive(Debug)]
+pub enum Error {
+    Exceeded {
+        function: String,
+        limit: String,
+        account: Option<Address>,
+        max: U256,
+        used: U256,
+        amount: U256,
+    },
+    Persist(String),
+    Io(io::Error),
+}
+
+
+impl From<io::Error> for Error {
+
+    fn from(err: io::Error) -> Self { Error::Io(err) }
+}
+
+
+impl fmt::Display for Error {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Exceeded { function, limit, account, max, used, amount } => {
+                write!(f,"Call to `{}` exceeds limit on {} (max {}, used {}, requested {})",
+                    function,limit,max,used,amount)?;
+                if let Some(account) = account {
+                    write!(f," for {}",account)?;
+                }
+                Ok(())
+            },
+            Error::Persist(msg) => write!(f,"Unable to persist limit state: {}",msg),
+            Error::Io(err) => write!(f,"Unable to persist limit state: {}",err),
+        }
+    }
+}
+
+
+impl error::Error for Error {
+
+    fn description(&self) -> &str {
+        match self {
+            Error::Exceeded { .. } => "call exceeds configured limit",
+            Error::Persist(_) => "unable to persist limit state",
+            Error::Io(err) => err.description(),
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match self {
+            Error::Io(err) => Some(err),
+            _other => None,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use limits::{Limits,Error};
+    use functions::{Call,FunctionConfig};
+    use ethrpc::abi::Value;
+    use crypto::Address;
+    use types::U256;
+    use toml;
+    use std::{env,fs,process};
+
+    const SIGNER: &str = "0x00000000000000000000000000000000000a11ce";
+
+    const BOB: &str = "0x0000000000000000000000000000000000000b0b";
+
+    const CAROL: &str = "0x00000000000000000000000000000000000ca201";
+
+
+    /// `transfer(to,value)`, with at most 3 calls per minute, and a total `value`
+    /// of at most 100 per `to` per hour.
+    fn transfer() -> FunctionConfig {
+        let spec = r#"name = "transfer"
+inputs = [
+    { name = "to", type = "address" },
+    { name = "value", type = "uint256" },
+]
+
+[[limits]]
+window = "minute"
+max = "3"
+
+[[limits]]
+window = "hour"
+sum = "value"
+per = "to"
+max = "100"
+"#;
+        toml::from_str(spec).unwrap()
+    }
+
+
+    fn call(to: &str, value: u64) -> Call {
+        let inputs = vec![
+            ("to".to_owned(),Value::Addr(to.parse().unwrap())),
+            ("value".to_owned(),Value::Uint(U256::from(value))),
+        ];
+        Call { name: "transfer".into(), inputs: inputs.into_iter().collect() }
+    }
+
+
+    fn signer() -> Address { SIGNER.parse().unwrap() }
+
+
+    #[test]
+    fn rolling_window() {
+        let (limits,config) = (Limits::load(None).unwrap(),transfer());
+        let start = 1_000_000;
+        for offset in 0..3 {
+            limits.consume_at(start + offset,signer(),&config,&call(BOB,1)).unwrap();
+        }
+        match limits.consume_at(start + 59,signer(),&config,&call(BOB,1)) {
+            Err(Error::Exceeded { limit, .. }) => assert_eq!(limit,"calls per minute"),
+            other => panic!("expected call limit, got {:?}",other),
+        }
+        // the first call expires once a full minute has passed
+        limits.consume_at(start + 60,signer(),&config,&call(BOB,1)).unwrap();
+        assert!(limits.consume_at(start + 60,signer(),&config,&call(BOB,1)).is_err());
+        limits.consume_at(start + 62,signer(),&config,&call(BOB,1)).unwrap();
+    }
+
+
+    #[test]
+    fn check_then_commit() {
+        let (limits,config) = (Limits::load(None).unwrap(),transfer());
+        let now = 1_000_000;
+        limits.consume_at(now,signer(),&config,&call(BOB,90)).unwrap();
+        // exceeds the hourly total for bob, so the call is not counted either...
+        match limits.consume_at(now,signer(),&config,&call(BOB,11)) {
+            Err(Error::Exceeded { limit, account, .. }) => {
+                assert_eq!(limit,"total `value` per hour per `to`");
+                assert_eq!(account,Some(BOB.parse().unwrap()));
+            },
+            other => panic!("expected sum limit, got {:?}",other),
+        }
+        // ...leaving room for two more calls this minute
+        limits.consume_at(now,signer(),&config,&call(BOB,10)).unwrap();
+        limits.consume_at(now,signer(),&config,&call(CAROL,100)).unwrap();
+        assert!(limits.consume_at(now,signer(),&config,&call(CAROL,0)).is_err());
+        // usage is tracked per signer
+        let other: Address = BOB.parse().unwrap();
+        limits.consume_at(now,other,&config,&call(BOB,100)).unwrap();
+    }
+
+
+    #[test]
+    fn persisted_usage() {
+        let path = env::temp_dir().join(format!("limits-test-{}.json",process::id()));
+        let _ = fs::remove_file(&path);
+        let path_str = path.to_str().unwrap();
+        let config = transfer();
+        let now = 1_000_000;
+        {
+            let limits = Limits::load(Some(path_str)).unwrap();
+            assert!(limits.is_persistent());
+            limits.consume_at(now,signer(),&config,&call(BOB,60)).unwrap();
+            limits.consume_at(now + 1,signer(),&config,&call(CAROL,1)).unwrap();
+        }
+        let limits = Limits::load(Some(path_str)).unwrap();
+        assert!(limits.consume_at(now + 2,signer(),&config,&call(BOB,41)).is_err());
+        limits.consume_at(now + 2,signer(),&config,&call(BOB,40)).unwrap();
+        assert!(limits.consume_at(now + 3,signer(),&config,&call(BOB,0)).is_err());
+        // usage older than the longest window is dropped when persisting
+        let limits = Limits::load(Some(path_str)).unwrap();
+        limits.consume_at(now + 90_000,signer(),&config,&call(CAROL,100)).unwrap();
+        let persisted = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(!persisted.to_lowercase().contains(&BOB[2..]));
+    }
+}
@@ -1,8 +1,13 @@
 use structopt::StructOpt;
-use config::ConfigFile;
-use crypto::{Address,Secret};
+use config::{ConfigFile,KeyConfig};
+use crypto::{self,Address,Secret};
+use keystore::{self,KeyFile,Kdf};
+use limits::Limits;
+use audit::AuditLog;
+use util;
 use error::Error;
-use rand;
+use zeroize::Zeroizing;
+use rpassword;
 use toml;
 use std::path::Path;
 use std::{env,fs};
@@ -10,7 +15,7 @@ use std::{env,fs};
 
 #[derive(Debug,Clone,StructOpt)]
 pub struct SignerOptions {
-    /// Allow raw transaction signing
+    /// Allow raw transaction signing (except by keys with a pre-sign hook or call limits)
     #[structopt(long = "allow-raw-txns")]
     pub allow_raw_txns: bool,
     /// Allow contract-creation transactions
@@ -25,15 +30,26 @@ pub struct SignerOptions {
     /// Raw hex-encoded secret key
     #[structopt(name = "secret-key", long = "secret-key")]
     secret_key: Option<Secret>,
-    /// Path to file containing secret key
+    /// Path to encrypted (v3) keystore file
+    #[structopt(name = "keystore", long = "keystore")]
+    keystore: Option<String>,
+    /// Path to file containing secret key (raw hex or keystore)
     #[structopt(name = "secret-file", long = "secret-file")]
     key_file: Option<String>,
-    /// Path to file containing secret key (lazily generated)
+    /// Path to file containing secret key (lazily generated; encrypted if a passphrase is configured)
     #[structopt(name = "secret-cache", long = "secret-cache")]
     key_cache: Option<String>,
     /// Name of env var containing secret key
     #[structopt(name = "secret-var", long = "secret-var")]
     key_var: Option<String>,
+    /// Path to append-only audit log of signing operations
+    #[structopt(name = "audit-log", long = "audit-log")]
+    audit_log: Option<String>,
+    /// Path to file in which call-limit usage is persisted
+    #[structopt(name = "limit-state", long = "limit-state")]
+    limit_state: Option<String>,
+    #[structopt(flatten)]
+    pub passphrase: PassphraseOptions,
 }
 
 
@@ -51,35 +67,165 @@ impl SignerOptions {
         }
     }
 
+    pub fn load_audit_log(&self) -> Result<AuditLog,Error> {
+        if let Some(path) = self.audit_log.as_ref() {
+            let audit = AuditLog::open(path)?;
+            Ok(audit)
+        } else {
+            Ok(AuditLog::disabled())
+        }
+    }
+
+    pub fn load_limits(&self) -> Result<Limits,Error> {
+        let limits = Limits::load(self.limit_state.as_ref().map(|path| path.as_str()))?;
+        Ok(limits)
+    }
+
     pub fn load_secret(&self) -> Result<Secret,Error> {
         if let Some(secret) = self.secret_key.as_ref() {
             Ok(*secret)
+        } else if let Some(path) = self.keystore.as_ref() {
+            let passphrase = self.passphrase.load(false)?;
+            KeyFile::load(path,passphrase.as_bytes())
         } else if let Some(path) = self.key_file.as_ref() {
-            let buf = fs::read_to_string(path)?;
-            let secret = buf.trim().parse()?;
-            Ok(secret)
+            read_secret(path,&self.passphrase)
         } else if let Some(path) = self.key_cache.as_ref() {
             if Path::new(path).is_file() {
-                let buf = fs::read_to_string(path)?;
-                let secret = buf.trim().parse()?;
-                Ok(secret)
+                read_secret(path,&self.passphrase)
             } else {
                 info!("Initializing key cache: {}",path);
-                let secret = rand::random();
-                let buf = format!("{:?}\n",secret);
-                fs::write(path,buf)?;
+                let secret = crypto::keygen();
+                if self.passphrase.is_configured() {
+                    let passphrase = self.passphrase.load(false)?;
+                    KeyFile::encrypt(&secret,passphrase.as_bytes(),Kdf::default())?.save(path)?;
+                } else {
+                    warn!("Writing plaintext key cache (no passphrase configured)");
+                    let buf = Zeroizing::new(format!("{:?}\n",secret));
+                    util::write_private(path,buf.as_bytes())?;
+                }
                 Ok(secret)
             }
         } else if let Some(var) = self.key_var.as_ref() {
-            let buf = env::var(var).map_err(|e| {
+            let buf = Zeroizing::new(env::var(var).map_err(|e| {
                 Error::message(e.to_string())
-            })?;
+            })?);
             let secret = buf.trim().parse()?;
             Ok(secret)
         } else {
             warn!("Using random ephemoral key (none specified)");
-            let secret = rand::random();
+            let secret = crypto::keygen();
             Ok(secret)
         }
     }
+
+    /// Check if a secret source was specified on the command-line.
+    ///
+    pub fn has_secret(&self) -> bool {
+        self.secret_key.is_some() || self.keystore.is_some() || self.key_file.is_some()
+            || self.key_cache.is_some() || self.key_var.is_some()
+    }
+
+    /// Load the secret of a named key from the config file.
+    ///
+    /// Keys without their own passphrase source fall back to the one
+    /// specified on the command-line.
+    ///
+    pub fn load_key_secret(&self, key: &KeyConfig) -> Result<Secret,Error> {
+        let passphrase = if key.passphrase_file.is_some() || key.passphrase_var.is_some() {
+            PassphraseOptions {
+                passphrase_file: key.passphrase_file.clone(),
+                passphrase_var: key.passphrase_var.clone(),
+            }
+        } else {
+            self.passphrase.clone()
+        };
+        if let Some(path) = key.keystore.as_ref() {
+            let passphrase = passphrase.load(false)?;
+            KeyFile::load(path,passphrase.as_bytes())
+        } else if let Some(path) = key.key_file.as_ref() {
+            read_secret(path,&passphrase)
+        } else if let Some(var) = key.key_var.as_ref() {
+            let buf = Zeroizing::new(env::var(var).map_err(|e| {
+                Error::message(format!("unable to load key `{}` from `{}`: {}",key.name,var,e))
+            })?);
+            let secret = buf.trim().parse()?;
+            Ok(secret)
+        } else {
+            let msg = format!("no secret source specified for key `{}`",key.name);
+            Err(Error::message(msg))
+        }
+    }
+}
+
+
+/// Read a secret from a file containing either raw hex or a keystore.
+///
+fn read_secret(path: &str, passphrase: &PassphraseOptions) -> Result<Secret,Error> {
+    let buf = Zeroizing::new(fs::read_to_string(path)?);
+    if keystore::is_keystore(&buf) {
+        let passphrase = passphrase.load(false)?;
+        let keyfile: KeyFile = buf.parse()?;
+        keyfile.decrypt(passphrase.as_bytes())
+    } else {
+        warn!("Loading plaintext secret from {}",path);
+        let secret = buf.trim().parse()?;
+        Ok(secret)
+    }
+}
+
+/// A passphrase, zeroized on drop.
+///
+pub type Passphrase = Zeroizing<String>;
+
+
+/// Source of the passphrase used to decrypt (or encrypt) keystores.
+///
+/// If neither a file nor an env var is specified, the passphrase is read
+/// from the controlling terminal.
+///
+#[derive(Debug,Clone,StructOpt)]
+pub struct PassphraseOptions {
+    /// Path to file containing keystore passphrase
+    #[structopt(name = "passphrase-file", long = "passphrase-file")]
+    passphrase_file: Option<String>,
+    /// Name of env var containing keystore passphrase
+    #[structopt(name = "passphrase-var", long = "passphrase-var")]
+    passphrase_var: Option<String>,
+}
+
+
+impl PassphraseOptions {
+
+    /// Check if a non-interactive passphrase source was specified.
+    ///
+    pub fn is_configured(&self) -> bool {
+        self.passphrase_file.is_some() || self.passphrase_var.is_some()
+    }
+
+    /// Load the passphrase, prompting on the terminal if no other source is
+    /// configured (twice, if `confirm` is set).
+    ///
+    /// The env var (if used) is removed from the process environment once read.
+    ///
+    pub fn load(&self, confirm: bool) -> Result<Passphrase,Error> {
+        if let Some(path) = self.passphrase_file.as_ref() {
+            let buf = Zeroizing::new(fs::read_to_string(path)?);
+            Ok(Zeroizing::new(buf.trim_end_matches(|c| c == '\n' || c == '\r').to_owned()))
+        } else if let Some(var) = self.passphrase_var.as_ref() {
+            let buf = Zeroizing::new(env::var(var).map_err(|e| {
+                Error::message(format!("unable to load passphrase from `{}`: {}",var,e))
+            })?);
+            env::remove_var(var);
+            Ok(buf)
+        } else {
+            let passphrase = Zeroizing::new(rpassword::read_password_from_tty(Some("Keystore passphrase: "))?);
+            if confirm {
+                let repeated = Zeroizing::new(rpassword::read_password_from_tty(Some("Repeat passphrase: "))?);
+                if *passphrase != *repeated {
+                    return Err(Error::message("passphrases do not match"));
+                }
+            }
+            Ok(passphrase)
+        }
+    }
 }
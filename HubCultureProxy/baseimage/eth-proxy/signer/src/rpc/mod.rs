@@ -5,10 +5,14 @@ mod request;
 mod response;
 
 pub use self::transaction::{Transaction,TxCall};
-pub use self::request::Request;
-pub use self::response::Response;
+pub use self::request::{Request,KeyedRequest,KeyId};
+pub use self::response::{Response,KeyInfo};
 use functions;
 use ethtokens;
+use limits;
+use audit;
+use approvals;
+use hooks;
 use std::{fmt,error};
 
 
@@ -19,6 +23,10 @@ pub type Result = ::std::result::Result<Response,Error>;
 wrap_errs! {
     Function => functions::Error,
     EthToken => ethtokens::Error,
+    Limit => limits::Error,
+    Audit => audit::Error,
+    Approval => approvals::Error,
+    Hook => hooks::Error,
     Msg => ErrorMsg,
 }
 
@@ -1,6 +1,12 @@
 use rpc::transaction::{Transaction,TxCall};
 use ethtokens::Request as TokenRequest;
 use functions::Call;
+use approvals::Approval;
+use proxy::auth::Restricted;
+use crypto::Address;
+use std::string::ParseError;
+use std::str::FromStr;
+use std::fmt;
 
 
 #[derive(Debug,Clone,Serialize,Deserialize)]
@@ -13,4 +19,118 @@ pub enum Request {
     EncodeCall(Call),
     GetAddress { },
     GetContracts { },
+    Approve(Approval),
+    GetPending { },
+}
+
+
+impl Restricted for Request {
+
+    fn method(&self) -> &str {
+        match self {
+            Request::SignToken(_) => "sign-token",
+            Request::EncodeToken(_) => "encode-token",
+            Request::SignRawTx(_) => "sign-raw-tx",
+            Request::SignTxCall(_) => "sign-tx-call",
+            Request::EncodeCall(_) => "encode-call",
+            Request::GetAddress { } => "get-address",
+            Request::GetContracts { } => "get-contracts",
+            Request::Approve(_) => "approve",
+            Request::GetPending { } => "get-pending",
+        }
+    }
+
+    fn function(&self) -> Option<&str> {
+        match self {
+            Request::SignTxCall(tx_call) => Some(&tx_call.call.name),
+            Request::EncodeCall(call) => Some(&call.name),
+            _other => None,
+        }
+    }
+}
+
+
+/// A request directed at a specific key (e.g. `{"key":"vault","get-address":{}}`).
+///
+/// Requests which do not specify a key are served by the default key.
+///
+#[derive(Debug,Clone,Serialize,Deserialize)]
+pub struct KeyedRequest {
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub key: Option<KeyId>,
+    #[serde(flatten)]
+    pub request: Request,
+}
+
+
+impl KeyedRequest {
+
+    pub fn new(key: impl Into<Option<KeyId>>, request: Request) -> Self {
+        Self { key: key.into(), request }
+    }
+}
+
+
+impl Restricted for KeyedRequest {
+
+    fn method(&self) -> &str { self.request.method() }
+
+    fn function(&self) -> Option<&str> { self.request.function() }
+
+    fn key(&self) -> Option<String> { self.key.as_ref().map(ToString::to_string) }
+}
+
+
+impl From<Request> for KeyedRequest {
+
+    fn from(request: Request) -> Self {
+        Self { key: None, request }
+    }
+}
+
+
+/// Identifies a key by address or by name.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(untagged)]
+pub enum KeyId {
+    Addr(Address),
+    Name(String),
+}
+
+
+impl fmt::Display for KeyId {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyId::Addr(addr) => write!(f,"{}",addr),
+            KeyId::Name(name) => f.write_str(name),
+        }
+    }
+}
+
+
+impl FromStr for KeyId {
+
+    type Err = ParseError;
+
+    /// Parse as an address if possible, falling back to a key name.
+    ///
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        match s.parse() {
+            Ok(addr) => Ok(KeyId::Addr(addr)),
+            Err(_) => Ok(KeyId::Name(s.to_owned())),
+        }
+    }
+}
+
+
+impl From<Address> for KeyId {
+
+    fn from(addr: Address) -> Self { KeyId::Addr(addr) }
+}
+
+impl From<String> for KeyId {
+
+    fn from(name: String) -> Self { KeyId::Name(name) }
 }
@@ -1,15 +1,29 @@
 use crypto::{Address,Signature};
 use types::{Bytes,H256};
 use contracts::Contracts;
+use approvals::PendingApproval;
 
 #[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
 #[serde(untagged)]
 pub enum Response {
+    // must precede `Contracts`, which accepts any object
+    Pending(PendingApproval),
+    PendingList(Vec<PendingApproval>),
     Contracts(Contracts),
     Addr(Address),
     Hash(H256),
     Sig(Signature),
     Bytes(Bytes),  
+    Keys(Vec<KeyInfo>),
+}
+
+
+/// Name & address of a key served by the signer.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct KeyInfo {
+    pub name: String,
+    pub address: Address,
 }
 
 
@@ -29,6 +43,27 @@ impl Response {
         }
     }
 
+    pub fn to_keys(self) -> Result<Vec<KeyInfo>,Self> {
+        match self {
+            Response::Keys(keys) => Ok(keys),
+            other => Err(other),
+        }
+    }
+
+    pub fn to_pending(self) -> Result<PendingApproval,Self> {
+        match self {
+            Response::Pending(pending) => Ok(pending),
+            other => Err(other),
+        }
+    }
+
+    pub fn to_pending_list(self) -> Result<Vec<PendingApproval>,Self> {
+        match self {
+            Response::PendingList(pending) => Ok(pending),
+            other => Err(other),
+        }
+    }
+
     pub fn to_hash(self) -> Result<H256,Self> {
         match self {
             Response::Hash(hash) => Ok(hash),
@@ -74,6 +109,27 @@ impl From<Contracts> for Response {
     }
 }
 
+impl From<Vec<KeyInfo>> for Response {
+
+    fn from(keys: Vec<KeyInfo>) -> Self {
+        Response::Keys(keys)
+    }
+}
+
+impl From<PendingApproval> for Response {
+
+    fn from(pending: PendingApproval) -> Self {
+        Response::Pending(pending)
+    }
+}
+
+impl From<Vec<PendingApproval>> for Response {
+
+    fn from(pending: Vec<PendingApproval>) -> Self {
+        Response::PendingList(pending)
+    }
+}
+
 impl From<Bytes> for Response {
 
     fn from(bytes: Bytes) -> Self {
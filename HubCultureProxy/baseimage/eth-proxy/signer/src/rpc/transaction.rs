@@ -1,6 +1,7 @@
 use functions::Call;
 use types::{U256,Bytes};
 use crypto::Address;
+use ethrpc::util::units::serde_uint;
 
 
 /// A contract-calling transaction.
@@ -8,14 +9,15 @@ use crypto::Address;
 #[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
 pub struct TxCall {
     /// Transaction nonce (required)
+    #[serde(with = "serde_uint")]
     pub nonce: U256,
 
     /// Gas prices (required)
-    #[serde(rename = "gasPrice")]
+    #[serde(rename = "gasPrice",with = "serde_uint")]
     pub gas_price: U256,
 
     /// Gas limit (default: 90000)
-    #[serde(rename = "gas",default = "default_gas_limit")]
+    #[serde(rename = "gas",default = "default_gas_limit",with = "serde_uint")]
     pub gas_limit: U256,
 
     #[serde(default,skip_serializing_if="Option::is_none")]
@@ -23,7 +25,7 @@ pub struct TxCall {
     pub to: Option<Address>,
 
     /// Transaction value in wei (default: 0)
-    #[serde(default)]
+    #[serde(default,with = "serde_uint")]
     pub value: U256,
 
     /// Description of a contract call
@@ -39,14 +41,15 @@ pub struct TxCall {
 #[derive(Hash,Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
 pub struct Transaction {
     /// Transaction nonce (required)
+    #[serde(with = "serde_uint")]
     pub nonce: U256,
 
     /// Gas prices (required)
-    #[serde(rename = "gasPrice")]
+    #[serde(rename = "gasPrice",with = "serde_uint")]
     pub gas_price: U256,
    
     /// Gas limit (default: 90000)
-    #[serde(rename = "gas",default = "default_gas_limit")]
+    #[serde(rename = "gas",default = "default_gas_limit",with = "serde_uint")]
     pub gas_limit: U256,
 
     /// Destination address (required unless contract-creation)
@@ -54,7 +57,7 @@ pub struct Transaction {
     pub to: Option<Address>,
  
     /// Transaction value in wei (default: 0)
-    #[serde(default)]
+    #[serde(default,with = "serde_uint")]
     pub value: U256,
  
     /// Transaction data (default: empty)
@@ -1,16 +1,24 @@
-use std::time::{SystemTime,UNIX_EPOCH};
+use std::fs::OpenOptions;
+use std::io::{self,Write};
+use std::path::Path;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
 
+pub use proxy::util::{lock,unix_time};
 
-/// Get current unix timestamp
-///
-/// ## Panics
+
+/// Write key material to a new file at `path`.
 ///
-/// This function panics if called prior to the unix epoch
+/// Fails if `path` already exists, rather than replacing (or following a
+/// symlink to) an existing file.  On unix, the file is created with mode
+/// `0600`, such that the key is never readable by other users.
 ///
-pub fn unix_time() -> u64 {
-    SystemTime::now().duration_since(UNIX_EPOCH)
-        .expect("Must be called after the unix epoch")
-        .as_secs()
+pub fn write_private(path: impl AsRef<Path>, buf: &[u8]) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path)?;
+    file.write_all(buf)?;
+    file.sync_all()
 }
-
-
@@ -0,0 +1,93 @@
+extern crate signer;
+
+
+use signer::keystore::{Kdf,KeyFile};
+use signer::crypto::{self,Secret};
+use std::{env,fs,process};
+
+
+/// Secret of both reference keystores (password `testpassword`).
+const SECRET: &str = "0x7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d";
+
+
+/// Reference `pbkdf2` keystore from the Web3 Secret Storage definition.
+const PBKDF2: &str = r#"{
+    "crypto" : {
+        "cipher" : "aes-128-ctr",
+        "cipherparams" : {
+            "iv" : "6087dab2f9fdbbfaddc31a909735c1e6"
+        },
+        "ciphertext" : "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+        "kdf" : "pbkdf2",
+        "kdfparams" : {
+            "c" : 262144,
+            "dklen" : 32,
+            "prf" : "hmac-sha256",
+            "salt" : "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+        },
+        "mac" : "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+    },
+    "id" : "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+    "version" : 3
+}"#;
+
+
+/// Reference `scrypt` keystore from the Web3 Secret Storage definition.
+const SCRYPT: &str = r#"{
+    "crypto" : {
+        "cipher" : "aes-128-ctr",
+        "cipherparams" : {
+            "iv" : "83dbcc02d8ccb40e466191a123791e0e"
+        },
+        "ciphertext" : "d172bf743a674da9cdad04534d56926ef8358534d458fffccd4e6ad2fbde479c",
+        "kdf" : "scrypt",
+        "kdfparams" : {
+            "dklen" : 32,
+            "n" : 262144,
+            "p" : 8,
+            "r" : 1,
+            "salt" : "ab0c7876052600dd703518d6fc3fe8984592145b591fc8fb5c6d43190334ba19"
+        },
+        "mac" : "2103ac29920d71da29f15d75b4a16dbe95cfd7ff8faea1056c33131d846e3097"
+    },
+    "id" : "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+    "version" : 3
+}"#;
+
+
+fn check_vector(json: &str) {
+    let expect: Secret = SECRET.parse().unwrap();
+    let keyfile: KeyFile = json.parse().unwrap();
+    let secret = keyfile.decrypt(b"testpassword").unwrap();
+    assert_eq!(format!("{:?}",secret),format!("{:?}",expect));
+    assert!(keyfile.decrypt(b"testpassworf").is_err());
+}
+
+
+#[test]
+fn pbkdf2_vector() { check_vector(PBKDF2); }
+
+
+#[test]
+fn scrypt_vector() { check_vector(SCRYPT); }
+
+
+#[test]
+fn save_private() {
+    let path = env::temp_dir().join(format!("keystore-test-{}.json",process::id()));
+    let _ = fs::remove_file(&path);
+    let secret = crypto::keygen();
+    let keyfile = KeyFile::encrypt(&secret,b"hunter2",Kdf::Scrypt { log_n: 10, r: 8, p: 1 }).unwrap();
+    keyfile.save(&path).unwrap();
+    // existing files are never overwritten
+    assert!(keyfile.save(&path).is_err());
+    let loaded = KeyFile::load(&path,b"hunter2");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777,0o600);
+    }
+    fs::remove_file(&path).unwrap();
+    assert_eq!(format!("{:?}",loaded.unwrap()),format!("{:?}",secret));
+}
@@ -22,6 +22,10 @@ LOGS_CALLBACK="stdout"
 
 cp -r $CONTRACT_DIR/* $PROJECT_DIR/config/contracts/
 
+# keep signer-proxy bindings in sync with the contract abi
+
+cp $CONTRACT_DIR/HubCulture.abi $PROXY_DIR/baseimage/eth-proxy/signer-proxy/contracts/
+
 # trigger primary build
 
 cd $PROJECT_DIR